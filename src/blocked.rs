@@ -0,0 +1,71 @@
+use std::{collections::HashSet, io, path::Path};
+
+use radiobrowser::ApiStation;
+
+use crate::{read_data_from_file_and_parse, write_data_to_file};
+
+pub const BLOCKED_PATH: &str = "blocked.json";
+
+/// Returns the uuids of the hidden stations, or none if nothing was hidden yet.
+pub fn load_blocked() -> HashSet<String> {
+    match read_data_from_file_and_parse(Path::new(BLOCKED_PATH)) {
+        Ok(parsed) => parsed
+            .members()
+            .filter_map(|uuid| uuid.as_str().map(String::from))
+            .collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+pub fn save_blocked(blocked: &HashSet<String>) -> io::Result<()> {
+    // Sorted so the file only changes when the list does.
+    let mut uuids = blocked.iter().collect::<Vec<_>>();
+    uuids.sort();
+    let data = serde_json::to_string(&uuids)?;
+
+    write_data_to_file(Path::new(BLOCKED_PATH), &data)
+}
+
+/// Whether the station with `uuid` was hidden. Stations without a uuid can't be.
+pub fn is_blocked(uuid: &str, blocked: &HashSet<String>) -> bool {
+    !uuid.is_empty() && blocked.contains(uuid)
+}
+
+/// `stations` without the hidden ones, in the same order.
+pub fn without_blocked(stations: &[ApiStation], blocked: &HashSet<String>) -> Vec<ApiStation> {
+    stations
+        .iter()
+        .filter(|station| !is_blocked(&station.stationuuid, blocked))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stations::local_station, tests::station};
+
+    #[test]
+    fn only_listed_uuids_are_blocked() {
+        let blocked = HashSet::from(["a".to_string(), String::new()]);
+
+        assert!(is_blocked("a", &blocked));
+        assert!(!is_blocked("b", &blocked));
+        assert!(!is_blocked("", &blocked));
+    }
+
+    #[test]
+    fn blocked_stations_are_dropped_in_order() {
+        let stations = [
+            station("a"),
+            station("b"),
+            local_station("custom", "http://c"),
+        ];
+        let blocked = HashSet::from(["a".to_string(), String::new()]);
+
+        assert_eq!(
+            without_blocked(&stations, &blocked),
+            [stations[1].clone(), stations[2].clone()]
+        );
+    }
+}
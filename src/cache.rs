@@ -0,0 +1,613 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_std::{fs as async_fs, io::WriteExt};
+use radiobrowser::ApiStation;
+use serde_json::{json, Value};
+
+use crate::{station_to_json, write_data_to_file, CACHE_PATH};
+
+pub const CACHE_META_PATH: &str = "stations.meta";
+pub const CACHE_TTL_ENV: &str = "RRADIO_CACHE_TTL_HOURS";
+
+/// The cache ttl from `RRADIO_CACHE_TTL_HOURS`, falling back to `default_hours`.
+pub fn cache_ttl(default_hours: u64) -> Duration {
+    let hours = env::var(CACHE_TTL_ENV)
+        .ok()
+        .and_then(|hours| hours.trim().parse::<u64>().ok())
+        .unwrap_or(default_hours);
+
+    Duration::from_secs(hours * 60 * 60)
+}
+
+/// Removes `path`, which is fine if it doesn't exist.
+pub fn remove_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(why) if why.kind() != io::ErrorKind::NotFound => Err(why),
+        _ => Ok(()),
+    }
+}
+
+/// Deletes the cached stations, their journal and their timestamp. A cache
+/// that is already gone counts as cleared.
+pub fn clear_cache() -> io::Result<()> {
+    clear_cache_at(Path::new(CACHE_PATH), Path::new(CACHE_META_PATH))
+}
+
+/// `clear_cache` for the cache at `path` with its timestamp at `meta_path`.
+fn clear_cache_at(path: &Path, meta_path: &Path) -> io::Result<()> {
+    remove_if_present(path)?;
+    remove_if_present(&journal_path(path))?;
+    remove_if_present(meta_path)
+}
+
+/// When the station cache was fetched, and from which mirror if known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheMeta {
+    pub fetched_at: SystemTime,
+    pub mirror: Option<String>,
+}
+
+/// Records now as the time the station cache was fetched from `mirror`. The
+/// timestamp goes on the first line, the mirror on the second.
+pub fn write_cache_meta(path: &Path, mirror: Option<&str>) -> io::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    write_data_to_file(path, &format!("{}\n{}", now, mirror.unwrap_or_default()))
+}
+
+/// Records now as the time the station cache was fetched, keeping the mirror
+/// recorded last.
+pub fn write_cache_timestamp(path: &Path) -> io::Result<()> {
+    let mirror = read_cache_meta(path).ok().and_then(|meta| meta.mirror);
+
+    write_cache_meta(path, mirror.as_deref())
+}
+
+/// Reads what `write_cache_meta` wrote. Older files hold only the timestamp.
+pub fn read_cache_meta(path: &Path) -> io::Result<CacheMeta> {
+    let data = fs::read_to_string(path)?;
+    let mut lines = data.lines().map(str::trim);
+    let secs = lines
+        .next()
+        .unwrap_or_default()
+        .parse::<u64>()
+        .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+    let mirror = lines
+        .next()
+        .filter(|mirror| !mirror.is_empty())
+        .map(String::from);
+
+    Ok(CacheMeta {
+        fetched_at: UNIX_EPOCH + Duration::from_secs(secs),
+        mirror,
+    })
+}
+
+pub fn read_cache_timestamp(path: &Path) -> io::Result<SystemTime> {
+    read_cache_meta(path).map(|meta| meta.fetched_at)
+}
+
+pub fn is_fresh(fetched_at: SystemTime, now: SystemTime, ttl: Duration) -> bool {
+    match now.duration_since(fetched_at) {
+        Ok(age) => age < ttl,
+        // A timestamp from the future means the clock moved; treat it as fresh.
+        Err(_) => true,
+    }
+}
+
+/// Whether the station cache was fetched less than `ttl` ago.
+/// A missing or unreadable timestamp counts as stale.
+pub fn cache_is_fresh(ttl: Duration) -> bool {
+    match read_cache_timestamp(Path::new(CACHE_META_PATH)) {
+        Ok(fetched_at) => is_fresh(fetched_at, SystemTime::now(), ttl),
+        Err(_) => false,
+    }
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    /// The cache didn't match the current station format and was deleted.
+    Schema(serde_json::Error),
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(why) => write!(f, "couldn't read station cache: {}", why),
+            CacheError::Schema(why) => {
+                write!(f, "discarded outdated station cache: {}", why)
+            }
+        }
+    }
+}
+
+impl Error for CacheError {}
+
+/// Reads the cached stations, with the changes journaled since they were
+/// last written in full. A cache that no longer deserializes is removed so
+/// the next fetch can replace it.
+pub async fn load_cached_stations(path: &Path) -> Result<Vec<ApiStation>, CacheError> {
+    let data = async_fs::read_to_string(path)
+        .await
+        .map_err(CacheError::Io)?;
+    match serde_json::from_str::<Vec<ApiStation>>(&data) {
+        Ok(stations) => {
+            let changes = read_cache_journal(path).await.map_err(CacheError::Io)?;
+            Ok(apply_cache_changes(stations, changes))
+        }
+        Err(why) => {
+            let _ = async_fs::remove_file(path).await;
+            let _ = async_fs::remove_file(journal_path(path)).await;
+            Err(CacheError::Schema(why))
+        }
+    }
+}
+
+/// The positions in the cache a fetch of `fetched` stations at `offset`
+/// covered. A fetch shorter than asked for reached the end of the list, so it
+/// covers every position from `offset` on.
+pub fn fetched_positions(offset: u32, fetched: usize, complete: bool) -> Range<usize> {
+    let start = offset as usize;
+    if complete {
+        start..usize::MAX
+    } else {
+        start..start.saturating_add(fetched)
+    }
+}
+
+/// Applies a fetch to the cached stations. The fetched stations come first,
+/// in the order fetched, and those whose `changeuuid` didn't change keep
+/// their cached entry. Cached stations at the `covered` positions that the
+/// fetch didn't list are gone upstream or fell out of that range, and are
+/// dropped. The rest, like pages loaded with "More", follow in their cached
+/// order.
+pub fn merge_station_cache(
+    old: Vec<ApiStation>,
+    new: Vec<ApiStation>,
+    covered: Range<usize>,
+) -> Vec<ApiStation> {
+    let fetched = new
+        .iter()
+        .map(|station| station.stationuuid.as_str())
+        .collect::<HashSet<_>>();
+    let mut cached = HashMap::new();
+    let mut unseen = vec![];
+    for (position, station) in old.into_iter().enumerate() {
+        if fetched.contains(station.stationuuid.as_str()) {
+            cached.insert(station.stationuuid.clone(), station);
+        } else if !covered.contains(&position) {
+            unseen.push(station);
+        }
+    }
+
+    new.into_iter()
+        .map(|station| match cached.remove(&station.stationuuid) {
+            Some(old) if old.changeuuid == station.changeuuid => old,
+            _ => station,
+        })
+        .chain(unseen)
+        .collect()
+}
+
+/// One change to the cached stations, as journaled next to the cache.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheChange {
+    /// A station that is new, or differs from its cached entry.
+    Upsert(Box<ApiStation>),
+    /// The uuid of a station that is gone.
+    Remove(String),
+}
+
+/// The changes turning `old` into `new`, by uuid. Moving a station doesn't
+/// count as a change.
+pub fn diff_station_cache(old: &[ApiStation], new: &[ApiStation]) -> Vec<CacheChange> {
+    let cached = old
+        .iter()
+        .map(|station| (station.stationuuid.as_str(), station))
+        .collect::<HashMap<_, _>>();
+    let kept = new
+        .iter()
+        .map(|station| station.stationuuid.as_str())
+        .collect::<HashSet<_>>();
+    let upserted = new
+        .iter()
+        .filter(|station| cached.get(station.stationuuid.as_str()) != Some(station))
+        .map(|station| CacheChange::Upsert(Box::new(station.clone())));
+    let removed = old
+        .iter()
+        .filter(|station| !kept.contains(station.stationuuid.as_str()))
+        .map(|station| CacheChange::Remove(station.stationuuid.clone()));
+
+    upserted.chain(removed).collect()
+}
+
+/// `stations` with `changes` applied in turn. An upserted station replaces
+/// its entry in place, or goes at the end if it has none.
+pub fn apply_cache_changes(
+    stations: Vec<ApiStation>,
+    changes: Vec<CacheChange>,
+) -> Vec<ApiStation> {
+    let mut stations = stations.into_iter().map(Some).collect::<Vec<_>>();
+    let mut positions = stations
+        .iter()
+        .enumerate()
+        .filter_map(|(index, station)| Some((station.as_ref()?.stationuuid.clone(), index)))
+        .collect::<HashMap<_, _>>();
+    for change in changes {
+        match change {
+            CacheChange::Upsert(station) => match positions.get(&station.stationuuid) {
+                Some(&index) => stations[index] = Some(*station),
+                None => {
+                    positions.insert(station.stationuuid.clone(), stations.len());
+                    stations.push(Some(*station));
+                }
+            },
+            CacheChange::Remove(uuid) => {
+                if let Some(index) = positions.remove(&uuid) {
+                    stations[index] = None;
+                }
+            }
+        }
+    }
+
+    stations.into_iter().flatten().collect()
+}
+
+/// Where the changes to the cache at `path` since it was last written in
+/// full are appended.
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".journal");
+
+    PathBuf::from(name)
+}
+
+/// Once the journal holds more changes than this share of the stations, the
+/// cache is written in full again.
+const JOURNAL_MAX_SHARE: usize = 4;
+
+/// Whether `journaled` changes to a cache of `stations` are enough to write
+/// it in full instead of journaling more.
+pub fn journal_is_full(journaled: usize, stations: usize) -> bool {
+    journaled > stations / JOURNAL_MAX_SHARE
+}
+
+fn change_to_json(change: &CacheChange) -> Value {
+    match change {
+        CacheChange::Upsert(station) => json!({ "upsert": station_to_json(*station.clone()) }),
+        CacheChange::Remove(uuid) => json!({ "remove": uuid }),
+    }
+}
+
+fn change_from_json(line: &str) -> Option<CacheChange> {
+    let mut value = serde_json::from_str::<Value>(line).ok()?;
+    if let Some(uuid) = value.get("remove").and_then(Value::as_str) {
+        return Some(CacheChange::Remove(uuid.to_string()));
+    }
+
+    serde_json::from_value(value.get_mut("upsert")?.take())
+        .ok()
+        .map(|station| CacheChange::Upsert(Box::new(station)))
+}
+
+/// Appends `changes` to the journal of the cache at `path`, one JSON line
+/// each.
+pub async fn append_cache_journal(path: &Path, changes: &[CacheChange]) -> io::Result<()> {
+    let lines = changes
+        .iter()
+        .map(|change| format!("{}\n", change_to_json(change)))
+        .collect::<String>();
+    let mut file = async_fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(journal_path(path))
+        .await?;
+    file.write_all(lines.as_bytes()).await?;
+
+    file.sync_all().await
+}
+
+/// The changes journaled for the cache at `path`, oldest first. A line cut
+/// short by a crash mid-append is skipped.
+pub async fn read_cache_journal(path: &Path) -> io::Result<Vec<CacheChange>> {
+    match async_fs::read_to_string(journal_path(path)).await {
+        Ok(data) => Ok(data.lines().filter_map(change_from_json).collect()),
+        Err(why) if why.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(why) => Err(why),
+    }
+}
+
+/// Writes `data` next to `path` first and renames it into place, so a crash
+/// mid-write leaves the previous file intact.
+pub async fn write_atomically(path: &Path, data: &str) -> io::Result<()> {
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = Path::new(&temp_name);
+
+    let mut file = async_fs::File::create(temp_path).await?;
+    file.write_all(data.as_bytes()).await?;
+    file.sync_all().await?;
+    async_fs::rename(temp_path, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stations::local_station, tests::temp_path};
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn station(uuid: &str, changeuuid: &str) -> ApiStation {
+        let mut station = local_station(uuid, &format!("http://{}.example/stream", uuid));
+        station.stationuuid = uuid.to_string();
+        station.changeuuid = changeuuid.to_string();
+        station
+    }
+
+    fn uuids(stations: &[ApiStation]) -> Vec<&str> {
+        stations
+            .iter()
+            .map(|station| station.stationuuid.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn merge_keeps_unchanged_entries_and_takes_changed_ones() {
+        let mut cached = station("a", "1");
+        cached.votes = 7;
+        let old = vec![cached, station("b", "1")];
+        let new = vec![station("a", "1"), station("b", "2")];
+
+        let merged = merge_station_cache(old, new, fetched_positions(0, 2, false));
+
+        assert_eq!(uuids(&merged), ["a", "b"]);
+        assert_eq!(merged[0].votes, 7);
+        assert_eq!(merged[1].changeuuid, "2");
+    }
+
+    #[test]
+    fn merge_adds_fetched_stations_and_keeps_unseen_ones_after_them() {
+        let old = vec![station("a", "1"), station("b", "1"), station("c", "1")];
+        let new = vec![station("d", "1"), station("b", "1")];
+
+        let merged = merge_station_cache(old, new, 0..0);
+
+        assert_eq!(uuids(&merged), ["d", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn complete_merge_drops_stations_the_fetch_no_longer_lists() {
+        let old = vec![station("a", "1"), station("b", "1")];
+        let new = vec![station("b", "1"), station("c", "1")];
+
+        let merged = merge_station_cache(old, new, fetched_positions(0, 2, true));
+
+        assert_eq!(uuids(&merged), ["b", "c"]);
+    }
+
+    #[test]
+    fn refetching_the_top_drops_stations_gone_from_it_and_keeps_later_pages() {
+        let old = (0..600)
+            .map(|n| station(&format!("s{}", n), "1"))
+            .collect::<Vec<_>>();
+        // The default limit of 500, with s42 deleted upstream and s500 moving up.
+        let new = (0..500)
+            .filter(|&n| n != 42)
+            .chain([500])
+            .map(|n| station(&format!("s{}", n), "1"))
+            .collect::<Vec<_>>();
+
+        let merged = merge_station_cache(old, new, fetched_positions(0, 500, false));
+
+        assert_eq!(merged.len(), 599);
+        assert!(!uuids(&merged).contains(&"s42"));
+        assert_eq!(uuids(&merged)[498..501], ["s499", "s500", "s501"]);
+        assert_eq!(uuids(&merged).last(), Some(&"s599"));
+    }
+
+    #[test]
+    fn a_complete_fetch_covers_every_position_from_its_offset() {
+        assert_eq!(fetched_positions(500, 20, false), 500..520);
+        assert_eq!(fetched_positions(500, 20, true), 500..usize::MAX);
+    }
+
+    #[test]
+    fn unchanged_merge_has_nothing_to_journal() {
+        let old = vec![station("a", "1"), station("b", "1")];
+        let merged = merge_station_cache(old.clone(), vec![station("b", "1")], 0..0);
+
+        assert!(diff_station_cache(&old, &merged).is_empty());
+    }
+
+    #[test]
+    fn diff_lists_added_changed_and_removed_stations() {
+        let old = vec![station("a", "1"), station("b", "1"), station("c", "1")];
+        let new = vec![station("c", "1"), station("b", "2"), station("d", "1")];
+
+        let changes = diff_station_cache(&old, &new);
+
+        assert_eq!(
+            changes,
+            [
+                CacheChange::Upsert(Box::new(station("b", "2"))),
+                CacheChange::Upsert(Box::new(station("d", "1"))),
+                CacheChange::Remove("a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn applying_a_diff_gives_the_new_stations() {
+        let old = vec![station("a", "1"), station("b", "1"), station("c", "1")];
+        let new = vec![station("b", "2"), station("c", "1"), station("d", "1")];
+
+        let applied = apply_cache_changes(old.clone(), diff_station_cache(&old, &new));
+
+        assert_eq!(applied, new);
+    }
+
+    #[test]
+    fn a_station_removed_and_then_upserted_comes_back_at_the_end() {
+        let old = vec![station("a", "1"), station("b", "1")];
+        let changes = vec![
+            CacheChange::Remove("a".to_string()),
+            CacheChange::Upsert(Box::new(station("a", "2"))),
+        ];
+
+        let applied = apply_cache_changes(old, changes);
+
+        assert_eq!(uuids(&applied), ["b", "a"]);
+        assert_eq!(applied[1].changeuuid, "2");
+    }
+
+    #[test]
+    fn journal_lines_read_back_as_the_changes_written() {
+        for change in [
+            CacheChange::Upsert(Box::new(station("a", "1"))),
+            CacheChange::Remove("b".to_string()),
+        ] {
+            let line = change_to_json(&change).to_string();
+            assert_eq!(change_from_json(&line), Some(change));
+        }
+        assert_eq!(change_from_json("{\"upsert\": {\"name\""), None);
+    }
+
+    #[test]
+    fn journal_sits_next_to_the_cache() {
+        assert_eq!(
+            journal_path(Path::new("stations.json")),
+            PathBuf::from("stations.json.journal")
+        );
+    }
+
+    #[test]
+    fn journal_fills_up_past_a_quarter_of_the_stations() {
+        assert!(!journal_is_full(25, 100));
+        assert!(journal_is_full(26, 100));
+        assert!(journal_is_full(1, 0));
+    }
+
+    #[test]
+    fn cache_older_than_the_ttl_is_stale() {
+        let now = SystemTime::now();
+
+        assert!(!is_fresh(now - 25 * HOUR, now, 24 * HOUR));
+        assert!(is_fresh(now - 23 * HOUR, now, 24 * HOUR));
+    }
+
+    #[test]
+    fn timestamp_from_the_future_counts_as_fresh() {
+        let now = SystemTime::now();
+
+        assert!(is_fresh(now + HOUR, now, 24 * HOUR));
+    }
+
+    #[test]
+    fn fabricated_old_timestamp_reads_back_as_stale() {
+        let path = temp_path("old.meta");
+        write_data_to_file(&path, "1000000000").unwrap();
+        let fetched_at = read_cache_timestamp(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(fetched_at, UNIX_EPOCH + Duration::from_secs(1_000_000_000));
+        assert!(!is_fresh(fetched_at, SystemTime::now(), 24 * HOUR));
+    }
+
+    fn load(path: &Path) -> Result<Vec<ApiStation>, CacheError> {
+        async_std::task::block_on(load_cached_stations(path))
+    }
+
+    #[test]
+    fn malformed_cache_is_deleted_so_the_next_load_refetches() {
+        let path = temp_path("malformed.json");
+        write_data_to_file(&path, "[{\"name\": \"Jazz FM\"").unwrap();
+
+        assert!(matches!(load(&path), Err(CacheError::Schema(_))));
+        assert!(!path.exists());
+        assert!(matches!(load(&path), Err(CacheError::Io(_))));
+    }
+
+    #[test]
+    fn cache_in_an_old_format_is_deleted_too() {
+        let path = temp_path("old-format.json");
+        write_data_to_file(&path, "[{\"name\": \"Jazz FM\", \"url\": \"http://jazz\"}]").unwrap();
+
+        assert!(matches!(load(&path), Err(CacheError::Schema(_))));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn stations_written_atomically_load_back_with_their_journal() {
+        let path = temp_path("round-trip.json");
+        let stations = [station("a", "1"), station("b", "1")];
+        let data = Value::Array(stations.iter().cloned().map(station_to_json).collect());
+        let changes = [
+            CacheChange::Remove("a".to_string()),
+            CacheChange::Upsert(Box::new(station("c", "1"))),
+        ];
+
+        let loaded = async_std::task::block_on(async {
+            write_atomically(&path, &data.to_string()).await?;
+            append_cache_journal(&path, &changes).await?;
+            load_cached_stations(&path).await.map_err(|why| match why {
+                CacheError::Io(why) => why,
+                CacheError::Schema(why) => why.into(),
+            })
+        });
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let left_temp_file = Path::new(&temp_name).exists();
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(journal_path(&path));
+
+        assert_eq!(uuids(&loaded.unwrap()), ["b", "c"]);
+        assert!(!left_temp_file);
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_previous_file() {
+        let path = temp_path("replaced.json");
+        write_data_to_file(&path, "old").unwrap();
+
+        async_std::task::block_on(write_atomically(&path, "new")).unwrap();
+        let data = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(data.unwrap(), "new");
+    }
+
+    #[test]
+    fn clearing_a_missing_cache_succeeds() {
+        let path = temp_path("never-cached.json");
+
+        assert!(clear_cache_at(&path, &temp_path("never-cached.meta")).is_ok());
+    }
+
+    #[test]
+    fn clearing_removes_the_cache_its_journal_and_timestamp() {
+        let path = temp_path("cleared.json");
+        let meta_path = temp_path("cleared.meta");
+        for file in [&path, &journal_path(&path), &meta_path] {
+            write_data_to_file(file, "").unwrap();
+        }
+
+        clear_cache_at(&path, &meta_path).unwrap();
+
+        assert!(!path.exists());
+        assert!(!journal_path(&path).exists());
+        assert!(!meta_path.exists());
+    }
+}
@@ -0,0 +1,103 @@
+pub const USAGE: &str = "\
+usage: rradio [options]
+
+options:
+    --play <stationuuid>  play a station once the list is loaded
+    --search <query>      start with this search
+    --refresh             fetch a fresh station list even if the cache is recent
+    --offline             only browse the cached stations
+    --serve               accept remote control over http on the configured port
+    --verbose             log debug messages, unless RADIO_LOG sets a level
+    --help                show this message";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CliArgs {
+    pub play: Option<String>,
+    pub search: Option<String>,
+    pub refresh: bool,
+    pub offline: bool,
+    pub serve: bool,
+    pub verbose: bool,
+    pub help: bool,
+}
+
+/// Maps the arguments after the program name to `CliArgs`.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliArgs, String> {
+    let mut cli = CliArgs::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--play" => {
+                let uuid = args.next().ok_or("--play needs a station uuid")?;
+                cli.play = Some(uuid);
+            }
+            "--search" => {
+                let query = args.next().ok_or("--search needs a query")?;
+                cli.search = Some(query);
+            }
+            "--refresh" => cli.refresh = true,
+            "--offline" => cli.offline = true,
+            "--serve" => cli.serve = true,
+            "--verbose" | "-v" => cli.verbose = true,
+            "--help" | "-h" => cli.help = true,
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(cli)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_give_the_defaults() {
+        assert_eq!(parse(&[]), Ok(CliArgs::default()));
+    }
+
+    #[test]
+    fn arguments_map_to_their_fields() {
+        assert_eq!(
+            parse(&[
+                "--play",
+                "9617a958",
+                "--search",
+                "tag:jazz",
+                "--refresh",
+                "-v"
+            ]),
+            Ok(CliArgs {
+                play: Some("9617a958".to_string()),
+                search: Some("tag:jazz".to_string()),
+                refresh: true,
+                verbose: true,
+                ..CliArgs::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--offline", "--serve", "-h"]),
+            Ok(CliArgs {
+                offline: true,
+                serve: true,
+                help: true,
+                ..CliArgs::default()
+            })
+        );
+    }
+
+    #[test]
+    fn missing_values_and_unknown_arguments_are_errors() {
+        assert!(parse(&["--play"]).is_err());
+        assert!(parse(&["--search"]).is_err());
+        assert_eq!(
+            parse(&["--loud"]),
+            Err("unknown argument: --loud".to_string())
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use std::sync::OnceLock;
+
+use radiobrowser::ApiStation;
+
+use crate::{
+    details::{format_bitrate, format_codec},
+    stations::SortKey,
+};
+
+const UNKNOWN: &str = "Unknown";
+
+/// A station field the browser can show as a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnField {
+    Name,
+    State,
+    Country,
+    Tags,
+    Bitrate,
+    Codec,
+}
+
+impl ColumnField {
+    fn parse(name: &str) -> Option<ColumnField> {
+        match name.trim().to_lowercase().as_str() {
+            "name" => Some(ColumnField::Name),
+            "state" => Some(ColumnField::State),
+            "country" => Some(ColumnField::Country),
+            "tags" => Some(ColumnField::Tags),
+            "bitrate" => Some(ColumnField::Bitrate),
+            "codec" => Some(ColumnField::Codec),
+            _ => None,
+        }
+    }
+
+    /// The name on the header above this column.
+    pub fn title(&self) -> &'static str {
+        match self {
+            ColumnField::Name => "Name",
+            ColumnField::State => "State",
+            ColumnField::Country => "Country",
+            ColumnField::Tags => "Tags",
+            ColumnField::Bitrate => "Bitrate",
+            ColumnField::Codec => "Codec",
+        }
+    }
+
+    /// What clicking the header of this column sorts by, if anything.
+    pub fn sort_key(&self) -> Option<SortKey> {
+        match self {
+            ColumnField::Name => Some(SortKey::Name),
+            ColumnField::Country => Some(SortKey::Country),
+            ColumnField::Bitrate => Some(SortKey::Bitrate),
+            ColumnField::State | ColumnField::Tags | ColumnField::Codec => None,
+        }
+    }
+
+    /// The header of this column, with an arrow pointing the way the list is
+    /// sorted when `sort_order` sorts by it.
+    pub fn header_label(&self, sort_order: Option<(SortKey, bool)>) -> String {
+        match sort_order {
+            Some((key, ascending)) if self.sort_key() == Some(key) => {
+                format!("{} {}", self.title(), if ascending { "▲" } else { "▼" })
+            }
+            _ => self.title().to_string(),
+        }
+    }
+
+    /// The text `station` shows in this column. Missing state and country
+    /// read "Unknown" so the columns after them stay put. The country leads
+    /// with its flag when the station gave a country code.
+    pub fn value(&self, station: &ApiStation) -> String {
+        let or_unknown = |value: &str| match value.trim() {
+            "" => UNKNOWN.to_string(),
+            value => value.to_string(),
+        };
+        match self {
+            ColumnField::Name => (if station.name.trim().is_empty() {
+                station.url_resolved.to_ascii_lowercase()
+            } else {
+                station.name.to_ascii_lowercase()
+            })
+            .trim()
+            .to_string(),
+            ColumnField::State => or_unknown(&station.state),
+            ColumnField::Country => match country_code_to_flag(&station.countrycode) {
+                flag if flag.is_empty() => or_unknown(&station.country),
+                flag => format!("{} {}", flag, or_unknown(&station.country)),
+            },
+            ColumnField::Tags => station.tags.trim().to_string(),
+            ColumnField::Bitrate => format_bitrate(station.bitrate),
+            ColumnField::Codec => format_codec(&station.codec),
+        }
+    }
+}
+
+/// The flag emoji for an ISO 3166-1 alpha-2 `code` like `de`, made of its two
+/// regional indicators. Anything but two ASCII letters gives an empty string.
+pub fn country_code_to_flag(code: &str) -> String {
+    let code = code.trim();
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return String::new();
+    }
+
+    code.chars()
+        .filter_map(|c| char::from_u32(0x1F1E6 + (c.to_ascii_uppercase() as u32 - 'A' as u32)))
+        .collect()
+}
+
+/// A column and its share of the browser width relative to the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub field: ColumnField,
+    pub weight: u32,
+}
+
+/// Every field in an equal share, as the browser always showed them.
+pub fn default_columns() -> Vec<ColumnSpec> {
+    [
+        ColumnField::Name,
+        ColumnField::State,
+        ColumnField::Country,
+        ColumnField::Tags,
+        ColumnField::Bitrate,
+        ColumnField::Codec,
+    ]
+    .into_iter()
+    .map(|field| ColumnSpec { field, weight: 1 })
+    .collect()
+}
+
+/// Parses `name:3,bitrate:1,country:2` into columns, in that order. A field
+/// without a weight gets 1. Falls back to the defaults if any entry doesn't
+/// parse or the weights add up to 0.
+pub fn parse_columns(spec: &str) -> Vec<ColumnSpec> {
+    let columns = spec
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (field, weight) = entry.split_once(':').unwrap_or((entry, "1"));
+            Some(ColumnSpec {
+                field: ColumnField::parse(field)?,
+                weight: weight.trim().parse().ok()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default();
+
+    if columns.iter().map(|column| column.weight).sum::<u32>() == 0 {
+        default_columns()
+    } else {
+        columns
+    }
+}
+
+/// Splits `total` pixels among `columns` by weight. The heaviest column,
+/// the first of them on a tie, picks up what rounding down left over.
+pub fn column_widths(columns: &[ColumnSpec], total: i32) -> Vec<i32> {
+    let weights = columns
+        .iter()
+        .map(|column| column.weight as i64)
+        .sum::<i64>();
+    if weights == 0 {
+        return vec![];
+    }
+    let mut widths = columns
+        .iter()
+        .map(|column| (total as i64 * column.weight as i64 / weights) as i32)
+        .collect::<Vec<_>>();
+    let heaviest = columns
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, column)| (column.weight, std::cmp::Reverse(*index)))
+        .map(|(index, _)| index);
+    if let Some(heaviest) = heaviest {
+        widths[heaviest] += total - widths.iter().sum::<i32>();
+    }
+
+    widths
+}
+
+static COLUMNS: OnceLock<Vec<ColumnSpec>> = OnceLock::new();
+
+/// Sets the columns every station row is formatted with. Only the first call counts.
+pub fn set_columns(columns: Vec<ColumnSpec>) {
+    let _ = COLUMNS.set(columns);
+}
+
+/// The columns chosen at startup, or the defaults before that.
+pub fn columns() -> &'static [ColumnSpec] {
+    COLUMNS.get_or_init(default_columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stations::local_station;
+
+    fn row(station: &ApiStation) -> Vec<String> {
+        default_columns()
+            .iter()
+            .map(|column| column.field.value(station))
+            .collect()
+    }
+
+    #[test]
+    fn sorted_column_header_points_the_way_it_is_sorted() {
+        let sorted = |ascending| Some((SortKey::Country, ascending));
+
+        assert_eq!(ColumnField::Country.header_label(sorted(true)), "Country ▲");
+        assert_eq!(
+            ColumnField::Country.header_label(sorted(false)),
+            "Country ▼"
+        );
+        assert_eq!(ColumnField::Name.header_label(sorted(true)), "Name");
+        assert_eq!(ColumnField::Name.header_label(None), "Name");
+    }
+
+    #[test]
+    fn only_columns_with_a_sort_key_sort() {
+        assert_eq!(ColumnField::Bitrate.sort_key(), Some(SortKey::Bitrate));
+        assert_eq!(ColumnField::Tags.sort_key(), None);
+    }
+
+    #[test]
+    fn populated_station_shows_every_field() {
+        let mut station = local_station("Jazz FM", "http://jazz");
+        station.state = "Berlin".to_string();
+        station.country = "Germany".to_string();
+        station.countrycode = "DE".to_string();
+        station.tags = "jazz,smooth".to_string();
+        station.bitrate = 128;
+        station.codec = "MP3".to_string();
+
+        assert_eq!(
+            row(&station),
+            [
+                "jazz fm",
+                "Berlin",
+                "🇩🇪 Germany",
+                "jazz,smooth",
+                "128k",
+                "MP3"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_state_reads_unknown() {
+        let mut station = local_station("Jazz FM", "http://jazz");
+        station.state = "  ".to_string();
+        station.country = "Germany".to_string();
+
+        assert_eq!(row(&station)[1..3], ["Unknown", "Germany"]);
+    }
+
+    #[test]
+    fn empty_station_keeps_every_column_in_place() {
+        let station = local_station("", " http://JAZZ ");
+
+        assert_eq!(
+            row(&station),
+            ["http://jazz", "Unknown", "Unknown", "", "—", "?"]
+        );
+    }
+
+    fn spec(field: ColumnField, weight: u32) -> ColumnSpec {
+        ColumnSpec { field, weight }
+    }
+
+    #[test]
+    fn widths_split_the_total_by_weight() {
+        let columns = [
+            spec(ColumnField::Name, 3),
+            spec(ColumnField::Bitrate, 1),
+            spec(ColumnField::Country, 2),
+        ];
+
+        assert_eq!(column_widths(&columns, 600), [300, 100, 200]);
+    }
+
+    #[test]
+    fn heaviest_column_takes_the_rounding_leftover() {
+        let columns = [
+            spec(ColumnField::Name, 1),
+            spec(ColumnField::Tags, 2),
+            spec(ColumnField::Codec, 2),
+        ];
+
+        let widths = column_widths(&columns, 101);
+
+        assert_eq!(widths, [20, 41, 40]);
+        assert_eq!(widths.iter().sum::<i32>(), 101);
+    }
+
+    #[test]
+    fn weightless_columns_get_no_widths() {
+        assert!(column_widths(&[], 600).is_empty());
+        assert!(column_widths(&[spec(ColumnField::Name, 0)], 600).is_empty());
+    }
+
+    #[test]
+    fn column_spec_parses_in_order_with_default_weights() {
+        assert_eq!(
+            parse_columns("name:3, bitrate ,country:2"),
+            [
+                spec(ColumnField::Name, 3),
+                spec(ColumnField::Bitrate, 1),
+                spec(ColumnField::Country, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_or_weightless_spec_falls_back_to_the_defaults() {
+        assert_eq!(parse_columns("name:3,volume:1"), default_columns());
+        assert_eq!(parse_columns("name:heavy"), default_columns());
+        assert_eq!(parse_columns("name:0,tags:0"), default_columns());
+        assert_eq!(parse_columns(""), default_columns());
+    }
+
+    #[test]
+    fn country_codes_become_their_flags() {
+        assert_eq!(country_code_to_flag("DE"), "\u{1F1E9}\u{1F1EA}");
+        assert_eq!(country_code_to_flag(" at "), "\u{1F1E6}\u{1F1F9}");
+        assert_eq!(country_code_to_flag("uS"), "\u{1F1FA}\u{1F1F8}");
+    }
+
+    #[test]
+    fn anything_but_two_letters_has_no_flag() {
+        for code in ["", "D", "DEU", "D1", "Ää", "--"] {
+            assert_eq!(country_code_to_flag(code), "", "{:?}", code);
+        }
+    }
+}
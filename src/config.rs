@@ -0,0 +1,225 @@
+use std::{fs, io, path::Path};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    control::{DEFAULT_CONTROL_HOST, DEFAULT_CONTROL_PORT},
+    density::Density,
+    filter::FilterState,
+    playback::DEFAULT_NETWORK_CACHING_MS,
+    theme::Theme,
+    write_data_to_file,
+};
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+/// Startup defaults read from `config.toml`. Missing fields keep their default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The volume picked on the slider, kept as it is moved.
+    pub volume: i32,
+    pub cache_ttl_hours: u64,
+    /// How many stations a single fetch asks radiobrowser for.
+    pub fetch_limit: u32,
+    /// How long radiobrowser gets to answer each request of a fetch.
+    pub fetch_timeout_secs: u64,
+    pub theme: Theme,
+    /// How tightly the station list is packed.
+    pub density: Density,
+    /// Only ever show the cached stations and never touch the network.
+    pub offline: bool,
+    /// Check that a stream answers before handing it to vlc.
+    pub probe_streams: bool,
+    /// Never tell radiobrowser which stations are played, like setting
+    /// `RRADIO_DO_NOT_TRACK`.
+    pub do_not_track: bool,
+    /// Hide stations with TLS problems and play their https url if they have one.
+    pub prefer_secure: bool,
+    /// Browser columns and their relative widths, like `name:3,bitrate:1`.
+    pub columns: String,
+    /// The equalizer preset, one of `equalizer::PRESETS`.
+    pub equalizer: String,
+    /// How many milliseconds switching stations fades from one to the
+    /// next. 0 cuts straight over.
+    pub crossfade_ms: u64,
+    /// How many milliseconds of each stream vlc buffers. Raise it if streams
+    /// stutter on a slow connection.
+    pub network_caching_ms: u32,
+    /// Play `last_station` as soon as the stations are loaded.
+    pub autoplay_last: bool,
+    /// The uuid of the station played last.
+    pub last_station: String,
+    /// Where `--serve` listens for remote control. Only this machine can
+    /// reach the default host.
+    pub control_host: String,
+    pub control_port: u16,
+    /// Where distances are measured from, as `latitude,longitude` in
+    /// degrees. Empty leaves sorting by distance out.
+    pub home_location: String,
+    /// The search and dropdowns as they were left.
+    #[serde(flatten)]
+    pub filter: FilterState,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            volume: 80,
+            cache_ttl_hours: 24,
+            fetch_limit: 500,
+            fetch_timeout_secs: 15,
+            theme: Theme::default(),
+            density: Density::default(),
+            offline: false,
+            probe_streams: false,
+            do_not_track: false,
+            prefer_secure: false,
+            columns: "name:1,state:1,country:1,tags:1,bitrate:1,codec:1".to_string(),
+            equalizer: "Flat".to_string(),
+            crossfade_ms: 1000,
+            network_caching_ms: DEFAULT_NETWORK_CACHING_MS,
+            autoplay_last: false,
+            last_station: String::new(),
+            control_host: DEFAULT_CONTROL_HOST.to_string(),
+            control_port: DEFAULT_CONTROL_PORT,
+            home_location: String::new(),
+            filter: FilterState::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `config.toml`, using the defaults for anything missing or invalid.
+    pub fn load() -> Config {
+        fs::read_to_string(CONFIG_PATH)
+            .map(|data| Config::parse(&data))
+            .unwrap_or_default()
+    }
+
+    /// Reads the flat `key = value` toml this config is written in.
+    /// Tables and lines that don't parse are skipped, as are unknown keys.
+    /// A value of the wrong type keeps the default for its key only.
+    pub fn parse(data: &str) -> Config {
+        let Ok(Value::Object(mut fields)) = serde_json::to_value(Config::default()) else {
+            return Config::default();
+        };
+        for (key, value) in data.lines().filter_map(parse_line) {
+            let Some(default) = fields.insert(key.clone(), value) else {
+                fields.remove(&key);
+                continue;
+            };
+            if let Err(why) = serde_json::from_value::<Config>(Value::Object(fields.clone())) {
+                warn!("ignoring {} in {}: {}", key, CONFIG_PATH, why);
+                fields.insert(key, default);
+            }
+        }
+
+        serde_json::from_value(Value::Object(fields)).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let fields = match serde_json::to_value(self)? {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        };
+        let data = fields
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect::<String>();
+
+        write_data_to_file(Path::new(CONFIG_PATH), &data)
+    }
+}
+
+/// Parses `key = value` where value is a quoted string, bool or number.
+fn parse_line(line: &str) -> Option<(String, Value)> {
+    let (key, value) = line.trim().split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() || key.starts_with(['#', '[']) {
+        return None;
+    }
+    let value = value.trim();
+    let value = if value.starts_with('"') {
+        // Basic toml strings escape like json ones.
+        let mut strings = serde_json::Deserializer::from_str(value).into_iter::<String>();
+        Value::String(strings.next()?.ok()?)
+    } else {
+        let value = value.split('#').next()?.trim();
+        match value {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            number => serde_json::from_str::<serde_json::Number>(number)
+                .ok()
+                .map(Value::Number)?,
+        }
+    };
+
+    Some((key.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_config_fills_the_gaps_with_defaults() {
+        let config = Config::parse("volume = 35\ntheme = \"light\"\n");
+
+        assert_eq!(
+            config,
+            Config {
+                volume: 35,
+                theme: Theme::Light,
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn comments_tables_and_unknown_keys_are_skipped() {
+        let config = Config::parse(
+            "# rradio\n\
+             [window]\n\
+             fetch_limit = 200 # fewer\n\
+             colour = \"red\"\n\
+             not a setting\n\
+             offline = true\n",
+        );
+
+        assert_eq!(config.fetch_limit, 200);
+        assert!(config.offline);
+        assert_eq!(config.volume, Config::default().volume);
+    }
+
+    #[test]
+    fn a_value_of_the_wrong_type_only_loses_its_own_key() {
+        let config = Config::parse("volume = \"loud\"\nfetch_limit = 200\n");
+
+        assert_eq!(
+            config,
+            Config {
+                fetch_limit: 200,
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn out_of_range_and_fractional_numbers_keep_their_defaults() {
+        let config = Config::parse("fetch_limit = -5\nvolume = 80.0\noffline = true\n");
+
+        assert_eq!(config.fetch_limit, Config::default().fetch_limit);
+        assert_eq!(config.volume, Config::default().volume);
+        assert!(config.offline);
+    }
+
+    #[test]
+    fn strings_are_unescaped_like_toml_basic_strings() {
+        let config = Config::parse(r#"last_station = "a\"b\\c""#);
+
+        assert_eq!(config.last_station, r#"a"b\c"#);
+    }
+}
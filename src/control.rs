@@ -0,0 +1,143 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use log::debug;
+
+pub const DEFAULT_CONTROL_HOST: &str = "127.0.0.1";
+pub const DEFAULT_CONTROL_PORT: u16 = 8620;
+
+/// Something asked of the player over http.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlRequest {
+    /// Play the station with this uuid.
+    Play(String),
+    /// Pause, or resume if already paused.
+    Pause,
+    Stop,
+    NowPlaying,
+}
+
+/// What an http `method` on `path` asks for: `POST /play/<uuid>`,
+/// `POST /pause`, `POST /stop` or `GET /now-playing`. A query string or
+/// trailing slash is ignored.
+pub fn route(method: &str, path: &str) -> Option<ControlRequest> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+
+    match (method, segments.as_slice()) {
+        ("POST", ["play", uuid]) => Some(ControlRequest::Play(uuid.to_string())),
+        ("POST", ["pause"]) => Some(ControlRequest::Pause),
+        ("POST", ["stop"]) => Some(ControlRequest::Stop),
+        ("GET", ["now-playing"]) => Some(ControlRequest::NowPlaying),
+        _ => None,
+    }
+}
+
+/// How a request was answered: commands are accepted and carried out later,
+/// queries answer with a JSON body.
+#[derive(Debug)]
+pub enum ControlResponse {
+    Accepted,
+    Json(String),
+    Unavailable(String),
+}
+
+/// Serves the control api on `host:port` from a background thread, one
+/// connection at a time. `handle` answers every request that routes.
+pub fn serve<F>(host: &str, port: u16, handle: F) -> io::Result<thread::JoinHandle<()>>
+where
+    F: Fn(ControlRequest) -> ControlResponse + Send + 'static,
+{
+    let listener = TcpListener::bind((host, port))?;
+
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(why) = answer(stream, &handle) {
+                debug!("control request failed: {}", why);
+            }
+        }
+    }))
+}
+
+/// Reads one request off `stream` and writes the response. Headers are
+/// skipped and a body is left unread, since no endpoint takes one.
+fn answer<F: Fn(ControlRequest) -> ControlResponse>(
+    stream: TcpStream,
+    handle: &F,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let request = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => route(method, path),
+        _ => None,
+    };
+    let (status, body) = match request.map(handle) {
+        Some(ControlResponse::Accepted) => ("202 Accepted", r#"{"accepted":true}"#.to_string()),
+        Some(ControlResponse::Json(body)) => ("200 OK", body),
+        Some(ControlResponse::Unavailable(why)) => (
+            "503 Service Unavailable",
+            serde_json::json!({ "error": why }).to_string(),
+        ),
+        None => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_route_to_their_request() {
+        assert_eq!(
+            route("POST", "/play/9617a958"),
+            Some(ControlRequest::Play("9617a958".to_string()))
+        );
+        assert_eq!(route("POST", "/pause"), Some(ControlRequest::Pause));
+        assert_eq!(route("POST", "/stop"), Some(ControlRequest::Stop));
+        assert_eq!(
+            route("GET", "/now-playing"),
+            Some(ControlRequest::NowPlaying)
+        );
+    }
+
+    #[test]
+    fn query_strings_and_trailing_slashes_are_ignored() {
+        assert_eq!(route("POST", "/pause/"), Some(ControlRequest::Pause));
+        assert_eq!(
+            route("GET", "/now-playing?format=json"),
+            Some(ControlRequest::NowPlaying)
+        );
+    }
+
+    #[test]
+    fn wrong_methods_and_unknown_paths_dont_route() {
+        assert_eq!(route("GET", "/stop"), None);
+        assert_eq!(route("POST", "/now-playing"), None);
+        assert_eq!(route("POST", "/play"), None);
+        assert_eq!(route("POST", "/play/a/b"), None);
+        assert_eq!(route("POST", "/"), None);
+    }
+}
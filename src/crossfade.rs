@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use vlc::{MediaPlayer, MediaPlayerAudioEx};
+
+/// How often a crossfade moves the volumes along.
+pub const CROSSFADE_STEP: Duration = Duration::from_millis(50);
+
+/// How many steps of `CROSSFADE_STEP` a crossfade over `duration` takes, at
+/// least one.
+pub fn crossfade_steps(duration: Duration) -> u32 {
+    let steps = duration.as_millis().div_ceil(CROSSFADE_STEP.as_millis());
+
+    steps.clamp(1, u32::MAX as u128) as u32
+}
+
+/// The volumes of the old and the new player after `step` of `steps`, fading
+/// linearly from the old one at `volume` to the new one at `volume`.
+pub fn ramp_volumes(step: u32, steps: u32, volume: i32) -> (i32, i32) {
+    let steps = steps.max(1);
+    let step = step.min(steps);
+    let new = (volume as i64 * step as i64 / steps as i64) as i32;
+
+    (volume - new, new)
+}
+
+/// A player whose volume can be faded and that can be stopped, as vlc's
+/// `MediaPlayer` can.
+pub trait Fade {
+    fn set_volume(&self, volume: i32);
+    fn stop(&self);
+}
+
+impl Fade for MediaPlayer {
+    fn set_volume(&self, volume: i32) {
+        let _ = MediaPlayerAudioEx::set_volume(self, volume);
+    }
+
+    fn stop(&self) {
+        MediaPlayer::stop(self)
+    }
+}
+
+/// The player being faded out, and how far along it is.
+pub struct Crossfade<P: Fade = MediaPlayer> {
+    old: P,
+    step: u32,
+    steps: u32,
+}
+
+impl<P: Fade> Crossfade<P> {
+    /// Moves one step along, setting both volumes toward `volume`. Returns
+    /// false once the new player is at full volume and the old one stopped.
+    pub fn advance(&mut self, new: &P, volume: i32) -> bool {
+        self.step += 1;
+        let (old_volume, new_volume) = ramp_volumes(self.step, self.steps, volume);
+        self.old.set_volume(old_volume);
+        new.set_volume(new_volume);
+        if self.step < self.steps {
+            return true;
+        }
+        self.old.stop();
+
+        false
+    }
+
+    /// Stops the old player right away.
+    pub fn finish(self) {
+        self.old.stop();
+    }
+}
+
+/// Starts fading from `old` to `new`, which should start out silent, over
+/// `duration`. Each step is taken by `Crossfade::advance`, off a timer. A
+/// duration of 0 stops `old` and leaves nothing to fade.
+pub fn crossfade<P: Fade>(old: P, new: &P, duration: Duration) -> Option<Crossfade<P>> {
+    if duration.is_zero() {
+        old.stop();
+        return None;
+    }
+    new.set_volume(0);
+
+    Some(Crossfade {
+        old,
+        step: 0,
+        steps: crossfade_steps(duration),
+    })
+}
+
+/// Makes `new` the `active` player. A player still fading out from before is
+/// stopped, and the one `new` replaces fades out over `duration`, so only
+/// `new` is ever active and at most one other player is left playing.
+pub fn hand_over<P: Fade>(
+    active: &mut Option<P>,
+    fading: &mut Option<Crossfade<P>>,
+    new: P,
+    duration: Duration,
+) {
+    if let Some(earlier) = fading.take() {
+        earlier.finish();
+    }
+    if let Some(old) = active.take() {
+        *fading = crossfade(old, &new, duration);
+    }
+    *active = Some(new);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// A player that notes down what it is told in a log shared with others.
+    struct MockPlayer {
+        name: &'static str,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Fade for MockPlayer {
+        fn set_volume(&self, volume: i32) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} at {}", self.name, volume));
+        }
+
+        fn stop(&self) {
+            self.log.borrow_mut().push(format!("{} stopped", self.name));
+        }
+    }
+
+    fn players(names: &[&'static str]) -> (Vec<MockPlayer>, Rc<RefCell<Vec<String>>>) {
+        let log = Rc::new(RefCell::new(vec![]));
+        let players = names
+            .iter()
+            .map(|&name| MockPlayer {
+                name,
+                log: log.clone(),
+            })
+            .collect();
+
+        (players, log)
+    }
+
+    fn stopped(log: &RefCell<Vec<String>>) -> Vec<String> {
+        log.borrow()
+            .iter()
+            .filter(|entry| entry.ends_with("stopped"))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn only_the_newest_player_is_active() {
+        let (players, log) = players(&["a", "b", "c"]);
+        let (mut active, mut fading) = (None, None);
+
+        for player in players {
+            hand_over(&mut active, &mut fading, player, Duration::from_secs(1));
+        }
+
+        assert_eq!(active.map(|player| player.name), Some("c"));
+        assert_eq!(fading.map(|fading| fading.old.name), Some("b"));
+        assert_eq!(stopped(&log), ["a stopped"]);
+    }
+
+    #[test]
+    fn without_a_crossfade_the_replaced_player_stops_at_once() {
+        let (players, log) = players(&["a", "b"]);
+        let (mut active, mut fading) = (None, None);
+
+        for player in players {
+            hand_over(&mut active, &mut fading, player, Duration::ZERO);
+        }
+
+        assert_eq!(active.map(|player| player.name), Some("b"));
+        assert!(fading.is_none());
+        assert_eq!(stopped(&log), ["a stopped"]);
+    }
+
+    #[test]
+    fn ramp_moves_the_volume_over_linearly() {
+        let ramp = (0..=4)
+            .map(|step| ramp_volumes(step, 4, 80))
+            .collect::<Vec<_>>();
+
+        assert_eq!(ramp, [(80, 0), (60, 20), (40, 40), (20, 60), (0, 80)]);
+    }
+
+    #[test]
+    fn ramp_volumes_always_add_up_to_the_volume() {
+        for step in 0..=7 {
+            let (old, new) = ramp_volumes(step, 7, 100);
+            assert_eq!(old + new, 100);
+        }
+        assert_eq!(ramp_volumes(9, 7, 100), (0, 100));
+        assert_eq!(ramp_volumes(0, 0, 100), (100, 0));
+    }
+
+    #[test]
+    fn a_fade_takes_a_step_per_interval_rounding_up() {
+        assert_eq!(crossfade_steps(Duration::from_secs(1)), 20);
+        assert_eq!(crossfade_steps(Duration::from_millis(60)), 2);
+        assert_eq!(crossfade_steps(Duration::from_millis(1)), 1);
+    }
+
+    #[test]
+    fn advancing_fades_both_players_then_stops_the_old() {
+        let (mut players, log) = players(&["old", "new"]);
+        let new = players.pop().unwrap();
+        let old = players.pop().unwrap();
+        let mut fade = crossfade(old, &new, Duration::from_millis(100)).unwrap();
+
+        assert!(fade.advance(&new, 50));
+        assert!(!fade.advance(&new, 50));
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "new at 0",
+                "old at 25",
+                "new at 25",
+                "old at 0",
+                "new at 50",
+                "old stopped"
+            ]
+        );
+    }
+}
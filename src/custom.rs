@@ -0,0 +1,117 @@
+use std::{fs, io, path::Path};
+
+use radiobrowser::ApiStation;
+use rand::Rng;
+
+use crate::{homepage::is_web_url, station_to_json, stations::local_station, write_data_to_file};
+
+pub const CUSTOM_STATIONS_PATH: &str = "custom_stations.json";
+/// Starts the uuid of every custom station, so they never clash with
+/// radiobrowser's.
+const CUSTOM_UUID_PREFIX: &str = "custom-";
+
+/// Whether `station` was added by hand rather than fetched from radiobrowser.
+pub fn is_custom(station: &ApiStation) -> bool {
+    station.stationuuid.starts_with(CUSTOM_UUID_PREFIX)
+}
+
+/// A new custom station streaming from `url`, named after the url if `name`
+/// is blank. Only http(s) urls are accepted.
+pub fn add_custom_station(name: &str, url: &str) -> Result<ApiStation, String> {
+    add_custom_station_with(name, url, &mut rand::thread_rng())
+}
+
+/// `add_custom_station` drawing the uuid from `rng`.
+pub fn add_custom_station_with<R: Rng>(
+    name: &str,
+    url: &str,
+    rng: &mut R,
+) -> Result<ApiStation, String> {
+    let url = url.trim();
+    if !is_web_url(url) {
+        return Err(format!("not a stream url: {}", url));
+    }
+    let name = match name.trim() {
+        "" => url,
+        name => name,
+    };
+    let mut station = local_station(name, url);
+    station.stationuuid = format!("{}{:032x}", CUSTOM_UUID_PREFIX, rng.gen::<u128>());
+
+    Ok(station)
+}
+
+/// Loads `custom_stations.json`, starting with none if it is missing or unreadable.
+pub fn load_custom_stations() -> Vec<ApiStation> {
+    fs::read_to_string(CUSTOM_STATIONS_PATH)
+        .ok()
+        .and_then(|data| serde_json::from_str::<Vec<ApiStation>>(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_custom_stations(stations: &[ApiStation]) -> io::Result<()> {
+    let json_vec = stations
+        .iter()
+        .cloned()
+        .map(station_to_json)
+        .collect::<Vec<_>>();
+
+    write_data_to_file(
+        Path::new(CUSTOM_STATIONS_PATH),
+        &serde_json::Value::Array(json_vec).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn custom_station_streams_from_its_url_under_a_local_uuid() {
+        let station = add_custom_station_with(
+            "My Stream",
+            " http://radio.local/live ",
+            &mut StdRng::seed_from_u64(1),
+        )
+        .unwrap();
+
+        assert_eq!(station.name, "My Stream");
+        assert_eq!(station.url, "http://radio.local/live");
+        assert_eq!(station.url_resolved, "http://radio.local/live");
+        assert!(is_custom(&station));
+        assert_eq!(station.stationuuid.len(), CUSTOM_UUID_PREFIX.len() + 32);
+    }
+
+    #[test]
+    fn blank_name_falls_back_to_the_url() {
+        let station = add_custom_station(" ", "https://radio.local/live").unwrap();
+
+        assert_eq!(station.name, "https://radio.local/live");
+    }
+
+    #[test]
+    fn every_custom_station_gets_a_uuid_of_its_own() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let first = add_custom_station_with("a", "http://a", &mut rng).unwrap();
+        let second = add_custom_station_with("a", "http://a", &mut rng).unwrap();
+
+        assert_ne!(first.stationuuid, second.stationuuid);
+    }
+
+    #[test]
+    fn urls_other_than_http_are_refused() {
+        assert!(add_custom_station("Local", "file:///music.mp3").is_err());
+        assert!(add_custom_station("Empty", "").is_err());
+        assert!(add_custom_station("Spaced", "http://radio.local/a b").is_err());
+    }
+
+    #[test]
+    fn fetched_stations_are_not_custom() {
+        let mut station = local_station("Jazz", "http://jazz");
+        station.stationuuid = "9617a958-0601-11e8-ae97-52543be04c81".to_string();
+
+        assert!(!is_custom(&station));
+    }
+}
@@ -0,0 +1,79 @@
+use std::{io, path::Path};
+
+use chrono::{DateTime, Utc};
+use radiobrowser::ApiStation;
+use serde_json::{json, Value};
+
+use crate::{cache::CacheMeta, station_to_json, write_data_to_file};
+
+/// The stations wrapped with when and where they were fetched, for tools
+/// outside rradio. The cache itself stays a bare list.
+pub fn dataset_json(stations: &[ApiStation], meta: &CacheMeta) -> Value {
+    json!({
+        "fetched_at": DateTime::<Utc>::from(meta.fetched_at).to_rfc3339(),
+        "mirror": meta.mirror,
+        "stations": stations.iter().cloned().map(station_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Writes `stations` and `meta` to `path` as pretty-printed JSON.
+pub fn export_dataset(stations: &[ApiStation], meta: &CacheMeta, path: &Path) -> io::Result<()> {
+    let dataset = serde_json::to_string_pretty(&dataset_json(stations, meta))?;
+
+    write_data_to_file(path, &dataset)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use super::*;
+    use crate::tests::{station, temp_path};
+
+    fn fetched_from(mirror: Option<&str>) -> CacheMeta {
+        CacheMeta {
+            fetched_at: UNIX_EPOCH + Duration::from_secs(1_710_072_000),
+            mirror: mirror.map(String::from),
+        }
+    }
+
+    #[test]
+    fn the_dataset_wraps_the_stations_with_their_fetch() {
+        let stations = [station("a"), station("b")];
+
+        let dataset = dataset_json(&stations, &fetched_from(Some("de1.api.radio-browser.info")));
+
+        assert_eq!(dataset["fetched_at"], "2024-03-10T12:00:00+00:00");
+        assert_eq!(dataset["mirror"], "de1.api.radio-browser.info");
+        assert_eq!(dataset["stations"].as_array().unwrap().len(), 2);
+        assert_eq!(dataset["stations"][1]["stationuuid"], "b");
+        assert_eq!(dataset.as_object().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn an_unknown_mirror_is_null() {
+        assert_eq!(
+            dataset_json(&[], &fetched_from(None))["mirror"],
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn the_export_is_pretty_printed_json() {
+        let path = temp_path("dataset.json");
+        let stations = [station("a")];
+
+        export_dataset(&stations, &fetched_from(None), &path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(written.starts_with("{\n  "));
+        assert_eq!(
+            serde_json::from_str::<Value>(&written).unwrap(),
+            dataset_json(&stations, &fetched_from(None))
+        );
+    }
+}
@@ -0,0 +1,60 @@
+/// Keeps at most one call pending: scheduling another cancels the one before,
+/// so only the last of a quick run of changes goes through. The handle type
+/// is whatever the scheduler hands out, fltk's timeout handles in the app.
+#[derive(Debug)]
+pub struct Debouncer<H> {
+    pending: Option<H>,
+}
+
+impl<H> Default for Debouncer<H> {
+    fn default() -> Self {
+        Debouncer { pending: None }
+    }
+}
+
+impl<H> Debouncer<H> {
+    /// Cancels the pending call with `cancel` and makes the one `schedule`
+    /// returns pending instead.
+    pub fn schedule(&mut self, cancel: impl FnOnce(H), schedule: impl FnOnce() -> H) {
+        self.cancel(cancel);
+        self.pending = Some(schedule());
+    }
+
+    /// Cancels the pending call, if there is one.
+    pub fn cancel(&mut self, cancel: impl FnOnce(H)) {
+        if let Some(handle) = self.pending.take() {
+            cancel(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_schedule_cancels_the_one_before() {
+        let mut debouncer = Debouncer::default();
+        let mut cancelled = vec![];
+
+        for handle in 1..=3 {
+            debouncer.schedule(|handle| cancelled.push(handle), || handle);
+        }
+
+        assert_eq!(cancelled, [1, 2]);
+        assert_eq!(debouncer.pending, Some(3));
+    }
+
+    #[test]
+    fn cancelling_leaves_nothing_pending() {
+        let mut debouncer = Debouncer::default();
+        let mut cancelled = vec![];
+        debouncer.schedule(|_| unreachable!(), || 1);
+
+        debouncer.cancel(|handle| cancelled.push(handle));
+        debouncer.cancel(|handle| cancelled.push(handle));
+
+        assert_eq!(cancelled, [1]);
+        assert_eq!(debouncer.pending, None);
+    }
+}
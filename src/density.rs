@@ -0,0 +1,69 @@
+use fltk::{
+    browser::Browser,
+    prelude::{BrowserExt, WidgetExt},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::columns::{column_widths, columns};
+
+/// How tightly the station list is packed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+impl Density {
+    /// The font size of the station rows. fltk sizes each row to fit its text.
+    pub fn text_size(self) -> i32 {
+        match self {
+            Density::Comfortable => 14,
+            Density::Compact => 11,
+        }
+    }
+
+    pub fn toggled(self) -> Density {
+        match self {
+            Density::Comfortable => Density::Compact,
+            Density::Compact => Density::Comfortable,
+        }
+    }
+}
+
+/// Resizes the station rows for `density` and lays the columns out again.
+pub fn apply_density(browser: &mut Browser, density: Density) {
+    browser.set_text_size(density.text_size());
+    browser.set_column_widths(&column_widths(columns(), browser.w()));
+    browser.redraw();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_rows_use_a_smaller_font() {
+        assert_eq!(Density::Comfortable.text_size(), 14);
+        assert_eq!(Density::Compact.text_size(), 11);
+    }
+
+    #[test]
+    fn toggling_switches_between_the_two_densities() {
+        assert_eq!(Density::Comfortable.toggled(), Density::Compact);
+        assert_eq!(Density::Compact.toggled(), Density::Comfortable);
+    }
+
+    #[test]
+    fn density_is_saved_lowercase() {
+        assert_eq!(
+            serde_json::to_value(Density::Compact).unwrap(),
+            serde_json::json!("compact")
+        );
+        assert_eq!(
+            serde_json::from_value::<Density>(serde_json::json!("comfortable")).unwrap(),
+            Density::Comfortable
+        );
+    }
+}
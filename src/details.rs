@@ -0,0 +1,121 @@
+use radiobrowser::ApiStation;
+
+const MISSING: &str = "—";
+
+fn or_missing(value: &str) -> &str {
+    match value.trim() {
+        "" => MISSING,
+        value => value,
+    }
+}
+
+/// A bitrate in kbps as a short badge: "128k", "1.4M" from 1000 kbps up, or
+/// "—" if it isn't known.
+pub fn format_bitrate(kbps: u32) -> String {
+    match kbps {
+        0 => MISSING.to_string(),
+        1..=999 => format!("{}k", kbps),
+        _ => format!("{:.1}M", kbps as f64 / 1000.0),
+    }
+}
+
+/// A codec as a short uppercase badge, with the names radiobrowser uses for
+/// the same codec folded together and "?" if it isn't known.
+pub fn format_codec(codec: &str) -> String {
+    let codec = codec.trim().to_uppercase();
+    match codec.as_str() {
+        "" | "UNKNOWN" => "?".to_string(),
+        "MP3" | "MPEG" | "MPEG AUDIO" => "MP3".to_string(),
+        "AAC" | "AAC+" | "AACP" | "HE-AAC" => "AAC".to_string(),
+        "OGG" | "VORBIS" | "OGG VORBIS" => "OGG".to_string(),
+        _ => codec,
+    }
+}
+
+/// The full metadata of `station`, one `Field: value` per line, with "—" for
+/// anything radiobrowser left empty.
+pub fn render_station_details(station: &ApiStation) -> String {
+    let last_check = match station.lastcheckok {
+        1 => "ok",
+        _ => "failed",
+    };
+    let last_checked = station
+        .lastchecktime_iso8601
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| MISSING.to_string());
+
+    [
+        format!("Name: {}", or_missing(&station.name)),
+        format!("Homepage: {}", or_missing(&station.homepage)),
+        format!("Country: {}", or_missing(&station.country)),
+        format!("Language: {}", or_missing(&station.language)),
+        format!("Tags: {}", or_missing(&station.tags)),
+        format!("Bitrate: {}", format_bitrate(station.bitrate)),
+        format!("Codec: {}", format_codec(&station.codec)),
+        format!("Votes: {}", station.votes),
+        format!("Last check: {} ({})", last_check, last_checked),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stations::local_station;
+
+    #[test]
+    fn empty_fields_show_a_dash() {
+        let mut station = local_station("Jazz FM", "http://jazz");
+        station.language = "english".to_string();
+        station.votes = 12;
+
+        assert_eq!(
+            render_station_details(&station),
+            "Name: Jazz FM\n\
+             Homepage: —\n\
+             Country: —\n\
+             Language: english\n\
+             Tags: —\n\
+             Bitrate: —\n\
+             Codec: ?\n\
+             Votes: 12\n\
+             Last check: failed (—)"
+        );
+    }
+
+    #[test]
+    fn whitespace_only_fields_count_as_empty() {
+        let mut station = local_station("  ", "http://jazz");
+        station.homepage = " \t".to_string();
+
+        let details = render_station_details(&station);
+
+        assert!(details.starts_with("Name: —\nHomepage: —\n"));
+    }
+
+    #[test]
+    fn bitrate_badges_for_zero_normal_and_high_rates() {
+        assert_eq!(format_bitrate(0), "—");
+        assert_eq!(format_bitrate(1), "1k");
+        assert_eq!(format_bitrate(128), "128k");
+        assert_eq!(format_bitrate(999), "999k");
+        assert_eq!(format_bitrate(1000), "1.0M");
+        assert_eq!(format_bitrate(1411), "1.4M");
+    }
+
+    #[test]
+    fn codec_badges_fold_aliases_together() {
+        assert_eq!(format_codec("mp3"), "MP3");
+        assert_eq!(format_codec("MPEG"), "MP3");
+        assert_eq!(format_codec("aac+"), "AAC");
+        assert_eq!(format_codec("HE-AAC"), "AAC");
+        assert_eq!(format_codec("Ogg Vorbis"), "OGG");
+        assert_eq!(format_codec(" flac "), "FLAC");
+    }
+
+    #[test]
+    fn unknown_codec_reads_as_a_question_mark() {
+        assert_eq!(format_codec(""), "?");
+        assert_eq!(format_codec("UNKNOWN"), "?");
+    }
+}
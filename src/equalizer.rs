@@ -0,0 +1,80 @@
+use vlc::{sys, MediaPlayer};
+
+/// The presets offered in the dropdown, in order.
+pub const PRESETS: [&str; 5] = ["Flat", "Rock", "Pop", "Jazz", "Classical"];
+
+/// vlc ships no jazz preset, so it gets band gains of its own, in dB for
+/// vlc's ten bands from 60 Hz up to 16 kHz.
+const JAZZ_BANDS: [f32; 10] = [4.0, 3.0, 1.0, 2.0, -1.5, -1.5, 0.0, 1.0, 3.0, 4.0];
+
+/// The index of the built-in vlc preset called `name`, ignoring case. Only
+/// presets offered in the dropdown and built into vlc are known.
+pub fn preset_name_to_index(name: &str) -> Option<u32> {
+    match name.trim().to_lowercase().as_str() {
+        "flat" => Some(0),
+        "classical" => Some(1),
+        "pop" => Some(11),
+        "rock" => Some(13),
+        _ => None,
+    }
+}
+
+/// Whether the loaded libvlc knows any equalizer presets at all.
+pub fn equalizer_supported() -> bool {
+    unsafe { sys::libvlc_audio_equalizer_get_preset_count() > 0 }
+}
+
+/// Applies the preset called `name` to `player`, returning false if there is
+/// no such preset or vlc refused it.
+pub fn apply_equalizer(player: &MediaPlayer, name: &str) -> bool {
+    unsafe {
+        let equalizer = match preset_name_to_index(name) {
+            Some(index) => sys::libvlc_audio_equalizer_new_from_preset(index),
+            None if name.eq_ignore_ascii_case("jazz") => {
+                let equalizer = sys::libvlc_audio_equalizer_new();
+                if !equalizer.is_null() {
+                    for (band, amp) in JAZZ_BANDS.iter().enumerate() {
+                        sys::libvlc_audio_equalizer_set_amp_at_index(equalizer, *amp, band as u32);
+                    }
+                }
+                equalizer
+            }
+            None => return false,
+        };
+        if equalizer.is_null() {
+            return false;
+        }
+        // vlc copies the settings, so the equalizer can go right away.
+        let applied = sys::libvlc_media_player_set_equalizer(player.raw(), equalizer) == 0;
+        sys::libvlc_audio_equalizer_release(equalizer);
+
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_presets_map_to_their_vlc_index() {
+        assert_eq!(preset_name_to_index("Flat"), Some(0));
+        assert_eq!(preset_name_to_index("classical"), Some(1));
+        assert_eq!(preset_name_to_index(" POP "), Some(11));
+        assert_eq!(preset_name_to_index("Rock"), Some(13));
+    }
+
+    #[test]
+    fn presets_vlc_lacks_or_doesnt_know_have_no_index() {
+        assert_eq!(preset_name_to_index("Jazz"), None);
+        assert_eq!(preset_name_to_index("Bass boost"), None);
+        assert_eq!(preset_name_to_index(""), None);
+    }
+
+    #[test]
+    fn every_offered_preset_but_jazz_is_built_in() {
+        for preset in PRESETS.iter().filter(|preset| **preset != "Jazz") {
+            assert!(preset_name_to_index(preset).is_some(), "{}", preset);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_std::task;
+use radiobrowser::ApiStation;
+
+use crate::filename::sanitize_filename;
+
+pub const FAVICON_DIR: &str = "favicons";
+
+/// Where the icon of `station` is cached under `dir`, or `None` for stations
+/// without an icon or a uuid to key it by.
+pub fn favicon_path(dir: &Path, station: &ApiStation) -> Option<PathBuf> {
+    icon_path(dir, &station.stationuuid, &station.favicon)
+}
+
+fn icon_path(dir: &Path, uuid: &str, url: &str) -> Option<PathBuf> {
+    let name = sanitize_filename(uuid);
+    if name.is_empty() || url.trim().is_empty() {
+        return None;
+    }
+
+    Some(dir.join(name))
+}
+
+/// The icon of `station` if it is already cached under `dir`.
+pub fn cached_favicon(dir: &Path, station: &ApiStation) -> Option<PathBuf> {
+    favicon_path(dir, station).filter(|path| path.is_file())
+}
+
+async fn download_image(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let is_image = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("image/"));
+    if !is_image {
+        return Err(format!("{} is not an image", url).into());
+    }
+
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// The cached icon of `station`, downloading it first if needed. Blocks, so
+/// call it from a background thread. Gives `None` if the station has no icon
+/// or it couldn't be fetched as an image.
+pub fn fetch_favicon(station: &ApiStation) -> Option<PathBuf> {
+    fetch_favicon_into(Path::new(FAVICON_DIR), station)
+}
+
+/// `fetch_favicon` with the cache under `dir`.
+fn fetch_favicon_into(dir: &Path, station: &ApiStation) -> Option<PathBuf> {
+    if let Some(path) = cached_favicon(dir, station) {
+        return Some(path);
+    }
+    task::block_on(download_favicon(
+        dir,
+        &station.stationuuid,
+        station.favicon.trim(),
+    ))
+}
+
+/// Downloads the icon at `url` into the cache under `dir` as the icon of the
+/// station with `uuid`.
+async fn download_favicon(dir: &Path, uuid: &str, url: &str) -> Option<PathBuf> {
+    let path = icon_path(dir, uuid, url)?;
+    let image = download_image(url).await.ok()?;
+    fs::create_dir_all(dir).ok()?;
+    fs::write(&path, image).ok()?;
+
+    Some(path)
+}
+
+/// How many icons are downloaded at once while prefetching.
+pub const PREFETCH_WORKERS: usize = 4;
+
+/// An icon to have in the cache before its station is selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaviconJob {
+    pub uuid: String,
+    pub url: String,
+}
+
+/// The jobs for those `stations` whose icons aren't cached under `dir` yet.
+pub fn prefetch_jobs<'a>(
+    dir: &Path,
+    stations: impl IntoIterator<Item = &'a ApiStation>,
+) -> Vec<FaviconJob> {
+    stations
+        .into_iter()
+        .filter(|station| favicon_path(dir, station).is_some_and(|path| !path.is_file()))
+        .map(|station| FaviconJob {
+            uuid: station.stationuuid.clone(),
+            url: station.favicon.trim().to_string(),
+        })
+        .collect()
+}
+
+pub type FetchFavicon =
+    Arc<dyn Fn(FaviconJob) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Fills the icon cache for the rows in view on a few async-std tasks. A new
+/// batch replaces the one before it, whose remaining icons are dropped.
+pub struct FaviconPrefetcher {
+    workers: usize,
+    fetch: FetchFavicon,
+    /// Bumped for every batch, so older workers know to stop.
+    batch: Arc<AtomicU64>,
+}
+
+impl Default for FaviconPrefetcher {
+    fn default() -> Self {
+        FaviconPrefetcher::new(
+            PREFETCH_WORKERS,
+            Arc::new(|job: FaviconJob| {
+                Box::pin(async move {
+                    download_favicon(Path::new(FAVICON_DIR), &job.uuid, &job.url).await;
+                })
+            }),
+        )
+    }
+}
+
+impl FaviconPrefetcher {
+    /// A prefetcher running `fetch` for at most `workers` jobs at once.
+    pub fn new(workers: usize, fetch: FetchFavicon) -> Self {
+        FaviconPrefetcher {
+            workers: workers.max(1),
+            fetch,
+            batch: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Starts on `jobs` in order, dropping what is left of the last batch.
+    /// Jobs already started finish. The handles end once the workers run out
+    /// of jobs or a newer batch replaces this one.
+    pub fn prefetch(&self, jobs: Vec<FaviconJob>) -> Vec<task::JoinHandle<()>> {
+        let batch = self.batch.fetch_add(1, Ordering::SeqCst) + 1;
+        let workers = self.workers.min(jobs.len());
+        let queue = Arc::new(Mutex::new(VecDeque::from(jobs)));
+
+        (0..workers)
+            .map(|_| {
+                let queue = queue.clone();
+                let current = self.batch.clone();
+                let fetch = self.fetch.clone();
+                task::spawn(async move {
+                    while current.load(Ordering::SeqCst) == batch {
+                        let Some(job) = queue.lock().ok().and_then(|mut queue| queue.pop_front())
+                        else {
+                            break;
+                        };
+                        fetch(job).await;
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{station, temp_path};
+    use std::{sync::atomic::AtomicUsize, time::Duration};
+
+    fn with_icon(uuid: &str, url: &str) -> ApiStation {
+        let mut station = station(uuid);
+        station.favicon = url.to_string();
+        station
+    }
+
+    #[test]
+    fn icons_are_cached_under_the_station_uuid() {
+        let dir = Path::new("favicons");
+
+        assert_eq!(
+            favicon_path(dir, &with_icon("9617a958", "http://jazz/icon.png")),
+            Some(dir.join("9617a958"))
+        );
+        assert_eq!(
+            favicon_path(dir, &with_icon("../../etc/passwd", "http://jazz/icon.png")),
+            Some(dir.join(".._.._etc_passwd"))
+        );
+    }
+
+    #[test]
+    fn stations_without_an_icon_or_uuid_have_no_cache_path() {
+        let dir = Path::new("favicons");
+
+        assert_eq!(favicon_path(dir, &with_icon("9617a958", " ")), None);
+        assert_eq!(
+            favicon_path(dir, &with_icon("", "http://jazz/icon.png")),
+            None
+        );
+    }
+
+    #[test]
+    fn cached_icon_is_used_without_downloading() {
+        let dir = temp_path("favicons");
+        // Nothing answers on the discard port, so a download would fail.
+        let station = with_icon("cached", "http://127.0.0.1:9/icon.png");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cached"), "png").unwrap();
+
+        let fetched = fetch_favicon_into(&dir, &station);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(fetched, Some(dir.join("cached")));
+    }
+
+    #[test]
+    fn icon_that_isnt_cached_is_not_found_in_the_cache() {
+        let dir = temp_path("no-favicons");
+
+        assert_eq!(
+            cached_favicon(&dir, &with_icon("a", "http://jazz/icon.png")),
+            None
+        );
+    }
+
+    fn jobs(uuids: &[&str]) -> Vec<FaviconJob> {
+        uuids
+            .iter()
+            .map(|uuid| FaviconJob {
+                uuid: uuid.to_string(),
+                url: format!("http://{}/icon.png", uuid),
+            })
+            .collect()
+    }
+
+    /// Counts how many fetches run at once, and keeps the uuids fetched.
+    #[derive(Default)]
+    struct Counter {
+        running: AtomicUsize,
+        most: AtomicUsize,
+        fetched: Mutex<Vec<String>>,
+    }
+
+    fn counting(counter: &Arc<Counter>) -> FetchFavicon {
+        let counter = counter.clone();
+        Arc::new(move |job: FaviconJob| {
+            let counter = counter.clone();
+            Box::pin(async move {
+                let running = counter.running.fetch_add(1, Ordering::SeqCst) + 1;
+                counter.most.fetch_max(running, Ordering::SeqCst);
+                task::sleep(Duration::from_millis(20)).await;
+                counter.running.fetch_sub(1, Ordering::SeqCst);
+                counter.fetched.lock().unwrap().push(job.uuid);
+            })
+        })
+    }
+
+    fn finish(handles: Vec<task::JoinHandle<()>>) {
+        task::block_on(async {
+            for handle in handles {
+                handle.await;
+            }
+        });
+    }
+
+    #[test]
+    fn no_more_than_the_workers_fetch_at_once() {
+        let counter = Arc::new(Counter::default());
+        let prefetcher = FaviconPrefetcher::new(3, counting(&counter));
+
+        let handles = prefetcher.prefetch(jobs(&["a", "b", "c", "d", "e", "f", "g"]));
+        assert_eq!(handles.len(), 3);
+        finish(handles);
+
+        assert!(counter.most.load(Ordering::SeqCst) <= 3);
+        let mut fetched = counter.fetched.lock().unwrap().clone();
+        fetched.sort();
+        assert_eq!(fetched, ["a", "b", "c", "d", "e", "f", "g"]);
+    }
+
+    #[test]
+    fn a_new_batch_drops_what_is_left_of_the_last() {
+        let counter = Arc::new(Counter::default());
+        let prefetcher = FaviconPrefetcher::new(1, counting(&counter));
+
+        let old = prefetcher.prefetch(jobs(&["a", "b", "c", "d"]));
+        let new = prefetcher.prefetch(jobs(&["z"]));
+        finish(old);
+        finish(new);
+
+        let fetched = counter.fetched.lock().unwrap().clone();
+        // The old worker may already be on its first job, but stops after it.
+        assert!(fetched.len() <= 2, "{:?}", fetched);
+        assert_eq!(fetched.last().map(String::as_str), Some("z"));
+    }
+
+    #[test]
+    fn only_uncached_icons_are_prefetched() {
+        let dir = temp_path("prefetch-favicons");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cached"), "png").unwrap();
+        let stations = [
+            with_icon("cached", "http://jazz/icon.png"),
+            with_icon("new", " http://rock/icon.png "),
+            with_icon("no-icon", ""),
+        ];
+
+        let jobs = prefetch_jobs(&dir, &stations);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            jobs,
+            [FaviconJob {
+                uuid: "new".to_string(),
+                url: "http://rock/icon.png".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::{io, path::Path};
+
+use crate::{read_data_from_file_and_parse, write_data_to_file};
+
+pub const FAVORITES_PATH: &str = "favorites.json";
+
+/// Returns the stored station uuids, or an empty list if there are none yet.
+pub fn load_favorites() -> Vec<String> {
+    load_favorites_from(Path::new(FAVORITES_PATH))
+}
+
+pub fn load_favorites_from(path: &Path) -> Vec<String> {
+    match read_data_from_file_and_parse(path) {
+        Ok(parsed) => parsed
+            .members()
+            .filter_map(|uuid| uuid.as_str().map(String::from))
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+pub fn save_favorites(favorites: &[String]) -> io::Result<()> {
+    save_favorites_to(Path::new(FAVORITES_PATH), favorites)
+}
+
+/// Writes `favorites` to `path`, each uuid once.
+pub fn save_favorites_to(path: &Path, favorites: &[String]) -> io::Result<()> {
+    let mut uuids: Vec<&String> = vec![];
+    for uuid in favorites {
+        if !uuids.contains(&uuid) {
+            uuids.push(uuid);
+        }
+    }
+    let data = serde_json::to_string(&uuids)?;
+
+    write_data_to_file(path, &data)
+}
+
+/// Adds or removes `uuid` and returns whether it is now a favorite.
+pub fn toggle_favorite(favorites: &mut Vec<String>, uuid: &str) -> bool {
+    if favorites.iter().any(|favorite| favorite == uuid) {
+        favorites.retain(|favorite| favorite != uuid);
+        false
+    } else {
+        favorites.push(uuid.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::tests::temp_path;
+
+    #[test]
+    fn favorites_round_trip_without_duplicates() {
+        let path = temp_path("favorites.json");
+        let favorites = ["a", "b", "a", "c"].map(String::from);
+
+        save_favorites_to(&path, &favorites).unwrap();
+        let loaded = load_favorites_from(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn missing_favorites_load_empty() {
+        assert!(load_favorites_from(&temp_path("no-favorites.json")).is_empty());
+    }
+
+    #[test]
+    fn toggling_a_favorite_adds_then_removes_it() {
+        let mut favorites = vec![];
+
+        assert!(toggle_favorite(&mut favorites, "a"));
+        assert_eq!(favorites, ["a"]);
+        assert!(!toggle_favorite(&mut favorites, "a"));
+        assert!(favorites.is_empty());
+    }
+}
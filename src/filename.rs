@@ -0,0 +1,93 @@
+use radiobrowser::ApiStation;
+
+/// Longest file stem kept, in characters, leaving room for a suffix and extension.
+const MAX_FILENAME_CHARS: usize = 100;
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes `name` usable as a file name on every platform: characters Windows
+/// or unix reject become `_`, trailing dots and spaces are dropped, reserved
+/// device names get a leading `_` and the result is cut to a safe length.
+/// Gives an empty string if nothing usable is left.
+pub fn sanitize_filename(name: &str) -> String {
+    let replaced = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_FILENAME_CHARS)
+        .collect::<String>();
+    let trimmed = replaced.trim_end_matches(['.', ' ']);
+    let stem = trimmed.split('.').next().unwrap_or_default();
+
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        format!("_{}", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// A file name for `station`, from its name or else its uuid.
+pub fn station_filename(station: &ApiStation) -> String {
+    [&station.name, &station.stationuuid]
+        .into_iter()
+        .map(|name| sanitize_filename(name))
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| "station".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stations::local_station;
+
+    #[test]
+    fn illegal_characters_become_underscores() {
+        assert_eq!(sanitize_filename("AC/DC: Live?"), "AC_DC_ Live_");
+        assert_eq!(sanitize_filename("a<b>c\"d\\e|f*g\u{7}"), "a_b_c_d_e_f_g_");
+    }
+
+    #[test]
+    fn trailing_dots_and_spaces_are_dropped() {
+        assert_eq!(sanitize_filename("  Jazz FM. . "), "Jazz FM");
+    }
+
+    #[test]
+    fn reserved_device_names_get_a_prefix() {
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("prn.mp3"), "_prn.mp3");
+        assert_eq!(sanitize_filename("Lpt1"), "_Lpt1");
+        assert_eq!(sanitize_filename("Console"), "Console");
+    }
+
+    #[test]
+    fn unicode_is_kept() {
+        assert_eq!(sanitize_filename("Радио Ёлка 🎷"), "Радио Ёлка 🎷");
+    }
+
+    #[test]
+    fn long_names_are_cut_by_characters() {
+        let long = "é".repeat(300);
+
+        assert_eq!(sanitize_filename(&long).chars().count(), MAX_FILENAME_CHARS);
+    }
+
+    #[test]
+    fn stations_without_a_usable_name_fall_back_to_their_uuid() {
+        let mut station = local_station(" ... ", "http://jazz");
+        station.stationuuid = "9617a958-0601-11e8-ae97-52543be04c81".to_string();
+
+        assert_eq!(station_filename(&station), station.stationuuid);
+
+        station.stationuuid.clear();
+        assert_eq!(station_filename(&station), "station");
+    }
+}
@@ -0,0 +1,903 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+
+use radiobrowser::ApiStation;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blocked::is_blocked,
+    geo::station_coordinates,
+    stations::{dedupe_stations, station_languages},
+};
+
+/// Whether every queried tag has to be present or just one of them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    #[default]
+    All,
+    Any,
+}
+
+/// Compares the comma separated `station_tags` with `query_tags`, trimmed
+/// and ignoring case. An empty query matches every station.
+pub fn tags_match(station_tags: &str, query_tags: &[&str], mode: MatchMode) -> bool {
+    if query_tags.is_empty() {
+        return true;
+    }
+    let station_tags = station_tags
+        .split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect::<Vec<_>>();
+    let has_tag = |tag: &&str| station_tags.contains(&tag.trim().to_lowercase());
+
+    match mode {
+        MatchMode::All => query_tags.iter().all(has_tag),
+        MatchMode::Any => query_tags.iter().any(has_tag),
+    }
+}
+
+/// Whether a station streaming at `bitrate` kbps is kept. Stations with bitrate
+/// 0 never said, so only `hide_unknown` decides about those.
+pub fn bitrate_matches(bitrate: u32, min_bitrate: u32, hide_unknown: bool) -> bool {
+    match bitrate {
+        0 => !hide_unknown,
+        bitrate => bitrate >= min_bitrate,
+    }
+}
+
+/// Whether `station` last passed a check no more than `max_age` before `now`.
+/// Stations without a last passed check never do.
+pub fn checked_within(station: &ApiStation, max_age: Duration, now: SystemTime) -> bool {
+    station.lastcheckoktime_iso8601.is_some_and(|checked| {
+        now.duration_since(SystemTime::from(checked))
+            // A check in the future means the clock moved; count it as recent.
+            .map_or(true, |age| age <= max_age)
+    })
+}
+
+/// Constraints parsed from the search input, all of which must match.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StationQuery {
+    pub text: Vec<String>,
+    pub tags: Vec<String>,
+    pub tag_mode: MatchMode,
+    /// Part of the country name, as typed after `country:`.
+    pub country: Option<String>,
+    /// The whole country name, as picked in the dropdown.
+    pub exact_country: Option<String>,
+    pub language: Option<String>,
+    pub codec: Option<String>,
+    /// Leave out stations known to stream below this many kbps.
+    pub min_bitrate: u32,
+    /// Leave out stations that don't state their bitrate.
+    pub hide_unknown_bitrate: bool,
+    /// Collapse stations streaming from the same url.
+    pub hide_duplicates: bool,
+    /// Leave out stations that failed their last check.
+    pub hide_dead: bool,
+    /// Leave out stations that haven't passed a check for longer than this.
+    pub checked_within: Option<Duration>,
+    /// Leave out stations that don't say where they are.
+    pub located_only: bool,
+    /// Leave out stations whose last TLS check failed.
+    pub hide_ssl_errors: bool,
+    /// Uuids of the stations hidden for good.
+    pub blocked: HashSet<String>,
+}
+
+impl StationQuery {
+    /// Whether the query could leave out any station at all.
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty()
+            || !self.tags.is_empty()
+            || self.country.is_some()
+            || self.exact_country.is_some()
+            || self.language.is_some()
+            || self.codec.is_some()
+            || self.min_bitrate > 0
+            || self.hide_unknown_bitrate
+            || self.hide_duplicates
+            || self.hide_dead
+            || self.checked_within.is_some()
+            || self.located_only
+            || self.hide_ssl_errors
+            || !self.blocked.is_empty()
+    }
+
+    /// Checks everything but the text terms, which `fuzzy_filter` ranks.
+    pub fn matches(&self, station: &ApiStation) -> bool {
+        let tags = self.tags.iter().map(String::as_str).collect::<Vec<_>>();
+
+        !is_blocked(&station.stationuuid, &self.blocked)
+            && tags_match(&station.tags, &tags, self.tag_mode)
+            && (!self.hide_dead || station.lastcheckok == 1)
+            && (!self.located_only || station_coordinates(station).is_some())
+            && (!self.hide_ssl_errors || station.ssl_error.unwrap_or(0) == 0)
+            && self
+                .checked_within
+                .is_none_or(|max_age| checked_within(station, max_age, SystemTime::now()))
+            && bitrate_matches(station.bitrate, self.min_bitrate, self.hide_unknown_bitrate)
+            && self
+                .country
+                .as_ref()
+                .is_none_or(|country| station.country.to_lowercase().contains(country))
+            && self
+                .exact_country
+                .as_ref()
+                .is_none_or(|country| station.country.trim().to_lowercase() == *country)
+            && self
+                .language
+                .as_ref()
+                .is_none_or(|language| station_languages(&station.language).contains(language))
+            && self
+                .codec
+                .as_ref()
+                .is_none_or(|codec| station.codec.to_lowercase() == *codec)
+    }
+}
+
+/// Parses `tag:jazz country:"United States" language:german codec:mp3 some
+/// words` into a query. `checked:7` keeps stations that passed a check in the
+/// last 7 days.
+/// `tag:rock,pop` asks for several tags, all of them unless `tagmode:any` is
+/// given. Unknown keys are kept as plain text terms.
+pub fn parse_query(input: &str) -> StationQuery {
+    let mut query = StationQuery::default();
+
+    for token in tokenize(input) {
+        let (key, value) = match token.split_once(':') {
+            Some((key, value)) if !value.is_empty() => (key, value),
+            _ => {
+                query.text.push(token);
+                continue;
+            }
+        };
+        match key {
+            "tag" => query.tags.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from),
+            ),
+            "tagmode" if value == "any" => query.tag_mode = MatchMode::Any,
+            "tagmode" if value == "all" => query.tag_mode = MatchMode::All,
+            "country" => query.country = Some(value.to_string()),
+            "language" => query.language = Some(value.trim().to_string()),
+            "codec" => query.codec = Some(value.to_string()),
+            "checked" => match value.parse::<u64>() {
+                Ok(days) => {
+                    query.checked_within =
+                        Some(Duration::from_secs(days.saturating_mul(SECS_PER_DAY)))
+                }
+                Err(_) => query.text.push(token),
+            },
+            _ => query.text.push(token),
+        }
+    }
+
+    query
+}
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Splits on whitespace outside of double quotes and lowercases every token.
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(current.to_lowercase());
+                    current.clear();
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current.to_lowercase());
+    }
+
+    tokens
+}
+
+/// Queries shorter than this only match as exact substrings, since nearly
+/// every name contains one or two letters in order.
+const FUZZY_MIN_LEN: usize = 3;
+const SUBSTRING_SCORE: i64 = 1000;
+
+/// How well `needle` matches `haystack`, both lowercase. Substrings score
+/// highest, earlier ones more so. Otherwise the needle has to appear as a
+/// subsequence, scored by how many of its letters are adjacent.
+fn fuzzy_score(haystack: &str, needle: &str) -> Option<i64> {
+    if let Some(position) = haystack.find(needle) {
+        return Some(SUBSTRING_SCORE - position as i64);
+    }
+    if needle.chars().count() < FUZZY_MIN_LEN {
+        return None;
+    }
+
+    let mut needle_chars = needle.chars().peekable();
+    let mut score = 0;
+    let mut streak = 0;
+    for c in haystack.chars() {
+        match needle_chars.peek() {
+            Some(&next) if next == c => {
+                needle_chars.next();
+                streak += 1;
+                score += 10 * streak;
+            }
+            Some(_) => {
+                streak = 0;
+                score -= 1;
+            }
+            None => break,
+        }
+    }
+
+    match needle_chars.peek() {
+        None => Some(score.min(SUBSTRING_SCORE - 1)),
+        Some(_) => None,
+    }
+}
+
+/// Which station fields the text terms are matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchFields {
+    pub name: bool,
+    pub tags: bool,
+    pub country: bool,
+    pub homepage: bool,
+}
+
+impl Default for SearchFields {
+    /// Name and tags, which were the only fields searched before.
+    fn default() -> Self {
+        SearchFields {
+            name: true,
+            tags: true,
+            country: false,
+            homepage: false,
+        }
+    }
+}
+
+impl SearchFields {
+    /// The selected fields of `station`, lowercase.
+    fn haystacks(&self, station: &ApiStation) -> Vec<String> {
+        [
+            (self.name, &station.name),
+            (self.tags, &station.tags),
+            (self.country, &station.country),
+            (self.homepage, &station.homepage),
+        ]
+        .into_iter()
+        .filter(|(selected, _)| *selected)
+        .map(|(_, field)| field.to_lowercase())
+        .collect()
+    }
+}
+
+/// Scores every station whose `fields` fuzzily match each word of `query`,
+/// best matches first. Stations scoring equal keep their order.
+pub fn fuzzy_filter(
+    stations: &[ApiStation],
+    query: &str,
+    fields: SearchFields,
+) -> Vec<(ApiStation, i64)> {
+    let terms = search_terms(query);
+    let mut scored = stations
+        .iter()
+        .filter_map(|station| Some((station.clone(), station_score(station, &terms, fields)?)))
+        .collect::<Vec<_>>();
+    scored.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    scored
+}
+
+fn search_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// The sum of the best score of each of `terms` in the `fields` of
+/// `station`, or `None` if any term matches none of them.
+fn station_score(station: &ApiStation, terms: &[String], fields: SearchFields) -> Option<i64> {
+    let haystacks = fields.haystacks(station);
+    terms
+        .iter()
+        .map(|term| {
+            haystacks
+                .iter()
+                .filter_map(|haystack| fuzzy_score(haystack, term))
+                .max()
+        })
+        .sum()
+}
+
+/// The stations `query` keeps, not yet ranked or deduplicated, in the order
+/// of `stations`.
+fn matching_stations(
+    stations: &[ApiStation],
+    query: &StationQuery,
+    fields: SearchFields,
+) -> Vec<ApiStation> {
+    let terms = search_terms(&query.text.join(" "));
+    stations
+        .iter()
+        .filter(|station| query.matches(station))
+        .filter(|station| terms.is_empty() || station_score(station, &terms, fields).is_some())
+        .cloned()
+        .collect()
+}
+
+pub fn filter_stations(
+    stations: &[ApiStation],
+    query: &StationQuery,
+    fields: SearchFields,
+) -> Vec<ApiStation> {
+    rank_matches(matching_stations(stations, query, fields), query, fields)
+}
+
+/// Orders the stations `query` matched by relevance and drops duplicates if
+/// asked to, finishing what `matching_stations` started.
+fn rank_matches(
+    matching: Vec<ApiStation>,
+    query: &StationQuery,
+    fields: SearchFields,
+) -> Vec<ApiStation> {
+    let filtered = if query.text.is_empty() {
+        matching
+    } else {
+        fuzzy_filter(&matching, &query.text.join(" "), fields)
+            .into_iter()
+            .map(|(station, _)| station)
+            .collect()
+    };
+
+    if query.hide_duplicates {
+        dedupe_stations(filtered)
+    } else {
+        filtered
+    }
+}
+
+/// The last query `FilterCache` ran, with the stations it matched.
+#[derive(Debug)]
+struct CachedFilter {
+    query: StationQuery,
+    fields: SearchFields,
+    /// How many stations were filtered, to notice a different list.
+    total: usize,
+    matching: Vec<ApiStation>,
+}
+
+impl CachedFilter {
+    /// Whether every station `query` matches among `total` is also among
+    /// the cached matches. That holds when only the text grew at the end,
+    /// as it does while typing, and every cached term was long enough to be
+    /// matched fuzzily: shorter terms only match as substrings, so
+    /// lengthening one could let in stations it missed before.
+    fn narrows_to(&self, query: &StationQuery, fields: SearchFields, total: usize) -> bool {
+        let old_text = self.query.text.join(" ");
+        let new_text = query.text.join(" ");
+
+        self.total == total
+            && self.fields == fields
+            && new_text.starts_with(&old_text)
+            && search_terms(&old_text)
+                .iter()
+                .all(|term| term.chars().count() >= FUZZY_MIN_LEN)
+            && StationQuery {
+                text: vec![],
+                ..query.clone()
+            } == StationQuery {
+                text: vec![],
+                ..self.query.clone()
+            }
+    }
+}
+
+/// Remembers the last filter so a query that only narrows it, like one a
+/// letter longer, searches the last matches instead of every station.
+#[derive(Debug, Default)]
+pub struct FilterCache {
+    last: Option<CachedFilter>,
+}
+
+impl FilterCache {
+    /// The same stations `filter_stations` returns, in the same order.
+    pub fn filter(
+        &mut self,
+        stations: &[ApiStation],
+        query: &StationQuery,
+        fields: SearchFields,
+    ) -> Vec<ApiStation> {
+        let searched = match &self.last {
+            Some(last) if last.narrows_to(query, fields, stations.len()) => &last.matching,
+            _ => stations,
+        };
+        let matching = matching_stations(searched, query, fields);
+        let filtered = rank_matches(matching.clone(), query, fields);
+        self.last = Some(CachedFilter {
+            query: query.clone(),
+            fields,
+            total: stations.len(),
+            matching,
+        });
+
+        filtered
+    }
+
+    /// Forgets the last filter, for when the stations themselves changed.
+    pub fn invalidate(&mut self) {
+        self.last = None;
+    }
+}
+
+/// The search and dropdown picks, saved so the next start lists the same.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterState {
+    /// What was typed in the search, tags and keys included.
+    #[serde(rename = "filter_search")]
+    pub search: String,
+    /// The country picked in the dropdown, empty for all of them.
+    #[serde(rename = "filter_country")]
+    pub country: String,
+    /// The language picked in the dropdown, empty for all of them.
+    #[serde(rename = "filter_language")]
+    pub language: String,
+}
+
+impl FilterState {
+    /// This state for a list with only `countries` and `languages`. A picked
+    /// country or language that is no longer among them is dropped.
+    pub fn restore(&self, countries: &[String], languages: &[String]) -> FilterState {
+        let known = |entry: &str, entries: &[String]| {
+            entries
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(entry))
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        FilterState {
+            search: self.search.clone(),
+            country: known(&self.country, countries),
+            language: known(&self.language, languages),
+        }
+    }
+}
+
+/// "Showing X of Y" while a filter is active, otherwise just the total.
+pub fn format_count(shown: usize, total: usize, filtered: bool) -> String {
+    if filtered {
+        format!("Showing {} of {}", shown, total)
+    } else {
+        format!("{} stations", total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{station_to_json, stations::local_station};
+
+    fn named(names: &[&str]) -> Vec<ApiStation> {
+        names
+            .iter()
+            .map(|name| local_station(name, &format!("http://{}", name)))
+            .collect()
+    }
+
+    fn ranked(stations: &[ApiStation], query: &str) -> Vec<String> {
+        fuzzy_filter(stations, query, SearchFields::default())
+            .into_iter()
+            .map(|(station, _)| station.name)
+            .collect()
+    }
+
+    #[test]
+    fn typo_still_finds_the_station() {
+        let stations = named(&["Jazz FM", "Rock Antenne"]);
+
+        assert_eq!(ranked(&stations, "jzz"), ["Jazz FM"]);
+    }
+
+    #[test]
+    fn substrings_rank_above_subsequences_and_earlier_above_later() {
+        let stations = named(&["Just Another Zone Zero", "Smooth Jazz", "Jazz FM"]);
+
+        assert_eq!(
+            ranked(&stations, "jazz"),
+            ["Jazz FM", "Smooth Jazz", "Just Another Zone Zero"]
+        );
+    }
+
+    #[test]
+    fn adjacent_letters_rank_above_scattered_ones() {
+        let stations = named(&["J a z Radio", "Jaz Radio"]);
+
+        assert_eq!(ranked(&stations, "jazr"), ["Jaz Radio", "J a z Radio"]);
+    }
+
+    #[test]
+    fn short_queries_only_match_substrings() {
+        let stations = named(&["Jazz FM", "JZ Radio"]);
+
+        assert_eq!(ranked(&stations, "jz"), ["JZ Radio"]);
+    }
+
+    #[test]
+    fn every_word_of_the_query_has_to_match() {
+        let stations = named(&["Smooth Jazz", "Jazz FM"]);
+
+        assert_eq!(ranked(&stations, "jazz fm"), ["Jazz FM"]);
+    }
+
+    #[test]
+    fn all_mode_needs_every_tag_in_any_order() {
+        assert!(tags_match("rock,pop", &["rock", "pop"], MatchMode::All));
+        assert!(tags_match(
+            "pop, Rock ,indie",
+            &[" ROCK", "pop"],
+            MatchMode::All
+        ));
+        assert!(!tags_match("pop,indie", &["rock", "pop"], MatchMode::All));
+    }
+
+    #[test]
+    fn any_mode_needs_one_of_the_tags() {
+        assert!(tags_match("pop,indie", &["rock", "pop"], MatchMode::Any));
+        assert!(!tags_match("jazz,blues", &["rock", "pop"], MatchMode::Any));
+    }
+
+    #[test]
+    fn tags_match_whole_tags_only() {
+        assert!(!tags_match("hard rock", &["rock"], MatchMode::All));
+        assert!(!tags_match("", &["rock"], MatchMode::Any));
+    }
+
+    #[test]
+    fn empty_tag_query_matches_every_station() {
+        assert!(tags_match("", &[], MatchMode::All));
+        assert!(tags_match("rock", &[], MatchMode::Any));
+    }
+
+    #[test]
+    fn count_shows_the_filtered_share_only_while_filtering() {
+        assert_eq!(format_count(0, 0, false), "0 stations");
+        assert_eq!(format_count(500, 500, false), "500 stations");
+        assert_eq!(format_count(0, 500, true), "Showing 0 of 500");
+        assert_eq!(format_count(42, 500, true), "Showing 42 of 500");
+        assert_eq!(format_count(500, 500, true), "Showing 500 of 500");
+    }
+
+    #[test]
+    fn bitrate_below_the_minimum_is_left_out() {
+        assert!(bitrate_matches(128, 128, false));
+        assert!(bitrate_matches(320, 128, true));
+        assert!(!bitrate_matches(96, 128, false));
+    }
+
+    #[test]
+    fn unknown_bitrate_is_only_left_out_when_asked() {
+        assert!(bitrate_matches(0, 128, false));
+        assert!(!bitrate_matches(0, 0, true));
+        assert!(!bitrate_matches(0, 128, true));
+    }
+
+    fn searched(fields: SearchFields, query: &str) -> Vec<String> {
+        let mut station = local_station("Jazz FM", "http://jazz");
+        station.tags = "smooth,lounge".to_string();
+        station.country = "Germany".to_string();
+        station.homepage = "https://radio.example.org".to_string();
+        let query = parse_query(query);
+
+        filter_stations(&[station], &query, fields)
+            .into_iter()
+            .map(|station| station.name)
+            .collect()
+    }
+
+    fn fields(name: bool, tags: bool, country: bool, homepage: bool) -> SearchFields {
+        SearchFields {
+            name,
+            tags,
+            country,
+            homepage,
+        }
+    }
+
+    #[test]
+    fn default_search_covers_name_and_tags() {
+        assert_eq!(SearchFields::default(), fields(true, true, false, false));
+        assert_eq!(searched(SearchFields::default(), "jazz"), ["Jazz FM"]);
+        assert_eq!(searched(SearchFields::default(), "lounge"), ["Jazz FM"]);
+        assert!(searched(SearchFields::default(), "germany").is_empty());
+        assert!(searched(SearchFields::default(), "example.org").is_empty());
+    }
+
+    #[test]
+    fn each_field_is_only_searched_when_selected() {
+        let queries = ["fm", "lounge", "germany", "example.org"];
+        let selections = [
+            fields(true, false, false, false),
+            fields(false, true, false, false),
+            fields(false, false, true, false),
+            fields(false, false, false, true),
+        ];
+
+        for (selected, fields) in selections.into_iter().enumerate() {
+            for (queried, query) in queries.iter().enumerate() {
+                assert_eq!(
+                    !searched(fields, query).is_empty(),
+                    selected == queried,
+                    "{:?} searching {}",
+                    fields,
+                    query
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn terms_can_match_in_different_selected_fields() {
+        assert_eq!(
+            searched(fields(false, false, true, true), "germany example.org"),
+            ["Jazz FM"]
+        );
+        assert!(searched(fields(false, false, false, false), "jazz").is_empty());
+    }
+
+    const DAY: Duration = Duration::from_secs(SECS_PER_DAY);
+
+    fn checked_at(time: Option<&str>) -> ApiStation {
+        let mut station = local_station("Jazz", "http://jazz");
+        station.lastcheckoktime_iso8601 = time.map(|time| time.parse().unwrap());
+        station
+    }
+
+    /// 2024-03-10T12:00:00Z.
+    fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_710_072_000)
+    }
+
+    #[test]
+    fn recent_check_is_within_the_max_age() {
+        let station = checked_at(Some("2024-03-08T12:00:00Z"));
+
+        assert!(checked_within(&station, 7 * DAY, now()));
+        assert!(checked_within(&station, 2 * DAY, now()));
+    }
+
+    #[test]
+    fn old_check_is_not() {
+        let station = checked_at(Some("2024-01-01T00:00:00Z"));
+
+        assert!(!checked_within(&station, 7 * DAY, now()));
+    }
+
+    #[test]
+    fn check_in_the_future_counts_as_recent() {
+        let station = checked_at(Some("2024-03-11T00:00:00Z"));
+
+        assert!(checked_within(&station, DAY, now()));
+    }
+
+    #[test]
+    fn station_without_a_readable_check_time_is_excluded() {
+        assert!(!checked_within(&checked_at(None), 7 * DAY, now()));
+
+        let mut malformed = station_to_json(checked_at(None));
+        malformed["lastcheckoktime_iso8601"] = "last tuesday".into();
+        assert!(serde_json::from_value::<ApiStation>(malformed).is_err());
+    }
+
+    #[test]
+    fn blocked_stations_are_filtered_out_and_the_rest_remain() {
+        let stations = ["a", "b", "c"].map(crate::tests::station);
+        let query = StationQuery {
+            blocked: HashSet::from(["b".to_string()]),
+            ..StationQuery::default()
+        };
+
+        let shown = filter_stations(&stations, &query, SearchFields::default());
+
+        assert!(query.is_active());
+        assert_eq!(shown, [stations[0].clone(), stations[2].clone()]);
+    }
+
+    #[test]
+    fn typing_through_the_cache_matches_a_full_filter() {
+        let stations = named(&[
+            "Jazz FM",
+            "Smooth Jazz",
+            "Jaz Radio",
+            "Just Another Zone Zero",
+            "Rock Antenne",
+        ]);
+        let mut cache = FilterCache::default();
+
+        for typed in ["j", "ja", "jaz", "jazz", "jazz ", "jazz f", "jazz fm"] {
+            let query = parse_query(typed);
+            assert_eq!(
+                cache.filter(&stations, &query, SearchFields::default()),
+                filter_stations(&stations, &query, SearchFields::default()),
+                "after typing {:?}",
+                typed
+            );
+        }
+    }
+
+    #[test]
+    fn a_longer_query_searches_the_cached_matches() {
+        let stations = named(&["Jazz FM", "Smooth Jazz", "Rock Antenne"]);
+        let mut cache = FilterCache::default();
+        cache.filter(&stations, &parse_query("jazz"), SearchFields::default());
+        let last = cache.last.as_ref().unwrap();
+
+        assert!(last.narrows_to(&parse_query("jazz f"), SearchFields::default(), 3));
+        assert!(!last.narrows_to(&parse_query("jaz"), SearchFields::default(), 3));
+        assert!(!last.narrows_to(&parse_query("jazz f"), SearchFields::default(), 4));
+    }
+
+    #[test]
+    fn growing_a_short_term_filters_every_station_again() {
+        let stations = named(&["JZ Radio", "Jazz FM"]);
+        let mut cache = FilterCache::default();
+        cache.filter(&stations, &parse_query("jz"), SearchFields::default());
+        let query = parse_query("jzz");
+
+        assert!(!cache
+            .last
+            .as_ref()
+            .unwrap()
+            .narrows_to(&query, SearchFields::default(), 2));
+        assert_eq!(
+            cache.filter(&stations, &query, SearchFields::default()),
+            filter_stations(&stations, &query, SearchFields::default())
+        );
+    }
+
+    #[test]
+    fn invalidating_filters_a_new_list_in_full() {
+        let mut cache = FilterCache::default();
+        cache.filter(
+            &named(&["Jazz FM", "Rock Antenne"]),
+            &parse_query("jazz"),
+            SearchFields::default(),
+        );
+        cache.invalidate();
+        // As long as the old list, so only invalidating keeps its matches out.
+        let stations = named(&["Jazz FM 2", "Rock Antenne"]);
+        let query = parse_query("jazz f");
+
+        assert_eq!(
+            cache.filter(&stations, &query, SearchFields::default()),
+            filter_stations(&stations, &query, SearchFields::default())
+        );
+    }
+
+    fn saved(country: &str, language: &str) -> FilterState {
+        FilterState {
+            search: "jazz tag:smooth".to_string(),
+            country: country.to_string(),
+            language: language.to_string(),
+        }
+    }
+
+    #[test]
+    fn restoring_drops_a_country_no_longer_listed() {
+        let countries = ["Austria".to_string(), "Germany".to_string()];
+        let languages = ["german".to_string()];
+
+        assert_eq!(
+            saved("Atlantis", "german").restore(&countries, &languages),
+            saved("", "german")
+        );
+    }
+
+    #[test]
+    fn restoring_keeps_listed_picks_as_the_list_spells_them() {
+        let countries = ["Germany".to_string()];
+        let languages = ["german".to_string()];
+
+        assert_eq!(
+            saved("GERMANY", "German").restore(&countries, &languages),
+            saved("Germany", "german")
+        );
+        assert_eq!(saved("", "").restore(&countries, &languages), saved("", ""));
+    }
+
+    #[test]
+    fn filter_state_round_trips_through_the_config_keys() {
+        let state = saved("Germany", "german");
+        let value = serde_json::to_value(&state).unwrap();
+
+        assert_eq!(value["filter_search"], "jazz tag:smooth");
+        assert_eq!(value["filter_country"], "Germany");
+        assert_eq!(serde_json::from_value::<FilterState>(value).unwrap(), state);
+    }
+
+    fn listed(name: &str, tags: &str, country: &str, codec: &str) -> ApiStation {
+        let mut station = local_station(name, &format!("http://{}", name));
+        station.tags = tags.to_string();
+        station.country = country.to_string();
+        station.codec = codec.to_string();
+        station
+    }
+
+    #[test]
+    fn keys_become_constraints_and_bare_words_text() {
+        let query = parse_query("Smooth tag:Jazz country:Germany codec:MP3 FM");
+
+        assert_eq!(query.text, ["smooth", "fm"]);
+        assert_eq!(query.tags, ["jazz"]);
+        assert_eq!(query.country.as_deref(), Some("germany"));
+        assert_eq!(query.codec.as_deref(), Some("mp3"));
+    }
+
+    #[test]
+    fn quoted_values_keep_their_spaces() {
+        let query = parse_query("country:\"United States\" \"smooth jazz\"");
+
+        assert_eq!(query.country.as_deref(), Some("united states"));
+        assert_eq!(query.text, ["smooth jazz"]);
+    }
+
+    #[test]
+    fn unknown_or_empty_keys_are_plain_text() {
+        let query = parse_query("genre:jazz tag: checked:soon");
+
+        assert_eq!(query.text, ["genre:jazz", "tag:", "checked:soon"]);
+        assert!(query.tags.is_empty());
+        assert_eq!(query.checked_within, None);
+    }
+
+    #[test]
+    fn every_constraint_has_to_match() {
+        let stations = [
+            listed("Jazz One", "jazz", "Germany", "MP3"),
+            listed("Jazz Two", "jazz", "Germany", "AAC"),
+            listed("Jazz Three", "jazz", "Austria", "MP3"),
+            listed("Rock One", "rock", "Germany", "MP3"),
+        ];
+
+        let found = filter_stations(
+            &stations,
+            &parse_query("tag:jazz country:germany codec:mp3"),
+            SearchFields::default(),
+        );
+
+        assert_eq!(found, [stations[0].clone()]);
+    }
+
+    #[test]
+    fn the_typed_country_matches_part_of_the_name() {
+        let query = parse_query("country:guinea");
+
+        assert!(query.matches(&listed("a", "", "Equatorial Guinea", "")));
+        assert!(query.matches(&listed("b", "", "Guinea", "")));
+    }
+
+    #[test]
+    fn the_picked_country_matches_only_itself() {
+        let query = StationQuery {
+            exact_country: Some("niger".to_string()),
+            ..StationQuery::default()
+        };
+
+        assert!(query.matches(&listed("a", "", " Niger ", "")));
+        assert!(query.matches(&listed("b", "", "NIGER", "")));
+        assert!(!query.matches(&listed("c", "", "Nigeria", "")));
+    }
+}
@@ -0,0 +1,113 @@
+use std::cmp::Ordering;
+
+use radiobrowser::ApiStation;
+
+/// The mean earth radius the haversine formula works with.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// The great-circle distance in km between two points given in degrees.
+pub fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Parses `48.21,16.37` into a latitude and longitude. Empty, malformed or
+/// out of range coordinates give `None`.
+pub fn parse_coordinates(coordinates: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = coordinates.split_once(',')?;
+    valid_coordinates(lat.trim().parse().ok()?, lon.trim().parse().ok()?)
+}
+
+fn valid_coordinates(lat: f64, lon: f64) -> Option<(f64, f64)> {
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Where `station` says it is, if it gave both coordinates and they are on
+/// the map.
+pub fn station_coordinates(station: &ApiStation) -> Option<(f64, f64)> {
+    valid_coordinates(station.geo_lat?, station.geo_long?)
+}
+
+/// How far `station` is from `home` in km, if it has coordinates.
+pub fn station_distance_km(station: &ApiStation, (lat, lon): (f64, f64)) -> Option<f64> {
+    station_coordinates(station)
+        .map(|(station_lat, station_lon)| distance_km(lat, lon, station_lat, station_lon))
+}
+
+/// Orders stations by their distance from `home`. Stations without
+/// coordinates come after every station with them.
+pub fn compare_distance(a: &ApiStation, b: &ApiStation, home: (f64, f64)) -> Ordering {
+    match (station_distance_km(a, home), station_distance_km(b, home)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::station;
+
+    const VIENNA: (f64, f64) = (48.2082, 16.3738);
+
+    fn assert_near(km: f64, expected: f64) {
+        assert!(
+            (km - expected).abs() < 5.0,
+            "{} km, not {} km",
+            km,
+            expected
+        );
+    }
+
+    fn located(uuid: &str, coordinates: Option<(f64, f64)>) -> ApiStation {
+        let mut station = station(uuid);
+        station.geo_lat = coordinates.map(|(lat, _)| lat);
+        station.geo_long = coordinates.map(|(_, lon)| lon);
+        station
+    }
+
+    #[test]
+    fn distances_between_known_cities() {
+        assert_near(distance_km(48.8566, 2.3522, 51.5074, -0.1278), 343.5);
+        assert_near(distance_km(52.52, 13.405, VIENNA.0, VIENNA.1), 523.6);
+        assert_near(distance_km(40.7128, -74.006, 34.0522, -118.2437), 3935.7);
+    }
+
+    #[test]
+    fn distance_is_zero_to_itself_and_half_the_globe_at_most() {
+        assert_eq!(distance_km(VIENNA.0, VIENNA.1, VIENNA.0, VIENNA.1), 0.0);
+        assert_near(
+            distance_km(0.0, 0.0, 0.0, 180.0),
+            std::f64::consts::PI * EARTH_RADIUS_KM,
+        );
+    }
+
+    #[test]
+    fn coordinates_have_to_be_complete_and_on_the_map() {
+        assert_eq!(parse_coordinates(" 48.21, 16.37 "), Some((48.21, 16.37)));
+        assert_eq!(parse_coordinates("91,0"), None);
+        assert_eq!(parse_coordinates("48.21"), None);
+        assert_eq!(parse_coordinates(""), None);
+        assert_eq!(station_coordinates(&located("a", None)), None);
+    }
+
+    #[test]
+    fn nearer_stations_come_first_and_unlocated_ones_last() {
+        let mut stations = [
+            located("nowhere", None),
+            located("paris", Some((48.8566, 2.3522))),
+            located("berlin", Some((52.52, 13.405))),
+        ];
+
+        stations.sort_by(|a, b| compare_distance(a, b, VIENNA));
+
+        let uuids: Vec<_> = stations.iter().map(|s| s.stationuuid.as_str()).collect();
+        assert_eq!(uuids, ["berlin", "paris", "nowhere"]);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{collections::VecDeque, env, fs, io, path::Path};
+
+use radiobrowser::ApiStation;
+
+use crate::{station_to_json, stations::same_station, write_data_to_file};
+
+pub const HISTORY_PATH: &str = "history.json";
+pub const HISTORY_SIZE_ENV: &str = "RRADIO_HISTORY_SIZE";
+const DEFAULT_HISTORY_SIZE: usize = 20;
+
+/// Recently played stations, most recent first.
+pub struct History {
+    entries: VecDeque<ApiStation>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Loads `history.json`, starting empty if it is missing or unreadable.
+    pub fn load(capacity: usize) -> Self {
+        let mut history = History::new(capacity);
+        let stations = fs::read_to_string(HISTORY_PATH)
+            .ok()
+            .and_then(|data| serde_json::from_str::<Vec<ApiStation>>(&data).ok())
+            .unwrap_or_default();
+        // Oldest first so the most recent ends up in front again.
+        for station in stations.into_iter().rev() {
+            history.push(station);
+        }
+
+        history
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let json_vec = self
+            .entries
+            .iter()
+            .cloned()
+            .map(station_to_json)
+            .collect::<Vec<_>>();
+
+        write_data_to_file(
+            Path::new(HISTORY_PATH),
+            &serde_json::Value::Array(json_vec).to_string(),
+        )
+    }
+
+    /// Puts `station` in front, moving it there if it was already played.
+    pub fn push(&mut self, station: ApiStation) {
+        self.entries.retain(|entry| !same_station(entry, &station));
+        self.entries.push_front(station);
+        self.entries.truncate(self.capacity);
+    }
+
+    pub fn stations(&self) -> Vec<ApiStation> {
+        self.entries.iter().cloned().collect()
+    }
+}
+
+/// The history size from `RRADIO_HISTORY_SIZE`, falling back to 20.
+pub fn history_size() -> usize {
+    env::var(HISTORY_SIZE_ENV)
+        .ok()
+        .and_then(|size| size.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_HISTORY_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stations::local_station, tests::station};
+
+    fn uuids(history: &History) -> Vec<String> {
+        history
+            .stations()
+            .into_iter()
+            .map(|station| station.stationuuid)
+            .collect()
+    }
+
+    #[test]
+    fn replaying_a_station_moves_it_to_the_front() {
+        let mut history = History::new(5);
+        for uuid in ["a", "b", "c", "a"] {
+            history.push(station(uuid));
+        }
+
+        assert_eq!(uuids(&history), ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn playing_the_same_station_twice_keeps_one_entry() {
+        let mut history = History::new(5);
+        history.push(station("a"));
+        history.push(station("a"));
+
+        assert_eq!(uuids(&history), ["a"]);
+    }
+
+    #[test]
+    fn history_keeps_only_the_most_recent() {
+        let mut history = History::new(2);
+        for uuid in ["a", "b", "c"] {
+            history.push(station(uuid));
+        }
+
+        assert_eq!(uuids(&history), ["c", "b"]);
+    }
+
+    #[test]
+    fn stations_without_a_uuid_are_told_apart_by_their_url() {
+        let mut history = History::new(5);
+        history.push(local_station("One", "http://one.example/stream"));
+        history.push(local_station("Two", "http://two.example/stream"));
+        history.push(local_station("One again", "http://one.example/stream"));
+
+        let names = history
+            .stations()
+            .into_iter()
+            .map(|station| station.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["One again", "Two"]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::{cmp::Reverse, time::Duration};
+
+use async_std::future;
+use reqwest::Url;
+
+use crate::details::format_bitrate;
+
+/// How long the master playlist of a station gets to arrive.
+const PLAYLIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One quality an HLS master playlist offers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HlsVariant {
+    /// Peak bits per second, 0 if the playlist didn't say.
+    pub bandwidth: u64,
+    pub codecs: Option<String>,
+    pub url: String,
+}
+
+impl HlsVariant {
+    /// The variant as offered in the quality dropdown, like "128k".
+    pub fn label(&self) -> String {
+        format_bitrate((self.bandwidth / 1000) as u32)
+    }
+}
+
+/// The variants listed in a master `playlist`, in playlist order. Each
+/// `#EXT-X-STREAM-INF` tag describes the uri on the next line that isn't a
+/// comment. Media playlists list none.
+pub fn parse_hls_variants(playlist: &str) -> Vec<HlsVariant> {
+    let mut variants = vec![];
+    let mut pending: Option<(u64, Option<String>)> = None;
+
+    for line in playlist.lines().map(str::trim) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let attributes = parse_attributes(attributes);
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.clone())
+            };
+            let bandwidth = attribute("BANDWIDTH")
+                .and_then(|bandwidth| bandwidth.parse().ok())
+                .unwrap_or(0);
+            pending = Some((bandwidth, attribute("CODECS")));
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else if let Some((bandwidth, codecs)) = pending.take() {
+            variants.push(HlsVariant {
+                bandwidth,
+                codecs,
+                url: line.to_string(),
+            });
+        }
+    }
+
+    variants
+}
+
+/// Splits `BANDWIDTH=128000,CODECS="mp4a.40.2,mp4a.40.5"` into its keys and
+/// values, with the quotes taken off. Commas inside quotes don't split.
+fn parse_attributes(attributes: &str) -> Vec<(String, String)> {
+    let mut pairs = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in attributes.chars().chain([',']) {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                if let Some((key, value)) = current.split_once('=') {
+                    pairs.push((key.trim().to_uppercase(), value.trim().to_string()));
+                }
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+
+    pairs
+}
+
+/// Fetches the playlist at `url` and returns its variants, best first, with
+/// their uris made absolute.
+pub async fn fetch_hls_variants(url: &str) -> Result<Vec<HlsVariant>, String> {
+    let base = Url::parse(url).map_err(|why| why.to_string())?;
+    let request = async {
+        reqwest::get(base.clone())
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    };
+    let playlist = future::timeout(PLAYLIST_TIMEOUT, request)
+        .await
+        .map_err(|_| "the playlist took too long".to_string())?
+        .map_err(|why| why.to_string())?;
+
+    let mut variants = parse_hls_variants(&playlist)
+        .into_iter()
+        .filter_map(|variant| {
+            let url = base.join(&variant.url).ok()?.to_string();
+            Some(HlsVariant { url, ..variant })
+        })
+        .collect::<Vec<_>>();
+    variants.sort_by_key(|variant| Reverse(variant.bandwidth));
+
+    Ok(variants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER_PLAYLIST: &str = "#EXTM3U\n\
+        #EXT-X-VERSION:3\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS=\"mp4a.40.5\"\n\
+        low/index.m3u8\n\
+        \n\
+        #EXT-X-STREAM-INF:CODECS=\"mp4a.40.2,mp4a.40.5\",BANDWIDTH=128000\n\
+        # a comment between the tag and its uri\n\
+        https://cdn.example/high/index.m3u8\n\
+        #EXT-X-STREAM-INF:RESOLUTION=1x1\n\
+        unknown.m3u8\n";
+
+    #[test]
+    fn master_playlist_lists_each_variant_in_order() {
+        assert_eq!(
+            parse_hls_variants(MASTER_PLAYLIST),
+            [
+                HlsVariant {
+                    bandwidth: 64000,
+                    codecs: Some("mp4a.40.5".to_string()),
+                    url: "low/index.m3u8".to_string(),
+                },
+                HlsVariant {
+                    bandwidth: 128000,
+                    codecs: Some("mp4a.40.2,mp4a.40.5".to_string()),
+                    url: "https://cdn.example/high/index.m3u8".to_string(),
+                },
+                HlsVariant {
+                    bandwidth: 0,
+                    codecs: None,
+                    url: "unknown.m3u8".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn media_playlist_has_no_variants() {
+        let media = "#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXTINF:6.0,\nsegment1.aac\n";
+
+        assert!(parse_hls_variants(media).is_empty());
+    }
+
+    #[test]
+    fn variants_are_labeled_by_bitrate() {
+        let variants = parse_hls_variants(MASTER_PLAYLIST);
+
+        assert_eq!(
+            variants.iter().map(HlsVariant::label).collect::<Vec<_>>(),
+            ["64k", "128k", "—"]
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::{io, process::Command};
+
+/// Whether `url` is an http(s) url with a host, and nothing a shell or
+/// launcher could read as more than one argument.
+pub fn is_web_url(url: &str) -> bool {
+    let lowercase = url.to_lowercase();
+    let Some(rest) = lowercase
+        .strip_prefix("http://")
+        .or_else(|| lowercase.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+
+    !host.is_empty()
+        && !url
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '"')
+}
+
+/// Opens `url` in the default browser. Only http(s) urls are launched.
+pub fn open_url(url: &str) -> io::Result<()> {
+    if !is_web_url(url) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("not a web url: {}", url),
+        ));
+    }
+
+    #[cfg(target_os = "windows")]
+    // `start` goes through cmd, which would interpret `&` in query strings.
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(url).spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_and_https_urls_are_web_urls() {
+        assert!(is_web_url("http://example.com"));
+        assert!(is_web_url("https://example.com/jazz?genre=smooth#top"));
+        assert!(is_web_url("HTTPS://EXAMPLE.COM"));
+    }
+
+    #[test]
+    fn other_schemes_and_empty_urls_are_not() {
+        assert!(!is_web_url("ftp://example.com"));
+        assert!(!is_web_url("file:///etc/passwd"));
+        assert!(!is_web_url(""));
+        assert!(!is_web_url("http://"));
+        assert!(!is_web_url("example.com"));
+    }
+
+    #[test]
+    fn urls_that_could_split_into_arguments_are_not() {
+        assert!(!is_web_url("http://example.com/ --new-window"));
+        assert!(!is_web_url("http://example.com/\"&calc"));
+        assert!(!is_web_url("http://example.com/\nrm"));
+    }
+}
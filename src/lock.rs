@@ -0,0 +1,205 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+pub const LOCK_PATH: &str = "rradio.lock";
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another rradio with this pid is running in the same directory.
+    Held(u32),
+    Io(io::Error),
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Held(pid) => write!(
+                f,
+                "rradio is already running here (pid {}); close it first",
+                pid
+            ),
+            LockError::Io(why) => write!(f, "couldn't take {}: {}", LOCK_PATH, why),
+        }
+    }
+}
+
+impl Error for LockError {}
+
+/// Holds the lock file until dropped, when it is removed again.
+#[derive(Debug)]
+pub struct LockGuard {
+    path: PathBuf,
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Takes `rradio.lock` for this process, so a second copy doesn't write the
+/// same station cache. A lock left by a process that is gone is taken over.
+pub fn acquire_lock() -> Result<LockGuard, LockError> {
+    acquire_lock_at(Path::new(LOCK_PATH), process::id(), process_alive)
+}
+
+/// Takes the lock at `path` for `pid`. A lock whose pid doesn't parse or
+/// isn't `alive` any more is stale and gets reclaimed.
+pub fn acquire_lock_at(
+    path: &Path,
+    pid: u32,
+    alive: impl Fn(u32) -> bool,
+) -> Result<LockGuard, LockError> {
+    // The pid is written first and linked into place whole, so a lock is
+    // never seen empty while its owner is still writing it.
+    let mut claim = path.as_os_str().to_owned();
+    claim.push(format!(".{}", pid));
+    let claim = PathBuf::from(claim);
+    fs::write(&claim, pid.to_string()).map_err(LockError::Io)?;
+    let taken = link_lock(path, &claim, pid, alive);
+    let _ = fs::remove_file(&claim);
+
+    taken
+}
+
+fn link_lock(
+    path: &Path,
+    claim: &Path,
+    pid: u32,
+    alive: impl Fn(u32) -> bool,
+) -> Result<LockGuard, LockError> {
+    // Once for the lock as found, once more after clearing a stale one.
+    for _ in 0..2 {
+        match fs::hard_link(claim, path) {
+            Ok(()) => {
+                return Ok(LockGuard {
+                    path: path.to_path_buf(),
+                })
+            }
+            Err(why) if why.kind() == io::ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(path)
+                    .ok()
+                    .and_then(|holder| holder.trim().parse::<u32>().ok());
+                match holder {
+                    Some(holder) if holder != pid && alive(holder) => {
+                        return Err(LockError::Held(holder))
+                    }
+                    _ => crate::cache::remove_if_present(path).map_err(LockError::Io)?,
+                }
+            }
+            Err(why) => return Err(LockError::Io(why)),
+        }
+    }
+
+    Err(LockError::Io(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "the lock kept being taken",
+    )))
+}
+
+/// Whether a process with `pid` is running. When that can't be told the
+/// process counts as running, so a live lock is never taken over.
+fn process_alive(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        return Path::new("/proc").join(pid.to_string()).exists();
+    }
+    let check = if cfg!(windows) {
+        process::Command::new("tasklist")
+            .args(["/NH", "/FI", &format!("PID eq {}", pid)])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+    } else {
+        process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .map(|output| output.status.success())
+    };
+
+    check.unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_path;
+
+    const OURS: u32 = 1000;
+    const THEIRS: u32 = 4242;
+
+    fn left_behind(name: &str, holder: &str) -> PathBuf {
+        let path = temp_path(name);
+        fs::write(&path, holder).unwrap();
+        path
+    }
+
+    #[test]
+    fn a_lock_of_a_dead_process_is_taken_over() {
+        let path = left_behind("stale.lock", &THEIRS.to_string());
+
+        let guard = acquire_lock_at(&path, OURS, |_| false).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), OURS.to_string());
+        drop(guard);
+    }
+
+    #[test]
+    fn a_lock_of_a_running_process_is_left_alone() {
+        let path = left_behind("held.lock", &THEIRS.to_string());
+
+        let held = acquire_lock_at(&path, OURS, |pid| pid == THEIRS);
+
+        assert!(matches!(held, Err(LockError::Held(THEIRS))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), THEIRS.to_string());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn an_unreadable_or_own_lock_is_taken_over() {
+        for (name, holder) in [("garbage.lock", "not a pid"), ("own.lock", "1000")] {
+            let path = left_behind(name, holder);
+
+            let guard = acquire_lock_at(&path, OURS, |_| true).unwrap();
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), OURS.to_string());
+            drop(guard);
+        }
+    }
+
+    #[test]
+    fn dropping_the_guard_removes_the_lock() {
+        let path = temp_path("released.lock");
+
+        let guard = acquire_lock_at(&path, OURS, |_| true).unwrap();
+        assert!(path.exists());
+        drop(guard);
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn taking_the_lock_leaves_no_claim_behind() {
+        let path = temp_path("claimed.lock");
+
+        let guard = acquire_lock_at(&path, OURS, |_| true).unwrap();
+        let claim = temp_path(&format!("claimed.lock.{}", OURS));
+
+        assert!(!claim.exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), OURS.to_string());
+        drop(guard);
+    }
+
+    #[test]
+    fn a_held_lock_leaves_no_claim_behind() {
+        let path = left_behind("contested.lock", &THEIRS.to_string());
+
+        assert!(acquire_lock_at(&path, OURS, |_| true).is_err());
+
+        assert!(!temp_path(&format!("contested.lock.{}", OURS)).exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
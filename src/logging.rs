@@ -0,0 +1,83 @@
+use std::env;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+pub const LOG_ENV: &str = "RADIO_LOG";
+
+/// Writes every enabled record to stderr as `LEVEL target: message`.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "{:<5} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Maps `off`, `error`, `warn`, `info`, `debug` or `trace`, in any case, to
+/// its filter.
+pub fn parse_log_level(level: &str) -> Option<LevelFilter> {
+    match level.trim().to_lowercase().as_str() {
+        "off" => Some(LevelFilter::Off),
+        "error" => Some(LevelFilter::Error),
+        "warn" | "warning" => Some(LevelFilter::Warn),
+        "info" => Some(LevelFilter::Info),
+        "debug" => Some(LevelFilter::Debug),
+        "trace" => Some(LevelFilter::Trace),
+        _ => None,
+    }
+}
+
+/// Warnings and errors only, or everything down to debug with `--verbose`.
+/// A valid `RADIO_LOG` wins over both.
+pub fn log_level(verbose: bool) -> LevelFilter {
+    env::var(LOG_ENV)
+        .ok()
+        .and_then(|level| parse_log_level(&level))
+        .unwrap_or(if verbose {
+            LevelFilter::Debug
+        } else {
+            LevelFilter::Warn
+        })
+}
+
+pub fn init_logging(verbose: bool) {
+    static LOGGER: StderrLogger = StderrLogger;
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(log_level(verbose));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_names_map_to_their_filter_in_any_case() {
+        assert_eq!(parse_log_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_log_level("ERROR"), Some(LevelFilter::Error));
+        assert_eq!(parse_log_level("Warning"), Some(LevelFilter::Warn));
+        assert_eq!(parse_log_level(" info\n"), Some(LevelFilter::Info));
+        assert_eq!(parse_log_level("debug"), Some(LevelFilter::Debug));
+        assert_eq!(parse_log_level("trace"), Some(LevelFilter::Trace));
+    }
+
+    #[test]
+    fn unknown_levels_dont_parse() {
+        assert_eq!(parse_log_level(""), None);
+        assert_eq!(parse_log_level("chicken nuggies"), None);
+        assert_eq!(parse_log_level("3"), None);
+    }
+}
@@ -1,148 +1,2059 @@
+mod blocked;
+mod cache;
+mod cli;
+mod columns;
+mod config;
+mod control;
+mod crossfade;
+mod custom;
+mod dataset;
+mod debounce;
+mod density;
+mod details;
+mod equalizer;
+mod favicon;
+mod favorites;
+mod filename;
+mod filter;
+mod geo;
+mod history;
+mod hls;
+mod homepage;
+mod lock;
+mod logging;
+mod mirrors;
+mod playback;
+mod playlist;
+mod probe;
+mod reconnect;
+mod recording;
+mod remote;
+mod retry;
+mod scrobble;
+mod searches;
+mod settings;
+mod share;
+mod shutdown;
+mod sleep_timer;
+mod spinner;
+mod stations;
+mod theme;
+mod typeahead;
+mod volume;
+mod votes;
+mod window_geometry;
+
 use std::{
+    collections::HashSet,
+    env,
     error::Error,
+    ffi::{c_void, CStr},
     fmt::Debug,
     fs::File,
-    io::{Read, Write},
-    path::Path,
+    future::Future,
+    io::{self, Read, Write},
+    mem,
+    ops::Range,
+    path::{Path, PathBuf},
+    process,
+    sync::mpsc,
     thread::{self},
+    time::{Duration, Instant},
 };
 
 use async_std::task::{self};
+use blocked::{is_blocked, load_blocked, save_blocked, without_blocked};
+use cache::{
+    append_cache_journal, apply_cache_changes, cache_is_fresh, cache_ttl, clear_cache,
+    diff_station_cache, fetched_positions, journal_is_full, journal_path, load_cached_stations,
+    merge_station_cache, read_cache_journal, read_cache_meta, remove_if_present, write_atomically,
+    write_cache_meta, write_cache_timestamp, CacheChange, CACHE_META_PATH,
+};
+use chrono::Local;
+use cli::{parse_args, USAGE};
+use columns::{column_widths, columns, parse_columns, set_columns};
+use config::{Config, CONFIG_PATH};
+use control::{serve, ControlRequest, ControlResponse};
+use crossfade::{hand_over, Crossfade, CROSSFADE_STEP};
+use custom::{add_custom_station, is_custom, load_custom_stations, save_custom_stations};
+use dataset::export_dataset;
+use debounce::Debouncer;
+use density::{apply_density, Density};
+use details::render_station_details;
+use equalizer::{apply_equalizer, equalizer_supported, PRESETS};
+use favicon::{cached_favicon, fetch_favicon, prefetch_jobs, FaviconPrefetcher, FAVICON_DIR};
+use favorites::{load_favorites, save_favorites, toggle_favorite};
+use filter::{
+    filter_stations, format_count, parse_query, FilterCache, FilterState, SearchFields,
+    StationQuery,
+};
 use fltk::{
-    app::{self, Receiver, Sender},
+    app::{self, Sender},
     browser::{Browser, BrowserType},
-    button::Button,
-    enums::{Align, CallbackTrigger, Color, FrameType, LabelType},
+    button::{Button, ToggleButton},
+    dialog,
+    enums::{Align, CallbackTrigger, Color, Event, FrameType, Key, LabelType, Shortcut},
     frame::Frame,
+    image::SharedImage,
     input::Input,
-    prelude::{BrowserExt, GroupExt, InputExt, WidgetBase, WidgetExt},
-    text::SimpleTerminal,
+    menu::{Choice, MenuButton, MenuFlag},
+    prelude::{
+        BrowserExt, ButtonExt, DisplayExt, GroupExt, ImageExt, InputExt, MenuExt, ValuatorExt,
+        WidgetBase, WidgetExt,
+    },
+    text::{SimpleTerminal, TextBuffer, TextDisplay, WrapMode},
+    valuator::HorSlider,
     window::{DoubleWindow, Window},
 };
-use json::{JsonError, JsonValue};
-use radiobrowser::{ApiStation, RadioBrowserAPI};
-use vlc::{Instance, Media, MediaPlayer};
+use geo::parse_coordinates;
+use history::{history_size, History};
+use hls::{fetch_hls_variants, HlsVariant};
+use homepage::{is_web_url, open_url};
+use json::JsonValue;
+use lock::acquire_lock;
+use log::{debug, error, info, trace, warn};
+use logging::init_logging;
+use mirrors::{mirrors, with_failover};
+use playback::{
+    add_media_option, build_media, network_caching_option, playback_time, playback_time_label,
+    playback_url, player_status, toggle_pause, vlc_available, ActivePlayer, PlayError,
+    PlaybackClock, DEFAULT_NETWORK_CACHING_MS, PLAYING_STATUS, VLC_MISSING,
+};
+use playlist::{export_m3u, import_playlist};
+use probe::{probe_stream, PROBE_TIMEOUT};
+use radiobrowser::{ApiStation, RadioBrowserAPI, StationOrder};
+use reconnect::{ReconnectPolicy, RetryState};
+use recording::{recording_filename, sout_option, unique_path};
+use remote::{refresh_station, search_stations_remote, RefreshError, RemoteQuery};
+use retry::{backoff_schedule, is_transient, with_deadline, with_jitter, RETRY_BASE_DELAY};
+use scrobble::{log_scrobble, ScrobbleEvent};
+use searches::{load_searches, push_search, save_searches};
+use settings::{open_settings_window, Settings};
+use share::station_share_url;
+use shutdown::{Shutdown, ShutdownStep};
+use sleep_timer::{schedule_sleep_timer, SleepTimer};
+use spinner::Spinner;
+use stations::{
+    distinct_countries, distinct_languages, find_station_by_uuid, group_by_country, is_secure,
+    next_sort_order, pick_random, remove_station, replace_station, same_station, secure_stream_url,
+    sort_stations, top_by, top_tags, SortKey,
+};
+use theme::{apply_theme, Theme};
+use typeahead::{prefix_match, TypeAheadBuffer};
+use vlc::{
+    sys::libvlc_media_t, Event as VlcEvent, EventType, Instance, Media, MediaPlayer,
+    MediaPlayerAudioEx, Meta, VLCObject,
+};
+use volume::{migrate_legacy_volume, MuteState};
+use votes::{vote_for_station, VoteTracker};
+use window_geometry::{restore_window_geometry, save_window_geometry};
 
-#[derive(Debug, Clone, Copy)]
+const CACHE_PATH: &str = "stations.json";
+/// Width of the station details pane right of the browser.
+const DETAILS_WIDTH: i32 = 180;
+const FAVICON_SIZE: i32 = 64;
+const DO_NOT_TRACK_ENV: &str = "RRADIO_DO_NOT_TRACK";
+/// How long the search input has to be idle before the list is refiltered.
+const SEARCH_DEBOUNCE_SECS: f64 = 0.25;
+/// How many stations the Popular and Trending lists show.
+const TOP_COUNT: usize = 100;
+/// Stations asked for per request, so the list fills in as pages arrive.
+const FETCH_BATCH_SIZE: u32 = 100;
+/// Tries per station fetch before giving up on network errors.
+const FETCH_ATTEMPTS: u32 = 4;
+
+#[derive(Debug, Clone)]
 pub enum Message {
     FetchStations,
-    StationsFetchedSuccess,
+    StationsFetchedSuccess {
+        stations: Vec<ApiStation>,
+        offset: u32,
+        /// Fewer stations than asked for came back, so these are all there are.
+        complete: bool,
+    },
     FilterStations,
+    StationSelected,
+    /// The icon of the station with `uuid` was fetched, or couldn't be.
+    FaviconReady {
+        uuid: String,
+        path: Option<PathBuf>,
+    },
+    OpenHomepage,
+    /// Copies a link to the selected station.
+    ShareStation,
+    /// The rows in view changed, so their icons are fetched ahead.
+    PrefetchFavicons,
+    /// Looks the selected station up again for a fresh stream url.
+    RefreshStation,
+    StationRefreshed {
+        uuid: String,
+        result: Result<ApiStation, RefreshError>,
+    },
+    /// The qualities offered by the HLS station with `uuid`, best first.
+    HlsVariants {
+        uuid: String,
+        variants: Vec<HlsVariant>,
+    },
+    Vote,
+    /// radiobrowser answered the vote for the station with `uuid`.
+    VoteResult {
+        uuid: String,
+        result: Result<(), String>,
+    },
+    ExportPlaylist,
+    /// Writes every loaded station and where it was fetched from as JSON.
+    ExportDataset,
+    OpenSettings,
+    /// The settings window was saved with these.
+    SettingsSaved(Settings),
+    ToggleRecording,
+    PlayRandom,
+    PlayNext,
+    PlayPrevious,
+    ImportPlaylist,
+    AddCustomStation,
+    ClearCache,
     PlayRequest,
+    /// The probe of `station` before playing it finished.
+    ProbeFinished {
+        station: ApiStation,
+        reachable: bool,
+    },
+    /// Starts `station` right away, without probing it first.
+    PlayStation(ApiStation),
+    /// Plays the loaded station with this uuid, as asked over http.
+    PlayUuid(String),
+    /// The http api asks what is playing, as JSON sent back on the channel.
+    ReportNowPlaying(mpsc::Sender<String>),
     PauseRequest,
+    StopRequest,
+    VolumeChanged,
+    /// The player started, so vlc accepts volume changes now.
+    PlayerStarted,
+    /// The stream ended or failed while playing.
+    StreamDropped,
+    Reconnect,
+    ToggleMute,
+    ToggleFavorite,
+    /// Hides the selected station for good.
+    BlockStation,
+    UnblockAll,
+    ShowFavorites,
+    ClickReportFailed,
+    NowPlaying(String),
+    /// vlc is connecting, buffering or playing, in words for the status line.
+    PlayerState(String),
+    SortBy(SortKey),
+    ShowHistory,
+    ShowPopular,
+    ShowTrending,
+    FocusSearch,
+    /// Enter was pressed in the search or the search button clicked, so the
+    /// query is worth remembering.
+    SubmitSearch,
+    /// The remembered search at this index was picked.
+    PickSearch(usize),
+    LoadMoreStations,
+    SleepTimerChanged,
+    EqualizerChanged,
+    ToggleTheme,
+    ToggleDensity,
+    TogglePreferSecure,
+    /// A second of playback went by.
+    PlaybackTick,
+    /// Time for the next step of a crossfade.
+    CrossfadeTick,
+    /// The window was closed.
+    Quit,
+    SleepTimerTick(u64),
+    FetchFailed(String),
+    /// A page of the fetch starting at `offset`, ahead of its final
+    /// `StationsFetchedSuccess` carrying every page.
+    StationsBatch {
+        stations: Vec<ApiStation>,
+        offset: u32,
+    },
+    /// The station cache was read off the ui thread.
+    CacheLoaded(Result<Vec<ApiStation>, String>),
+    /// A fetch was merged into the cache, which was written unless that failed.
+    CacheStored {
+        stations: Vec<ApiStation>,
+        result: Result<(), String>,
+    },
+    /// Fetching failed for now; attempt number `attempt` follows after `delay`.
+    FetchRetry {
+        attempt: u32,
+        delay: Duration,
+    },
+    FetchedFromMirror(String),
+    /// Results of server search number `id`; only the latest one is shown.
+    RemoteSearchResults {
+        id: u64,
+        result: Result<Vec<ApiStation>, String>,
+    },
+}
+
+/// Keeps the active vlc player alive across event loop iterations.
+/// There is at most one player, and every player shares the same instance.
+struct PlayerState {
+    instance: Option<Instance>,
+    player: Option<MediaPlayer>,
+    /// A second, silent player dumping a stream to disk.
+    recorder: Option<MediaPlayer>,
+    /// The level sent to vlc, 0 while muted.
+    volume: i32,
+    mute: MuteState,
+    /// The equalizer preset every new player starts with.
+    equalizer: String,
+    /// How many milliseconds of every stream vlc buffers.
+    network_caching_ms: u32,
+    /// How long switching stations fades from one to the next, 0 to cut.
+    crossfade: Duration,
+    /// The station played before, until it has faded out.
+    fading: Option<Crossfade>,
+}
+
+impl PlayerState {
+    fn new(volume: i32) -> Self {
+        PlayerState {
+            instance: None,
+            player: None,
+            recorder: None,
+            volume,
+            mute: MuteState::default(),
+            equalizer: "Flat".to_string(),
+            network_caching_ms: DEFAULT_NETWORK_CACHING_MS,
+            crossfade: Duration::ZERO,
+            fading: None,
+        }
+    }
+
+    /// The shared vlc instance, created on first use.
+    fn instance(&mut self) -> Option<&Instance> {
+        if self.instance.is_none() {
+            self.instance = Instance::new();
+        }
+        self.instance.as_ref()
+    }
+
+    /// Creates a player with the stream of `station` loaded on the shared instance.
+    fn try_play(&mut self, station: &ApiStation) -> Result<ActivePlayer, PlayError> {
+        let network_caching_ms = self.network_caching_ms;
+        let instance = self.instance().ok_or(PlayError::NoVlc)?;
+        let media = build_media(instance, station, network_caching_ms)?;
+        let player = MediaPlayer::new(instance).ok_or(PlayError::NoPlayer)?;
+
+        Ok(ActivePlayer { player, media })
+    }
+
+    /// Starts playing `station` on a new player in place of the active one,
+    /// which fades out if a crossfade is set. Fading is stepped along by
+    /// `crossfade_step`. If the new stream won't start, the active one keeps
+    /// playing.
+    fn play(
+        &mut self,
+        station: &ApiStation,
+        play_button: &Button,
+        tx_message: &Sender<Message>,
+    ) -> Result<(), PlayError> {
+        self.end_crossfade();
+        let started = self
+            .try_play(station)
+            .and_then(|ActivePlayer { player, media }| {
+                init_player(
+                    play_button.clone(),
+                    tx_message.clone(),
+                    &player,
+                    media,
+                    self.volume,
+                )?;
+                Ok(player)
+            });
+        let player = started?;
+        apply_equalizer(&player, &self.equalizer);
+        hand_over(&mut self.player, &mut self.fading, player, self.crossfade);
+
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.player.is_some()
+    }
+
+    fn is_crossfading(&self) -> bool {
+        self.fading.is_some()
+    }
+
+    /// Moves a running crossfade one step along. Returns false once it is
+    /// done, or if there was none.
+    fn crossfade_step(&mut self) -> bool {
+        let (Some(fading), Some(player)) = (self.fading.as_mut(), self.player.as_ref()) else {
+            self.end_crossfade();
+            return false;
+        };
+        if fading.advance(player, self.volume) {
+            return true;
+        }
+        self.fading = None;
+
+        false
+    }
+
+    /// Cuts a running crossfade short, stopping the station fading out and
+    /// giving the new one its full volume.
+    fn end_crossfade(&mut self) {
+        if let Some(fading) = self.fading.take() {
+            fading.finish();
+            self.apply_volume();
+        }
+    }
+
+    /// Switches to the preset called `name`, on the active player too.
+    fn set_equalizer(&mut self, name: &str) {
+        self.equalizer = name.to_string();
+        if let Some(player) = &self.player {
+            apply_equalizer(player, name);
+        }
+    }
+
+    /// Starts writing the stream at `url` to `out`, replacing any running recording.
+    fn start_recording(&mut self, url: &str, out: &Path) -> Result<(), String> {
+        self.stop_recording();
+        let caching = network_caching_option(self.network_caching_ms);
+        let instance = self.instance().ok_or("couldn't initialize vlc")?;
+        let media = Media::new_location(instance, url).ok_or("couldn't open the stream")?;
+        if !add_media_option(&media, &caching) || !add_media_option(&media, &sout_option(out)) {
+            return Err("couldn't set up the recording".to_string());
+        }
+        let recorder = MediaPlayer::new(instance).ok_or("couldn't start a vlc player")?;
+        recorder.set_media(&media);
+        recorder
+            .play()
+            .map_err(|_| "couldn't start recording".to_string())?;
+        self.recorder = Some(recorder);
+
+        Ok(())
+    }
+
+    fn stop_recording(&mut self) -> bool {
+        match self.recorder.take() {
+            Some(recorder) => {
+                recorder.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How far the active player got and how long its media is, in ms.
+    fn playback_time(&self) -> Option<(i64, i64)> {
+        self.player.as_ref().map(playback_time)
+    }
+
+    /// Toggles pause on the active player and returns whether it is now playing.
+    fn toggle_pause(&self) -> Option<bool> {
+        self.player.as_ref().map(toggle_pause)
+    }
+
+    /// Stops playback and drops the player, releasing its media.
+    fn stop(&mut self) -> bool {
+        self.end_crossfade();
+        match self.player.take() {
+            Some(player) => {
+                player.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops playback and recording and lets go of vlc, whose threads end
+    /// with the instance.
+    fn shut_down(&mut self) {
+        self.stop_recording();
+        self.stop();
+        self.instance = None;
+    }
+
+    /// Sets the level picked on the slider, unmuting if muted.
+    fn set_volume(&mut self, volume: i32) {
+        let (mute, volume) = self.mute.volume_changed(volume.clamp(0, 100));
+        self.mute = mute;
+        self.volume = volume;
+        self.apply_volume();
+    }
+
+    /// The level the user picked, even while muted.
+    fn chosen_volume(&self) -> i32 {
+        match self.mute {
+            MuteState::Muted { previous } => previous,
+            MuteState::Unmuted => self.volume,
+        }
+    }
+
+    /// Mutes or unmutes without touching playback, and returns whether it is now muted.
+    fn toggle_mute(&mut self) -> bool {
+        let (mute, volume) = self.mute.toggle(self.volume);
+        self.mute = mute;
+        self.volume = volume;
+        self.apply_volume();
+
+        self.mute.is_muted()
+    }
+
+    /// vlc rejects volume changes until the media is actually playing,
+    /// so the level is only pushed to a running player.
+    fn apply_volume(&self) {
+        if let Some(player) = &self.player {
+            if player.is_playing() {
+                let _ = player.set_volume(self.volume);
+            }
+        }
+    }
 }
 
 #[async_std::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = match parse_args(env::args().skip(1)) {
+        Ok(cli) if cli.help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Ok(cli) => cli,
+        Err(why) => {
+            eprintln!("{}\n\n{}", why, USAGE);
+            process::exit(2);
+        }
+    };
+    init_logging(cli.verbose);
     let app = app::App::default();
-    let mut win = Window::default().with_size(640, 480);
+    // Held until main returns, when the lock file goes with it.
+    let _lock = match acquire_lock() {
+        Ok(lock) => lock,
+        Err(why) => {
+            eprintln!("{}", why);
+            dialog::alert_default(&why.to_string());
+            process::exit(1);
+        }
+    };
+    let mut config = Config::load();
+    if let Err(why) = migrate_legacy_volume(&mut config) {
+        warn!("couldn't move the volume into {}: {}", CONFIG_PATH, why);
+    }
+    let offline = config.offline || cli.offline;
+    if !Path::new(CONFIG_PATH).exists() {
+        // Leave a config with every default in place so it is easy to edit.
+        if let Err(why) = config.save() {
+            warn!("couldn't write {}: {}", CONFIG_PATH, why);
+        }
+    }
+    let mut win = Window::default().with_size(800, 480);
     win.make_resizable(true);
     let mut frame = Frame::default()
         .with_size(win.width(), win.height())
         .center_of_parent();
     frame.set_frame(FrameType::FlatBox);
-    frame.set_color(Color::Black);
 
     let (tx_message, rx_message) = app::channel::<Message>();
+    if cli.serve {
+        let tx_control = tx_message.clone();
+        let served = serve(&config.control_host, config.control_port, move |request| {
+            control_response(&tx_control, request)
+        });
+        match served {
+            Ok(_) => info!(
+                "remote control on http://{}:{}",
+                config.control_host, config.control_port
+            ),
+            Err(why) => error!("couldn't serve remote control: {}", why),
+        }
+    }
 
-    let mut browser = build_browser(&win);
+    set_columns(parse_columns(&config.columns));
+    let mut browser = build_browser(&win, config.density);
     browser.set_type(BrowserType::Hold);
     browser.add("no stations to display");
-    browser.set_selection_color(Color::Magenta);
     browser.set_label_type(LabelType::Shadow);
     browser.set_label_color(Color::Black);
 
-    let (mut search_input, mut search_button) = build_search(&win);
+    let mut favicon_frame = Frame::new(
+        win.width() - DETAILS_WIDTH,
+        40,
+        DETAILS_WIDTH,
+        FAVICON_SIZE,
+        "",
+    );
+    // Mid gray stays readable on either theme.
+    favicon_frame.set_label_color(Color::from_rgb(128, 128, 128));
+    let mut details_buffer = TextBuffer::default();
+    let mut details = TextDisplay::new(
+        win.width() - DETAILS_WIDTH,
+        40 + FAVICON_SIZE,
+        DETAILS_WIDTH,
+        browser.height() - 180 - FAVICON_SIZE,
+        "",
+    );
+    details.set_buffer(details_buffer.clone());
+    details.wrap_mode(WrapMode::AtBounds, 0);
+    let mut time_frame = Frame::new(
+        details.x(),
+        details.y() + details.h(),
+        DETAILS_WIDTH,
+        20,
+        "",
+    );
+    time_frame.set_label_color(Color::from_rgb(128, 128, 128));
+    time_frame.set_tooltip("Time played, and the length of recordings and other finite streams");
+    let mut quality_choice = build_quality_choice(&time_frame);
+    let equalizer_choice = build_equalizer_choice(&quality_choice, &config.equalizer, &tx_message);
+    let bitrate_slider = build_bitrate_slider(&equalizer_choice, &tx_message);
+    let mut count_frame = Frame::new(
+        details.x(),
+        bitrate_slider.y() + bitrate_slider.h(),
+        DETAILS_WIDTH,
+        30,
+        "",
+    );
+    let mut homepage_button = Button::new(
+        details.x(),
+        count_frame.y() + count_frame.h(),
+        65,
+        40,
+        "Homepage",
+    );
+    homepage_button.set_tooltip("Open the selected station's homepage in the browser");
+    homepage_button.emit(tx_message.clone(), Message::OpenHomepage);
+    homepage_button.deactivate();
+    let mut vote_button = Button::new(
+        details.x() + 65,
+        count_frame.y() + count_frame.h(),
+        60,
+        40,
+        "👍 Vote",
+    );
+    vote_button.set_tooltip("Vote for the selected station on radiobrowser");
+    vote_button.emit(tx_message.clone(), Message::Vote);
+    let mut record_button = Button::new(
+        details.x() + 125,
+        count_frame.y() + count_frame.h(),
+        DETAILS_WIDTH - 125,
+        40,
+        "Record",
+    );
+    record_button.set_tooltip("Record the playing stream to a file");
+    record_button.emit(tx_message.clone(), Message::ToggleRecording);
+    // After the details column, which lines up with the full height list.
+    let vlc_found = vlc_available();
+    if !vlc_found {
+        warn!("libvlc couldn't be started, playback is disabled");
+        show_vlc_missing_banner(&mut browser);
+    }
+    let mut tag_chips = build_tag_chips(&mut browser, &tx_message);
+    let mut column_headers = build_column_headers(&mut browser, &tx_message);
+
+    let (mut search_input, mut search_button, mut recent_searches) = build_search(&win);
+    let view_menu = build_view_menu(&win, &tx_message, &config);
+    build_file_menu(&win, &tx_message);
+    let mut country_choice = build_country_choice(&win);
+    let mut language_choice = build_language_choice(&win);
 
     let mut status = SimpleTerminal::default()
         .with_align(Align::Right)
-        .with_size(win.width() - 40, 40)
+        .with_size(win.width() - 620, 40)
         .below_of(&browser, 0);
-    status.set_pos(40, status.y());
+    status.set_pos(450, status.y());
+    status.set_ansi(true);
+    apply_theme(
+        &config.theme,
+        &mut frame,
+        &mut browser,
+        &mut details,
+        &mut count_frame,
+        &mut status,
+    );
+
+    let mut retry_button = build_control_button(&browser, win.width() - 230, 60, "Retry");
+    retry_button.set_tooltip("Fetch the station list again");
+    retry_button.emit(tx_message.clone(), Message::FetchStations);
+    retry_button.hide();
 
-    let mut play_button = Button::new(win.width() - 40, 0, 40, 40, ">").below_of(&browser, 0);
+    let mut volume_slider = build_volume_slider(&win, &browser, config.volume.clamp(0, 100));
+    let mut mute_button = build_control_button(&browser, win.width() - 100, 30, "🔊");
+    mute_button.set_tooltip("Mute or unmute");
+    mute_button.emit(tx_message.clone(), Message::ToggleMute);
+    let mut sleep_choice = build_sleep_choice(&win, &browser);
+
+    let mut play_button = build_control_button(&browser, 0, 40, ">");
     let tx_message_clone = tx_message.clone();
     play_button.set_callback(move |_| tx_message_clone.send(Message::PauseRequest));
+    play_button.set_tooltip("Play/pause (Space)\nStop (Esc)\nFocus search (/)");
+
+    let mut stop_button = build_control_button(&browser, 40, 40, "■");
+    stop_button.emit(tx_message.clone(), Message::StopRequest);
+
+    let mut favorite_button = build_control_button(&browser, 80, 40, "★");
+    favorite_button.set_tooltip("Add or remove the selected station from favorites");
+    favorite_button.emit(tx_message.clone(), Message::ToggleFavorite);
+
+    let mut favorites_button = build_control_button(&browser, 120, 50, "Favs");
+    favorites_button.set_tooltip("Show favorite stations");
+    favorites_button.emit(tx_message.clone(), Message::ShowFavorites);
+
+    let mut history_button = build_control_button(&browser, 170, 50, "Hist");
+    history_button.set_tooltip("Show recently played stations");
+    history_button.emit(tx_message.clone(), Message::ShowHistory);
+
+    let mut more_button = build_control_button(&browser, 220, 50, "More");
+    more_button.set_tooltip("Load the next page of stations");
+    more_button.emit(tx_message.clone(), Message::LoadMoreStations);
+
+    let mut random_button = build_control_button(&browser, 270, 40, "Rnd");
+    random_button.set_tooltip("Play a random station from the list");
+    random_button.emit(tx_message.clone(), Message::PlayRandom);
+
+    let mut previous_button = build_control_button(&browser, 310, 30, "⏮");
+    previous_button.set_tooltip("Play the previous station in the list");
+    previous_button.emit(tx_message.clone(), Message::PlayPrevious);
+
+    let mut next_button = build_control_button(&browser, 340, 30, "⏭");
+    next_button.set_tooltip("Play the next station in the list");
+    next_button.emit(tx_message.clone(), Message::PlayNext);
+
+    let mut popular_button = build_control_button(&browser, 370, 40, "Top");
+    popular_button.set_tooltip("Popular: the most voted stations");
+    popular_button.emit(tx_message.clone(), Message::ShowPopular);
+
+    let mut trending_button = build_control_button(&browser, 410, 40, "Hot");
+    trending_button.set_tooltip("Trending: the stations gaining the most clicks");
+    trending_button.emit(tx_message.clone(), Message::ShowTrending);
+
+    if !vlc_found {
+        for button in [
+            &mut play_button,
+            &mut record_button,
+            &mut random_button,
+            &mut previous_button,
+            &mut next_button,
+        ] {
+            button.deactivate();
+        }
+    }
 
-    search_input.emit(tx_message, Message::FilterStations);
-    search_button.emit(tx_message, Message::FilterStations);
-    browser.emit(tx_message, Message::PlayRequest);
+    debounce_search(&mut search_input, tx_message.clone());
+    search_button.emit(tx_message.clone(), Message::SubmitSearch);
+    country_choice.emit(tx_message.clone(), Message::FilterStations);
+    language_choice.emit(tx_message.clone(), Message::FilterStations);
+    let tx_message_clone = tx_message.clone();
+    browser.set_callback(move |_| {
+        if app::event_clicks() {
+            tx_message_clone.send(Message::PlayRequest);
+        } else {
+            tx_message_clone.send(Message::StationSelected);
+        }
+    });
+    navigate_with_keys(&mut browser, tx_message.clone());
+    volume_slider.emit(tx_message.clone(), Message::VolumeChanged);
+    sleep_choice.emit(tx_message.clone(), Message::SleepTimerChanged);
+
+    let search_input_ref = search_input.clone();
+    let tx_message_clone = tx_message.clone();
+    win.handle(move |_, event| match event {
+        Event::KeyDown => match shortcut_message(app::event_key(), search_input_ref.has_focus()) {
+            Some(msg) => {
+                tx_message_clone.send(msg);
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    });
+
+    let mut browser_ref = browser.clone();
+    let mut column_headers_ref = column_headers.clone();
+    win.resize_callback(move |_, _, _, _, _| {
+        browser_ref.set_column_widths(&column_widths(columns(), browser_ref.w()));
+        layout_column_headers(&mut column_headers_ref, &browser_ref);
+    });
+
+    let tx_message_clone = tx_message.clone();
+    win.set_callback(move |_| {
+        if app::event() == Event::Close {
+            tx_message_clone.send(Message::Quit);
+        }
+    });
 
     win.end();
+    restore_window_geometry(&mut win);
     win.show();
+    // The dropdowns are restored once there are stations to fill them.
+    let mut pending_filter =
+        Some(config.filter.clone()).filter(|filter| *filter != FilterState::default());
+    if let Some(query) = cli
+        .search
+        .as_ref()
+        .or(pending_filter.as_ref().map(|f| &f.search))
+    {
+        search_input.set_value(query);
+    }
+    if cli.refresh && !offline {
+        tx_message.send(Message::FetchStations);
+    }
+    if !offline {
+        watch_browser_view(&browser, tx_message.clone());
+    }
+    // `--play` picks the station to start with instead.
+    let mut pending_autoplay = Some(config.last_station.clone())
+        .filter(|uuid| config.autoplay_last && cli.play.is_none() && !uuid.is_empty());
+    if offline
+        || cli.search.is_some()
+        || cli.play.is_some()
+        || cli.refresh
+        || pending_autoplay.is_some()
+        || pending_filter.is_some()
+    {
+        tx_message.send(Message::FilterStations);
+    }
 
     let all_stations: &mut Option<Vec<ApiStation>> = &mut None;
+    let mut player_state = PlayerState::new(volume_slider.value() as i32);
+    player_state.set_equalizer(&config.equalizer);
+    player_state.network_caching_ms = config.network_caching_ms;
+    player_state.crossfade = Duration::from_millis(config.crossfade_ms);
+    let mut crossfade_timer: Option<app::TimeoutHandle> = None;
+    let mut favorites = load_favorites();
+    let mut blocked = load_blocked();
+    let mut votes = VoteTracker::default();
+    let mut sort_order: Option<(SortKey, bool)> = None;
+    let mut filter_cache = FilterCache::default();
+    // Where the last fetch came from, recorded with the cache.
+    let mut fetched_mirror: Option<String> = None;
+    let favicon_prefetcher = FaviconPrefetcher::default();
+    let mut countries: Vec<String> = vec![];
+    let mut languages: Vec<String> = vec![];
+    let mut history = History::load(history_size());
+    let mut searches = load_searches();
+    let mut custom_stations = load_custom_stations();
+    fill_recent_searches(&mut recent_searches, &searches, &tx_message);
+    let mut sleep_timer: Option<SleepTimer> = None;
+    // Set while a fetch is in flight, so at most one runs at a time.
+    let mut fetch_spinner: Option<Spinner> = None;
+    // A fresh list replaces a stale cache even if writing the cache failed.
+    let mut fetched_this_run = false;
+    let mut shutdown = Shutdown::default();
+    // Pages of the running fetch, with the offset it started at.
+    let mut streaming: Option<(u32, Vec<ApiStation>)> = None;
+    // The station whose stream is being probed before it plays.
+    let mut probing: Option<String> = None;
+    // Lines of the country headers while grouped, and the folded countries.
+    let mut group_headers: Vec<(i32, String)> = vec![];
+    let mut collapsed_countries: HashSet<String> = HashSet::new();
+    // A cache read or write is in flight.
+    let mut cache_busy = false;
+    // Station refreshes that came in meanwhile, to apply once it is done.
+    let mut pending_cache_changes: Vec<CacheChange> = vec![];
+    let mut cache_unreadable = false;
+    // What to reconnect to when the stream drops, and what gets recorded.
+    let mut playing_station: Option<ApiStation> = None;
+    let mut retry_state = RetryState::new(ReconnectPolicy::from_env());
+    let mut reconnect_timeout: Option<app::TimeoutHandle> = None;
+    let mut playback_clock: Option<PlaybackClock> = None;
+    let mut remote_search_id: u64 = 0;
+    // The `--play` station, played as soon as it shows up in the list.
+    let mut pending_play = cli.play.clone();
+    // The qualities offered by the selected station, with its uuid.
+    let mut hls_variants: Option<(String, Vec<HlsVariant>)> = None;
+    // What the status line goes back to once connecting or buffering is over.
+    let mut now_playing_text = String::new();
 
     while app.wait() {
         if let Some(msg) = rx_message.recv() {
             match msg {
+                Message::FetchStations | Message::LoadMoreStations if offline => {
+                    status.set_text("Offline mode, not fetching stations");
+                }
                 Message::FetchStations => {
-                    spawn_fetch_thread(&browser, &tx_message, all_stations);
-                    if all_stations.is_some() {
-                        status.set_text(&format!(
-                            "Successfully fetched {} stations",
-                            &all_stations.clone().unwrap().len(),
-                        ));
+                    if fetch_spinner.is_none() {
+                        fetch_spinner = Some(Spinner::start(&status, "Fetching…"));
+                        spawn_fetch_thread(
+                            tx_message.clone(),
+                            config.fetch_limit,
+                            0,
+                            Duration::from_secs(config.fetch_timeout_secs),
+                        );
                     }
                 }
-                Message::FilterStations => {
-                    if all_stations.is_none() {
-                        if !is_cache_present() {
-                            spawn_fetch_thread(&browser, &tx_message, all_stations);
-                            let json_vec = all_stations
-                                .unwrap()
-                                .into_iter()
-                                .map(|e| station_to_json(e).unwrap())
-                                .collect::<Vec<_>>();
-                            write_data_to_file(serde_json::to_string(&json_vec));
-                            fill_station_browser(&browser, all_stations);
+                Message::AddCustomStation => {
+                    let Some(name) = dialog::input_default("Name of the station:", "") else {
+                        continue;
+                    };
+                    let Some(url) = dialog::input_default("Url of its stream:", "http://") else {
+                        continue;
+                    };
+                    match add_custom_station(&name, &url) {
+                        Ok(station) => {
+                            status.set_text(&format!("Added {}", station.name));
+                            custom_stations.push(station);
+                            if let Err(why) = save_custom_stations(&custom_stations) {
+                                error!("couldn't save the custom stations: {}", why);
+                                status.set_text(&format!(
+                                    "\x1b[31mcouldn't save the custom stations: {}\x1b[0m",
+                                    why
+                                ));
+                            }
+                            tx_message.send(Message::FilterStations);
+                        }
+                        Err(why) => dialog::alert_default(&why),
+                    }
+                }
+                Message::ClearCache => {
+                    if cache_busy {
+                        status.set_text("The station cache is in use, try again in a moment");
+                        continue;
+                    }
+                    let confirmed = dialog::choice2_default(
+                        "Delete the cached stations and fetch them again?",
+                        "Cancel",
+                        "Clear",
+                        "",
+                    ) == Some(1);
+                    if !confirmed {
+                        continue;
+                    }
+                    match clear_cache() {
+                        Ok(()) => {
+                            info!("cleared the station cache");
+                            cache_unreadable = false;
+                            if offline {
+                                status.set_text("Cleared the station cache; connect to fetch");
+                            } else {
+                                status.set_text("Cleared the station cache");
+                                tx_message.send(Message::FetchStations);
+                            }
                         }
+                        Err(why) => {
+                            error!("couldn't clear the station cache: {}", why);
+                            status.set_text(&format!(
+                                "\x1b[31mcouldn't clear the station cache: {}\x1b[0m",
+                                why
+                            ));
+                        }
+                    }
+                }
+                Message::LoadMoreStations => {
+                    if fetch_spinner.is_none() {
+                        let offset = all_stations.as_ref().map_or(0, Vec::len) as u32;
+                        fetch_spinner = Some(Spinner::start(&status, "Fetching…"));
+                        spawn_fetch_thread(
+                            tx_message.clone(),
+                            config.fetch_limit,
+                            offset,
+                            Duration::from_secs(config.fetch_timeout_secs),
+                        );
+                    }
+                }
+                Message::FilterStations
+                    if !offline && menu_toggled(&view_menu, SERVER_SEARCH_ITEM) =>
+                {
+                    let query = current_query(
+                        &search_input,
+                        (&country_choice, &countries),
+                        (&language_choice, &languages),
+                        &bitrate_slider,
+                        &view_menu,
+                        &blocked,
+                        &tag_chips,
+                    );
+                    let name = query.text.join(" ");
+                    let tag = query.tags.first().cloned().unwrap_or_default();
+                    let country = query.exact_country.or(query.country).unwrap_or_default();
+                    if RemoteQuery::new(&name, &tag, &country).is_empty() {
+                        status.set_text("Type a name, tag: or country: to search the server");
+                        continue;
+                    }
+                    remote_search_id += 1;
+                    status.set_text("Searching the server…");
+                    spawn_remote_search(
+                        tx_message.clone(),
+                        remote_search_id,
+                        name,
+                        tag,
+                        country,
+                        config.fetch_limit,
+                    );
+                }
+                Message::RemoteSearchResults { id, result } => {
+                    if id != remote_search_id {
+                        continue;
+                    }
+                    match result {
+                        Ok(stations) => {
+                            status.set_text(&format!("Server found: {}", stations.len()));
+                            fill_station_browser(
+                                &browser,
+                                &stations,
+                                "No stations on the server match.",
+                            );
+                        }
+                        Err(why) => status
+                            .set_text(&format!("\x1b[31mserver search failed: {}\x1b[0m", why)),
+                    }
+                }
+                Message::FilterStations => {
+                    // Filtering runs again once the cache is read or written.
+                    if cache_busy {
+                        continue;
+                    }
+                    let cache_path = Path::new(CACHE_PATH);
+                    let cache_stale = is_cache_present(cache_path)
+                        && !cache_is_fresh(cache_ttl(config.cache_ttl_hours));
+                    // Offline a stale cache still beats having nothing to browse.
+                    if all_stations.is_none()
+                        && !cache_unreadable
+                        && is_cache_present(cache_path)
+                        && (offline || !cache_stale)
+                    {
+                        cache_busy = true;
+                        spawn_cache_load(tx_message.clone(), cache_path.to_path_buf());
+                        continue;
+                    }
+                    if offline && all_stations.is_none() {
+                        status.set_text("No cached stations; connect to fetch");
+                        continue;
+                    }
+                    if needs_fetch(
+                        offline,
+                        all_stations.is_some(),
+                        cache_stale,
+                        fetched_this_run,
+                    ) && fetch_spinner.is_none()
+                    {
+                        // The results are filtered once they arrive.
+                        fetch_spinner = Some(Spinner::start(&status, "Fetching…"));
+                        spawn_fetch_thread(
+                            tx_message.clone(),
+                            config.fetch_limit,
+                            0,
+                            Duration::from_secs(config.fetch_timeout_secs),
+                        );
+                        continue;
                     }
                     search_input.set_text_color(Color::from_rgba_tuple((255, 255, 255, 50)));
-                    status.set_text(&format!("..."));
-                    let filtered_stations = filter_stations(
-                        &all_stations.clone().unwrap_or(vec![]),
-                        &search_input.value(),
+                    if streaming.is_none() {
+                        if let Some(saved) = pending_filter.take() {
+                            let restored = saved.restore(&countries, &languages);
+                            choose_entry(&mut country_choice, &countries, &restored.country);
+                            choose_entry(&mut language_choice, &languages, &restored.language);
+                        }
+                    }
+                    if pending_filter.is_none() {
+                        let filter = FilterState {
+                            search: search_input.value(),
+                            country: chosen_label(&country_choice, &countries),
+                            language: chosen_label(&language_choice, &languages),
+                        };
+                        if filter != config.filter {
+                            config.filter = filter;
+                            if let Err(why) = config.save() {
+                                warn!("couldn't save the search: {}", why);
+                            }
+                        }
+                    }
+                    let streamed = streaming.as_ref().and_then(|(offset, pages)| {
+                        streamed_view(all_stations.as_deref(), pages, *offset > 0)
+                    });
+                    let stations = streamed
+                        .as_deref()
+                        .or(all_stations.as_deref())
+                        .unwrap_or_default();
+                    let query = current_query(
+                        &search_input,
+                        (&country_choice, &countries),
+                        (&language_choice, &languages),
+                        &bitrate_slider,
+                        &view_menu,
+                        &blocked,
+                        &tag_chips,
                     );
-                    status.set_text(&format!("Found: {}", filtered_stations.len()));
+                    // Custom stations are listed ahead of the fetched ones.
+                    let mut filtered_stations =
+                        filter_stations(&custom_stations, &query, search_fields(&view_menu));
+                    filtered_stations.extend(filter_cache.filter(
+                        stations,
+                        &query,
+                        search_fields(&view_menu),
+                    ));
+                    let total = custom_stations.len() + stations.len();
+                    count_frame.set_label(&format_count(
+                        filtered_stations.len(),
+                        total,
+                        query.is_active(),
+                    ));
 
-                    browser.clear();
-                    for station in filtered_stations.as_slice() {
-                        browser.add_with_data(&format_station(&station), station.clone())
+                    group_headers = if menu_toggled(&view_menu, GROUP_BY_COUNTRY_ITEM)
+                        && !filtered_stations.is_empty()
+                    {
+                        fill_grouped_browser(
+                            &browser,
+                            &group_by_country(&filtered_stations),
+                            &collapsed_countries,
+                        )
+                    } else {
+                        fill_station_browser(
+                            &browser,
+                            &filtered_stations,
+                            empty_list_message(total),
+                        );
+                        vec![]
+                    };
+                    // Pages still streaming in may not hold it yet.
+                    let loaded = all_stations.as_deref().filter(|_| streaming.is_none());
+                    if let Some(stations) = loaded {
+                        if let Some(uuid) = pending_autoplay.take() {
+                            match find_station_by_uuid(stations, &uuid) {
+                                Some(station) => {
+                                    tx_message.send(Message::PlayStation(station.clone()))
+                                }
+                                None => info!("the last played station {} is gone", uuid),
+                            }
+                        }
+                    }
+                    if let Some(uuid) = pending_play.take() {
+                        if select_station(&mut browser, &uuid) {
+                            tx_message.send(Message::StationSelected);
+                            tx_message.send(Message::PlayRequest);
+                        } else {
+                            status.set_text(&format!("No station with uuid {} in the list", uuid));
+                        }
+                    }
+                }
+                Message::StationsBatch { stations, offset } => {
+                    filter_cache.invalidate();
+                    streaming
+                        .get_or_insert_with(|| (offset, vec![]))
+                        .1
+                        .extend(stations);
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::FetchFailed(why) => {
+                    streaming = None;
+                    if let Some(spinner) = fetch_spinner.take() {
+                        spinner.stop();
+                    }
+                    error!("couldn't fetch stations: {}", why);
+                    status.set_text(&format!("\x1b[31mcouldn't fetch stations: {}\x1b[0m", why));
+                    retry_button.show();
+                }
+                Message::FetchRetry { attempt, delay } => {
+                    warn!("fetch failed, attempt {} in {:?}", attempt, delay);
+                    if let Some(spinner) = fetch_spinner.take() {
+                        spinner.stop();
+                    }
+                    fetch_spinner = Some(Spinner::start(
+                        &status,
+                        &format!(
+                            "Fetch failed, attempt {}/{} in {:.0}s…",
+                            attempt,
+                            FETCH_ATTEMPTS,
+                            delay.as_secs_f64()
+                        ),
+                    ));
+                }
+                Message::FetchedFromMirror(mirror) => {
+                    debug!("fetched from {}", mirror);
+                    status.set_text(&format!("Fetched stations from {}", mirror));
+                    fetched_mirror = Some(mirror);
+                }
+                Message::StationsFetchedSuccess {
+                    stations,
+                    offset,
+                    complete,
+                } => {
+                    filter_cache.invalidate();
+                    streaming = None;
+                    info!("fetched {} stations at offset {}", stations.len(), offset);
+                    if let Some(spinner) = fetch_spinner.take() {
+                        spinner.stop();
+                    }
+                    retry_button.hide();
+                    match all_stations {
+                        Some(loaded) if offset > 0 => {
+                            let cached = loaded.clone();
+                            loaded.extend(stations);
+                            status.set_text(&format!("Loaded {} stations", loaded.len()));
+                            // The stored list is filtered afresh, earlier pages and all.
+                            cache_busy = true;
+                            spawn_cache_store(
+                                tx_message.clone(),
+                                Some(cached),
+                                loaded.clone(),
+                                0..0,
+                                fetched_mirror.clone(),
+                            );
+                        }
+                        _ => {
+                            // Set now so filtering meanwhile doesn't fetch again.
+                            fetched_this_run = true;
+                            cache_busy = true;
+                            let covered = fetched_positions(offset, stations.len(), complete);
+                            spawn_cache_store(
+                                tx_message.clone(),
+                                all_stations.clone(),
+                                stations,
+                                covered,
+                                fetched_mirror.clone(),
+                            );
+                        }
+                    }
+                    let stations = all_stations.as_deref().unwrap_or_default();
+                    countries = distinct_countries(stations);
+                    populate_choice(&mut country_choice, ALL_COUNTRIES, &countries);
+                    languages = distinct_languages(stations);
+                    populate_choice(&mut language_choice, ALL_LANGUAGES, &languages);
+                    fill_tag_chips(&mut tag_chips, &top_tags(stations, TAG_CHIPS as usize));
+                }
+                Message::CacheLoaded(result) => {
+                    filter_cache.invalidate();
+                    cache_busy = false;
+                    match result {
+                        Ok(stations) => {
+                            info!("loaded {} cached stations", stations.len());
+                            countries = distinct_countries(&stations);
+                            populate_choice(&mut country_choice, ALL_COUNTRIES, &countries);
+                            languages = distinct_languages(&stations);
+                            populate_choice(&mut language_choice, ALL_LANGUAGES, &languages);
+                            fill_tag_chips(
+                                &mut tag_chips,
+                                &top_tags(&stations, TAG_CHIPS as usize),
+                            );
+                            let _ = all_stations.insert(stations);
+                        }
+                        // A corrupt cache has been removed, so this falls through to a refetch.
+                        Err(why) => {
+                            warn!("{}", why);
+                            status.set_text(&why);
+                            cache_unreadable = true;
+                        }
+                    }
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::CacheStored {
+                    mut stations,
+                    result,
+                } => {
+                    filter_cache.invalidate();
+                    cache_busy = false;
+                    if !pending_cache_changes.is_empty() {
+                        stations =
+                            apply_cache_changes(stations, mem::take(&mut pending_cache_changes));
+                        spawn_cache_save(stations.clone());
+                    }
+                    match result {
+                        Ok(()) => status
+                            .set_text(&format!("Successfully fetched {} stations", stations.len())),
+                        Err(why) => {
+                            error!("{}", why);
+                            status.set_text(&why);
+                        }
+                    }
+                    countries = distinct_countries(&stations);
+                    populate_choice(&mut country_choice, ALL_COUNTRIES, &countries);
+                    languages = distinct_languages(&stations);
+                    populate_choice(&mut language_choice, ALL_LANGUAGES, &languages);
+                    fill_tag_chips(&mut tag_chips, &top_tags(&stations, TAG_CHIPS as usize));
+                    let _ = all_stations.insert(stations);
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::StationSelected
+                    if selected_station(&browser).is_none()
+                        && header_country(&group_headers, browser.value()).is_some() =>
+                {
+                    if let Some(country) = header_country(&group_headers, browser.value()) {
+                        if !collapsed_countries.remove(&country) {
+                            collapsed_countries.insert(country);
+                        }
+                    }
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::StationSelected => match selected_station(&browser) {
+                    Some(station) => {
+                        populate_choice(&mut quality_choice, AUTO_QUALITY, &[]);
+                        quality_choice.deactivate();
+                        hls_variants = None;
+                        if station.hls == 1 && !offline {
+                            spawn_hls_variants_fetch(tx_message.clone(), station.clone());
+                        }
+                        if offline {
+                            let cached = cached_favicon(Path::new(FAVICON_DIR), &station);
+                            show_favicon(&mut favicon_frame, cached.as_deref());
+                        } else {
+                            show_favicon(&mut favicon_frame, None);
+                            spawn_favicon_fetch(tx_message.clone(), station.clone());
+                        }
+                        details_buffer.set_text(&render_station_details(&station));
+                        if is_web_url(&station.homepage) {
+                            homepage_button.activate();
+                        } else {
+                            homepage_button.deactivate();
+                        }
+                    }
+                    None => {
+                        populate_choice(&mut quality_choice, AUTO_QUALITY, &[]);
+                        quality_choice.deactivate();
+                        hls_variants = None;
+                        favicon_frame.set_image(None::<SharedImage>);
+                        favicon_frame.set_label("");
+                        favicon_frame.redraw();
+                        details_buffer.set_text("");
+                        homepage_button.deactivate();
+                    }
+                },
+                Message::ExportPlaylist => {
+                    let Some(path) = dialog::file_chooser("Export playlist", "*.m3u", ".", false)
+                    else {
+                        continue;
+                    };
+                    match export_m3u(&shown_stations(&browser), Path::new(&path)) {
+                        Ok(written) => {
+                            status.set_text(&format!("Exported {} stations to {}", written, path))
+                        }
+                        Err(why) => status.set_text(&format!("couldn't export {}: {}", path, why)),
+                    }
+                }
+                Message::ExportDataset => {
+                    let Some(stations) = all_stations.as_deref() else {
+                        status.set_text("No stations loaded yet");
+                        continue;
+                    };
+                    let meta = match read_cache_meta(Path::new(CACHE_META_PATH)) {
+                        Ok(meta) => meta,
+                        Err(why) => {
+                            status.set_text(&format!("couldn't read {}: {}", CACHE_META_PATH, why));
+                            continue;
+                        }
+                    };
+                    let Some(path) =
+                        dialog::file_chooser("Export all stations", "*.json", ".", false)
+                    else {
+                        continue;
+                    };
+                    match export_dataset(stations, &meta, Path::new(&path)) {
+                        Ok(()) => status.set_text(&format!(
+                            "Exported {} stations to {}",
+                            stations.len(),
+                            path
+                        )),
+                        Err(why) => status.set_text(&format!("couldn't export {}: {}", path, why)),
+                    }
+                }
+                Message::ImportPlaylist => {
+                    let Some(path) = dialog::file_chooser(
+                        "Import playlist",
+                        "Playlists (*.{m3u,m3u8,pls})",
+                        ".",
+                        false,
+                    ) else {
+                        continue;
+                    };
+                    match import_playlist(Path::new(&path)) {
+                        Ok(stations) => {
+                            status.set_text(&format!(
+                                "Imported {} stations from {}",
+                                stations.len(),
+                                path
+                            ));
+                            fill_station_browser(
+                                &browser,
+                                &stations,
+                                "The playlist holds no stations.",
+                            );
+                        }
+                        Err(why) => status.set_text(&format!("couldn't import {}: {}", path, why)),
+                    }
+                }
+                Message::PlayNext | Message::PlayPrevious => {
+                    let rows = station_rows(&browser);
+                    let forward = matches!(msg, Message::PlayNext);
+                    // Found again each time, since refiltering moves the rows.
+                    let current = playing_station.as_ref().and_then(|playing| {
+                        rows.iter()
+                            .position(|(_, station)| same_station(station, playing))
+                    });
+                    let Some(index) = step_index(current, rows.len(), forward) else {
+                        status.set_text("No stations to skip to");
+                        continue;
+                    };
+                    browser.select(rows[index].0);
+                    browser.middle_line(rows[index].0);
+                    tx_message.send(Message::StationSelected);
+                    tx_message.send(Message::PlayRequest);
+                }
+                Message::PlayRandom => {
+                    let rows = station_rows(&browser);
+                    let stations = rows
+                        .iter()
+                        .map(|(_, station)| station.clone())
+                        .collect::<Vec<_>>();
+                    let Some(index) = pick_random(&stations).and_then(|picked| {
+                        stations
+                            .iter()
+                            .position(|station| same_station(station, picked))
+                    }) else {
+                        status.set_text("No stations to pick from");
+                        continue;
+                    };
+                    browser.select(rows[index].0);
+                    browser.middle_line(rows[index].0);
+                    tx_message.send(Message::StationSelected);
+                    tx_message.send(Message::PlayRequest);
+                }
+                Message::ToggleRecording => {
+                    if player_state.stop_recording() {
+                        record_button.set_label("Record");
+                        status.set_text("Recording stopped.");
+                        continue;
+                    }
+                    let Some(station) = &playing_station else {
+                        status.set_text("Play a station to record it");
+                        continue;
+                    };
+                    let default_path =
+                        unique_path(Path::new(&recording_filename(station, Local::now())));
+                    let Some(path) = dialog::file_chooser("Record to", "*", &default_path, false)
+                    else {
+                        continue;
+                    };
+                    let path = unique_path(Path::new(&path));
+                    match player_state.start_recording(&station.url_resolved, &path) {
+                        Ok(()) => {
+                            record_button.set_label("■ Rec");
+                            status.set_text(&format!("Recording to {}", path.display()));
+                        }
+                        Err(why) => status.set_text(&format!("couldn't record: {}", why)),
+                    }
+                }
+                Message::FaviconReady { uuid, path } => {
+                    // Only show it if that station is still the selected one.
+                    if selected_station(&browser).is_some_and(|station| station.stationuuid == uuid)
+                    {
+                        show_favicon(&mut favicon_frame, path.as_deref());
+                    }
+                }
+                Message::HlsVariants { uuid, variants } => {
+                    let selected = selected_station(&browser);
+                    if variants.len() < 2
+                        || selected.is_none_or(|station| station.stationuuid != uuid)
+                    {
+                        continue;
+                    }
+                    let labels = variants.iter().map(HlsVariant::label).collect::<Vec<_>>();
+                    populate_choice(&mut quality_choice, AUTO_QUALITY, &labels);
+                    quality_choice.activate();
+                    hls_variants = Some((uuid, variants));
+                }
+                Message::PrefetchFavicons if offline => {}
+                Message::PrefetchFavicons => {
+                    let jobs = prefetch_jobs(Path::new(FAVICON_DIR), &visible_stations(&browser));
+                    favicon_prefetcher.prefetch(jobs);
+                }
+                Message::RefreshStation if offline => {
+                    status.set_text("Offline mode, not looking stations up")
+                }
+                Message::RefreshStation => match selected_station(&browser) {
+                    Some(station) if !is_custom(&station) => {
+                        status.set_text(&format!("Re-resolving {}…", station.name.trim()));
+                        spawn_station_refresh(tx_message.clone(), station.stationuuid);
+                    }
+                    Some(_) => status.set_text("Custom stations aren't on radiobrowser"),
+                    None => status.set_text("Select a station first"),
+                },
+                Message::StationRefreshed { uuid, result } => {
+                    let Some(stations) = all_stations.as_mut() else {
+                        continue;
+                    };
+                    let change = match result {
+                        Ok(station) => {
+                            let changed = replace_station(stations, &station);
+                            for (line, _) in station_rows(&browser)
+                                .into_iter()
+                                .filter(|(_, listed)| listed.stationuuid == uuid)
+                            {
+                                browser.set_text(line, &format_station(&station));
+                                browser.set_data(line, station.clone());
+                            }
+                            if selected_station(&browser)
+                                .is_some_and(|selected| selected.stationuuid == uuid)
+                            {
+                                details_buffer.set_text(&render_station_details(&station));
+                            }
+                            status
+                                .set_text(&format!("Now streaming from {}", station.url_resolved));
+                            changed.then(|| CacheChange::Upsert(Box::new(station)))
+                        }
+                        Err(RefreshError::Deleted) => {
+                            status.set_text(&format!("\x1b[31m{}\x1b[0m", RefreshError::Deleted));
+                            let changed = remove_station(stations, &uuid);
+                            filter_cache.invalidate();
+                            tx_message.send(Message::FilterStations);
+                            changed.then_some(CacheChange::Remove(uuid))
+                        }
+                        Err(why) => {
+                            status.set_text(&format!("\x1b[31m{}\x1b[0m", why));
+                            None
+                        }
+                    };
+                    match change {
+                        // The store in flight would write its list over this one.
+                        Some(change) if cache_busy => pending_cache_changes.push(change),
+                        Some(_) => {
+                            spawn_cache_save(stations.clone());
+                        }
+                        None => {}
+                    }
+                }
+                Message::ShareStation => match selected_station(&browser) {
+                    Some(station) => {
+                        let link = station_share_url(&station);
+                        app::copy(&link);
+                        status.set_text(&format!("Copied {}", link));
+                    }
+                    None => status.set_text("Select a station first"),
+                },
+                Message::OpenHomepage => {
+                    if let Some(station) = selected_station(&browser) {
+                        if let Err(why) = open_url(&station.homepage) {
+                            status.set_text(&format!("couldn't open homepage: {}", why));
+                        }
                     }
                 }
-                Message::StationsFetchedSuccess => fill_station_browser(&browser, all_stations),
+                Message::Vote if offline => status.set_text("Offline mode, not voting"),
+                Message::Vote => match selected_station(&browser) {
+                    Some(station) if is_custom(&station) || station.stationuuid.is_empty() => {
+                        status.set_text("This station can't be voted for")
+                    }
+                    Some(station) if votes.try_vote(&station.stationuuid) => {
+                        spawn_vote(tx_message.clone(), station.stationuuid);
+                    }
+                    Some(station) => status.set_text(&format!(
+                        "Already voted for {} this session",
+                        station.name.trim()
+                    )),
+                    None => status.set_text("Select a station first"),
+                },
+                Message::VoteResult { uuid, result } => match result {
+                    Ok(()) => {
+                        // radiobrowser only recounts votes periodically, so
+                        // count ours right away.
+                        for station in all_stations.iter_mut().flatten() {
+                            if station.stationuuid == uuid {
+                                station.votes += 1;
+                            }
+                        }
+                        for (line, mut station) in station_rows(&browser) {
+                            if station.stationuuid == uuid {
+                                station.votes += 1;
+                                browser.set_data(line, station);
+                            }
+                        }
+                        if let Some(station) =
+                            selected_station(&browser).filter(|station| station.stationuuid == uuid)
+                        {
+                            details_buffer.set_text(&render_station_details(&station));
+                            status.set_text(&format!("Voted for {}", station.name.trim()));
+                        }
+                    }
+                    Err(why) => {
+                        votes.release(&uuid);
+                        status.set_text(&format!("\x1b[31mcouldn't vote: {}\x1b[0m", why));
+                    }
+                },
+                _ if play_disabled(vlc_found, &msg) => status.set_text(VLC_MISSING),
                 Message::PlayRequest => {
-                    let station = unsafe {
-                        browser
-                            .data::<ApiStation>(
-                                *browser
-                                    .selected_items()
-                                    .first()
-                                    .expect("Couldn't get selected station"),
-                            )
-                            .expect("")
+                    let Some(mut station) = selected_station(&browser) else {
+                        status.set_text("Select a station first");
+                        continue;
                     };
-
+                    if !resolve_playback_url(&mut station) {
+                        status.set_text(&format!("\x1b[31m{}\x1b[0m", PlayError::NoUrl));
+                        continue;
+                    }
+                    let variant = hls_variants
+                        .as_ref()
+                        .filter(|(uuid, _)| *uuid == station.stationuuid)
+                        .zip((quality_choice.value() as usize).checked_sub(1))
+                        .and_then(|((_, variants), index)| variants.get(index));
+                    if let Some(variant) = variant {
+                        station.url_resolved = variant.url.clone();
+                    } else if config.prefer_secure {
+                        if let Some(url) = secure_stream_url(&station) {
+                            station.url_resolved = url.to_string();
+                        }
+                    }
+                    // A HEAD or GET tells nothing about an HLS playlist's segments.
+                    if config.probe_streams && !offline && station.hls != 1 {
+                        status.set_text("Checking…");
+                        probing = Some(station.stationuuid.clone());
+                        spawn_probe(tx_message.clone(), station);
+                    } else {
+                        tx_message.send(Message::PlayStation(station));
+                    }
+                }
+                Message::ProbeFinished { station, reachable } => {
+                    // Only the latest request gets to play.
+                    if probing.as_ref() != Some(&station.stationuuid) {
+                        continue;
+                    }
+                    probing = None;
+                    if reachable {
+                        tx_message.send(Message::PlayStation(station));
+                    } else {
+                        status.set_text(&format!(
+                            "\x1b[31mStation unreachable: {}\x1b[0m",
+                            station.name.trim()
+                        ));
+                    }
+                }
+                Message::PlayUuid(uuid) => {
+                    let station = find_station_by_uuid(&custom_stations, &uuid).or_else(|| {
+                        find_station_by_uuid(all_stations.as_deref().unwrap_or_default(), &uuid)
+                    });
+                    match station {
+                        Some(station) => tx_message.send(Message::PlayStation(station.clone())),
+                        None => status.set_text(&format!("No station with uuid {} loaded", uuid)),
+                    }
+                }
+                Message::ReportNowPlaying(reply) => {
+                    let report = serde_json::json!({
+                        "playing": playing_station.is_some(),
+                        "uuid": playing_station.as_ref().map(|station| &station.stationuuid),
+                        "name": playing_station.as_ref().map(|station| station.name.trim()),
+                        "status": playing_station.as_ref().map(|_| &now_playing_text),
+                    });
+                    let _ = reply.send(report.to_string());
+                }
+                Message::PlayStation(mut station) => {
+                    probing = None;
+                    if !resolve_playback_url(&mut station) {
+                        status.set_text(&format!("\x1b[31m{}\x1b[0m", PlayError::NoUrl));
+                        continue;
+                    }
                     let status_text = format!("Playing: {}", &station.url_resolved);
                     status.set_text(&status_text);
-                    println!("{}", status_text);
-                    let instance = Instance::new().expect("Error initializing vlc instance");
-                    let media = Media::new_location(&instance, &station.url_resolved)
-                        .expect("Error initializing vlc media");
-                    let player =
-                        MediaPlayer::new(&instance).expect("Error initializing vlc media player");
-                    init_player(
-                        play_button.clone(),
-                        tx_message.clone(),
-                        rx_message.clone(),
-                        player,
-                        media,
+                    info!(
+                        "playing {} from {}",
+                        station.name.trim(),
+                        station.url_resolved
                     );
+                    if let Some(timeout) = reconnect_timeout.take() {
+                        app::remove_timeout3(timeout);
+                    }
+                    retry_state.reset();
+                    if let Err(why) = player_state.play(&station, &play_button, &tx_message) {
+                        error!("couldn't play {}: {:?}", station.url_resolved, why);
+                        status.set_text(&format!("\x1b[31m{}\x1b[0m", why));
+                        if player_state.is_active() {
+                            play_button.set_label("||");
+                        } else {
+                            play_button.set_label(">");
+                            end_scrobble(&mut playing_station);
+                        }
+                        dialog::alert_default(&why.to_string());
+                        continue;
+                    }
+                    now_playing_text = status_text;
+                    if player_state.is_crossfading() {
+                        start_crossfade_timer(&mut crossfade_timer, &tx_message);
+                    }
+                    end_scrobble(&mut playing_station);
+                    scrobble(ScrobbleEvent::started(&station));
+                    if let Some(clock) = playback_clock.take() {
+                        clock.stop();
+                    }
+                    time_frame.set_label(&playback_time_label(0, 0));
+                    let tx_tick = tx_message.clone();
+                    playback_clock = Some(PlaybackClock::start(move || {
+                        tx_tick.send(Message::PlaybackTick)
+                    }));
+                    playing_station = Some(station.clone());
+                    config.last_station = station.stationuuid.clone();
+                    if !offline && !is_custom(&station) && !do_not_track(config.do_not_track) {
+                        report_click(&station.stationuuid);
+                    }
+                    history.push(station);
+                    if let Err(why) = history.save() {
+                        status.set_text(&format!("couldn't save history: {}", why));
+                    }
                 }
-                Message::PauseRequest => {
-                    let _ = &status.set_text("Playback stopped.");
-                    play_button.set_label(">");
+                Message::PauseRequest => match player_state.toggle_pause() {
+                    Some(true) => {
+                        status.set_text("Playback resumed.");
+                        play_button.set_label("||");
+                    }
+                    Some(false) => {
+                        status.set_text("Playback paused.");
+                        play_button.set_label(">");
+                    }
+                    None => status.set_text("Nothing is playing."),
+                },
+                Message::StopRequest => {
+                    probing = None;
+                    if let Some(timer) = sleep_timer.take() {
+                        timer.cancel();
+                        sleep_choice.set_value(0);
+                    }
+                    if let Some(timeout) = reconnect_timeout.take() {
+                        app::remove_timeout3(timeout);
+                    }
+                    end_scrobble(&mut playing_station);
+                    if player_state.stop() {
+                        status.set_text("Playback stopped.");
+                    }
+                    if let Some(clock) = playback_clock.take() {
+                        clock.stop();
+                    }
+                    time_frame.set_label("");
+                    play_button.set_label(">");
+                    if player_state.stop_recording() {
+                        record_button.set_label("Record");
+                    }
+                }
+                Message::VolumeChanged => {
+                    let volume = volume_slider.value() as i32;
+                    if volume != config.volume {
+                        config.volume = volume;
+                        if let Err(why) = config.save() {
+                            status.set_text(&format!("couldn't save volume: {}", why));
+                        }
+                    }
+                    player_state.set_volume(volume);
+                    mute_button.set_label("🔊");
+                }
+                Message::PlayerStarted => {
+                    debug!("player started");
+                    retry_state.reset();
+                    player_state.apply_volume();
+                }
+                Message::StreamDropped => {
+                    if playing_station.is_none() {
+                        continue;
+                    }
+                    warn!("stream dropped");
+                    match retry_state.next_delay() {
+                        Some((attempt, delay)) => {
+                            status.set_text(&format!(
+                                "Stream dropped, reconnecting in {}s ({}/{})",
+                                delay.as_secs(),
+                                attempt,
+                                retry_state.attempts()
+                            ));
+                            let tx_message = tx_message.clone();
+                            reconnect_timeout =
+                                Some(app::add_timeout3(delay.as_secs_f64(), move |_| {
+                                    tx_message.send(Message::Reconnect)
+                                }));
+                        }
+                        None => {
+                            status.set_text(&format!(
+                                "\x1b[31mStream lost after {} reconnect attempts\x1b[0m",
+                                retry_state.attempts()
+                            ));
+                            end_scrobble(&mut playing_station);
+                            player_state.stop();
+                            play_button.set_label(">");
+                        }
+                    }
+                }
+                Message::Reconnect => {
+                    reconnect_timeout = None;
+                    if let Some(station) = &playing_station {
+                        status.set_text(&format!("Reconnecting to {}", station.url_resolved));
+                        match player_state.play(station, &play_button, &tx_message) {
+                            Ok(()) if player_state.is_crossfading() => {
+                                start_crossfade_timer(&mut crossfade_timer, &tx_message)
+                            }
+                            Ok(()) => {}
+                            Err(why) => {
+                                // The dropped player has nothing left to play.
+                                status.set_text(&format!("\x1b[31m{}\x1b[0m", why));
+                                end_scrobble(&mut playing_station);
+                                player_state.stop();
+                                play_button.set_label(">");
+                            }
+                        }
+                    }
+                }
+                Message::ToggleMute => {
+                    if player_state.toggle_mute() {
+                        mute_button.set_label("🔇");
+                    } else {
+                        mute_button.set_label("🔊");
+                    }
+                }
+                Message::ToggleFavorite => match selected_station(&browser) {
+                    Some(station) => {
+                        let added = toggle_favorite(&mut favorites, &station.stationuuid);
+                        match save_favorites(&favorites) {
+                            Ok(_) if added => status
+                                .set_text(&format!("Added {} to favorites", station.name.trim())),
+                            Ok(_) => status.set_text(&format!(
+                                "Removed {} from favorites",
+                                station.name.trim()
+                            )),
+                            Err(why) => {
+                                status.set_text(&format!("couldn't save favorites: {}", why))
+                            }
+                        }
+                    }
+                    None => status.set_text("Select a station first"),
+                },
+                Message::SortBy(key) => {
+                    if let Some(stations) = all_stations.as_mut() {
+                        let (key, ascending) = next_sort_order(sort_order, key);
+                        sort_order = Some((key, ascending));
+                        label_column_headers(&mut column_headers, sort_order);
+                        sort_stations(stations, key, ascending);
+                        filter_cache.invalidate();
+                        let filtered_stations = filter_stations(
+                            stations,
+                            &current_query(
+                                &search_input,
+                                (&country_choice, &countries),
+                                (&language_choice, &languages),
+                                &bitrate_slider,
+                                &view_menu,
+                                &blocked,
+                                &tag_chips,
+                            ),
+                            search_fields(&view_menu),
+                        );
+                        fill_station_browser(
+                            &browser,
+                            &filtered_stations,
+                            empty_list_message(stations.len()),
+                        );
+                    }
+                }
+                Message::BlockStation => match selected_station(&browser) {
+                    Some(station) if !station.stationuuid.is_empty() => {
+                        blocked.insert(station.stationuuid.clone());
+                        match save_blocked(&blocked) {
+                            Ok(()) => status.set_text(&format!("Hid {}", station.name.trim())),
+                            Err(why) => {
+                                status.set_text(&format!("couldn't save hidden stations: {}", why))
+                            }
+                        }
+                        tx_message.send(Message::FilterStations);
+                    }
+                    Some(_) => status.set_text("This station can't be hidden"),
+                    None => status.set_text("Select a station first"),
+                },
+                Message::UnblockAll => {
+                    let count = blocked.len();
+                    blocked.clear();
+                    match save_blocked(&blocked) {
+                        Ok(()) => {
+                            status.set_text(&format!("Showing {} hidden stations again", count))
+                        }
+                        Err(why) => {
+                            status.set_text(&format!("couldn't save hidden stations: {}", why))
+                        }
+                    }
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::ShowHistory => {
+                    let played = without_blocked(&history.stations(), &blocked);
+                    status.set_text(&format!("Recently played: {}", played.len()));
+                    fill_station_browser(&browser, &played, "Nothing played yet.");
+                }
+                Message::ShowPopular | Message::ShowTrending => {
+                    let stations =
+                        without_blocked(all_stations.as_deref().unwrap_or_default(), &blocked);
+                    let (label, top) = if matches!(msg, Message::ShowPopular) {
+                        (
+                            "Popular",
+                            top_by(&stations, |station| station.votes as i64, TOP_COUNT),
+                        )
+                    } else {
+                        (
+                            "Trending",
+                            top_by(&stations, |station| station.clicktrend as i64, TOP_COUNT),
+                        )
+                    };
+                    status.set_text(&format!("{}: {}", label, top.len()));
+                    fill_station_browser(&browser, &top, empty_list_message(0));
+                }
+                Message::EqualizerChanged => {
+                    let Some(preset) = equalizer_choice.choice() else {
+                        continue;
+                    };
+                    player_state.set_equalizer(&preset);
+                    config.equalizer = preset;
+                    if let Err(why) = config.save() {
+                        status.set_text(&format!("couldn't save equalizer: {}", why));
+                    }
+                }
+                Message::SleepTimerChanged => {
+                    if let Some(timer) = sleep_timer.take() {
+                        timer.cancel();
+                    }
+                    match SLEEP_TIMER_MINUTES.get(sleep_choice.value() as usize) {
+                        Some(&minutes) if minutes > 0 => {
+                            let timer = schedule_sleep_timer(minutes);
+                            status.set_text(&format!(
+                                "Sleep timer: {} min left",
+                                timer.minutes_left()
+                            ));
+                            sleep_timer = Some(timer);
+                        }
+                        _ => status.set_text("Sleep timer off"),
+                    }
+                }
+                Message::SleepTimerTick(minutes) => {
+                    status.set_text(&format!("Sleep timer: {} min left", minutes))
+                }
+                Message::Quit => {
+                    config.volume = player_state.chosen_volume();
+                    let steps: Vec<ShutdownStep> = vec![
+                        (
+                            "playback",
+                            Box::new(|| {
+                                player_state.shut_down();
+                                end_scrobble(&mut playing_station);
+                                Ok(())
+                            }),
+                        ),
+                        ("favorites", Box::new(|| save_favorites(&favorites))),
+                        ("history", Box::new(|| history.save())),
+                        ("config", Box::new(|| config.save())),
+                        ("window geometry", Box::new(|| save_window_geometry(&win))),
+                    ];
+                    for failure in shutdown.run(steps).into_iter().flatten() {
+                        warn!("couldn't shut down cleanly: {}", failure);
+                    }
+                    app::quit();
+                }
+                Message::ToggleTheme => {
+                    config.theme = config.theme.toggled();
+                    apply_theme(
+                        &config.theme,
+                        &mut frame,
+                        &mut browser,
+                        &mut details,
+                        &mut count_frame,
+                        &mut status,
+                    );
+                    if let Err(why) = config.save() {
+                        status.set_text(&format!("couldn't save theme: {}", why));
+                    }
+                }
+                Message::OpenSettings => {
+                    let tx_settings = tx_message.clone();
+                    open_settings_window(&config, move |settings| {
+                        tx_settings.send(Message::SettingsSaved(settings))
+                    });
+                }
+                Message::SettingsSaved(settings) => {
+                    let (theme, volume) = (config.theme, config.volume);
+                    settings.apply(&mut config);
+                    player_state.network_caching_ms = config.network_caching_ms;
+                    if config.volume != volume {
+                        volume_slider.set_value(config.volume as f64);
+                        player_state.set_volume(config.volume);
+                        mute_button.set_label("🔊");
+                    }
+                    if config.theme != theme {
+                        apply_theme(
+                            &config.theme,
+                            &mut frame,
+                            &mut browser,
+                            &mut details,
+                            &mut count_frame,
+                            &mut status,
+                        );
+                        if let Some(mut item) = view_menu.find_item(LIGHT_THEME_ITEM) {
+                            if config.theme == Theme::Light {
+                                item.set();
+                            } else {
+                                item.clear();
+                            }
+                        }
+                    }
+                    match config.save() {
+                        Ok(()) => status.set_text("Settings saved"),
+                        Err(why) => status.set_text(&format!("couldn't save settings: {}", why)),
+                    }
+                }
+                Message::ToggleDensity => {
+                    config.density = config.density.toggled();
+                    apply_density(&mut browser, config.density);
+                    if let Err(why) = config.save() {
+                        status.set_text(&format!("couldn't save row density: {}", why));
+                    }
+                }
+                Message::CrossfadeTick => {
+                    if !player_state.crossfade_step() {
+                        if let Some(timer) = crossfade_timer.take() {
+                            app::remove_timeout3(timer);
+                        }
+                    }
+                }
+                Message::PlaybackTick => match player_state.playback_time() {
+                    Some((time, length)) => {
+                        time_frame.set_label(&playback_time_label(time, length))
+                    }
+                    // Playback ended some other way, like a lost stream.
+                    None => {
+                        if let Some(clock) = playback_clock.take() {
+                            clock.stop();
+                        }
+                        time_frame.set_label("");
+                    }
+                },
+                Message::TogglePreferSecure => {
+                    config.prefer_secure = menu_toggled(&view_menu, PREFER_SECURE_ITEM);
+                    if let Err(why) = config.save() {
+                        status.set_text(&format!("couldn't save the setting: {}", why));
+                    }
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::FocusSearch => {
+                    let _ = search_input.take_focus();
+                }
+                Message::SubmitSearch => {
+                    push_search(&mut searches, &search_input.value());
+                    fill_recent_searches(&mut recent_searches, &searches, &tx_message);
+                    if let Err(why) = save_searches(&searches) {
+                        warn!("couldn't save the recent searches: {}", why);
+                    }
+                    tx_message.send(Message::FilterStations);
+                }
+                Message::PickSearch(index) => {
+                    if let Some(term) = searches.get(index).cloned() {
+                        search_input.set_value(&term);
+                        tx_message.send(Message::SubmitSearch);
+                    }
+                }
+                Message::NowPlaying(title) => {
+                    now_playing_text = format!("Now playing: {}", title);
+                    status.set_text(&now_playing_text);
+                }
+                Message::PlayerState(state) if state == PLAYING_STATUS => {
+                    status.set_text(&now_playing_text)
+                }
+                Message::PlayerState(state) => status.set_text(&state),
+                Message::ClickReportFailed => {
+                    warn!("couldn't report the click to radiobrowser");
+                    status.set_text("radiobrowser unreachable, click not reported")
+                }
+                Message::ShowFavorites => {
+                    let favorite_stations = all_stations
+                        .iter()
+                        .flatten()
+                        .filter(|station| favorites.contains(&station.stationuuid))
+                        .filter(|station| !is_blocked(&station.stationuuid, &blocked))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    status.set_text(&format!("Favorites: {}", favorite_stations.len()));
+                    fill_station_browser(&browser, &favorite_stations, "No favorites yet.");
                 }
             }
         };
@@ -151,8 +2062,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn station_to_json(station: ApiStation) -> Result<ApiStation, serde_json::Error> {
-    let s = serde_json::json!({
+/// `station` as the JSON the api sent it as, so it deserializes back into an
+/// equal station. `ApiStation` only derives `Deserialize`, hence every field
+/// listed once here, in the order it declares them.
+fn station_to_json(station: ApiStation) -> serde_json::Value {
+    serde_json::json!({
        "changeuuid": station.changeuuid,
        "stationuuid": station.stationuuid,
        "serveruuid": station.serveruuid,
@@ -178,163 +2092,1931 @@ fn station_to_json(station: ApiStation) -> Result<ApiStation, serde_json::Error>
        "lastcheckoktime_iso8601": station.lastcheckoktime_iso8601,
        "lastlocalchecktime_iso8601": station.lastlocalchecktime_iso8601,
        "clicktimestamp_iso8601": station.clicktimestamp_iso8601,
-       "lastchecktime_iso8601": station.lastchecktime_iso8601,
-       "lastcheckoktime_iso8601": station.lastcheckoktime_iso8601,
-       "lastlocalchecktime_iso8601": station.lastlocalchecktime_iso8601,
-       "clicktimestamp_iso8601": station.clicktimestamp_iso8601,
        "clickcount": station.clickcount,
-       "clicktrend": station.clicktrend ,
+       "clicktrend": station.clicktrend,
        "ssl_error": station.ssl_error,
        "geo_lat": station.geo_lat,
        "geo_long": station.geo_long,
        "has_extended_info": station.has_extended_info,
-    });
-    let json_value = serde_json::from_value::<ApiStation>(s);
-
-    json_value
+    })
 }
 
+/// Starts playback of `media`. Player events are forwarded through `tx_mediastate`
+/// and handled in the main event loop, so no extra thread is needed.
 fn init_player(
     play_button: Button,
-    _tx_mediastate: Sender<Message>,
-    rx_mediastate: Receiver<Message>,
-    player: MediaPlayer,
+    tx_mediastate: Sender<Message>,
+    player: &MediaPlayer,
     media: Media,
-) {
+    volume: i32,
+) -> Result<(), PlayError> {
     player.set_media(&media);
-    player.play().expect("Error playing vlc media");
+    let _ = player.set_volume(volume);
+    // Streams without icy metadata never raise this, leaving the "Playing: url" status alone.
+    let tx_meta = tx_mediastate.clone();
+    let _ = media
+        .event_manager()
+        .attach(EventType::MediaMetaChanged, move |event, object| {
+            if let VlcEvent::MediaMetaChanged(Meta::NowPlaying | Meta::Title) = event {
+                let title = event_media_meta(&object, Meta::NowPlaying)
+                    .or_else(|| event_media_meta(&object, Meta::Title));
+                if let Some(title) = title.filter(|title| !title.trim().is_empty()) {
+                    tx_meta.send(Message::NowPlaying(title));
+                }
+            }
+        });
+    // Live streams only end when the connection drops, so both count as a drop.
+    for event_type in [
+        EventType::MediaPlayerEndReached,
+        EventType::MediaPlayerEncounteredError,
+    ] {
+        let tx_dropped = tx_mediastate.clone();
+        let _ = player.event_manager().attach(event_type, move |_, _| {
+            tx_dropped.send(Message::StreamDropped)
+        });
+    }
+    for event_type in [
+        EventType::MediaPlayerOpening,
+        EventType::MediaPlayerBuffering,
+        EventType::MediaPlayerPlaying,
+    ] {
+        let tx_state = tx_mediastate.clone();
+        let _ = player.event_manager().attach(event_type, move |event, _| {
+            if let Some(state) = player_status(&event) {
+                tx_state.send(Message::PlayerState(state));
+            }
+        });
+    }
+    // Re-apply the last chosen volume once vlc reports the stream as playing.
+    let _ = player
+        .event_manager()
+        .attach(EventType::MediaPlayerPlaying, move |_, _| {
+            tx_mediastate.send(Message::PlayerStarted)
+        });
+    player.play().map_err(|_| PlayError::PlaybackFailed)?;
     let play_button_ref = &mut play_button.clone();
     play_button_ref.set_label("||");
-    let _player_thread = thread::spawn(move || loop {
-        thread::sleep(std::time::Duration::from_secs(u64::MAX));
-        match rx_mediastate.recv() {
-            Some(msg) => {
-                println!("{:?}", msg)
+
+    Ok(())
+}
+
+/// Reads a meta field from the media that raised a vlc event.
+fn event_media_meta(object: &VLCObject, meta: Meta) -> Option<String> {
+    unsafe {
+        let p_str = vlc::sys::libvlc_media_get_meta(object.raw() as *mut libvlc_media_t, meta);
+        if p_str.is_null() {
+            return None;
+        }
+        let value = CStr::from_ptr(p_str).to_string_lossy().into_owned();
+        vlc::sys::libvlc_free(p_str as *mut c_void);
+
+        Some(value)
+    }
+}
+
+fn is_cache_present(path: &Path) -> bool {
+    path.exists()
+}
+
+fn write_data_to_file(path: &Path, data: &str) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(data.as_bytes())?;
+    debug!("wrote {}", path.display());
+
+    Ok(())
+}
+
+fn read_data_from_file_and_parse(path: &Path) -> io::Result<JsonValue> {
+    let mut file = File::open(path)?;
+    let mut s = String::new();
+    file.read_to_string(&mut s)?;
+    trace!("{} contains:\n{}", path.display(), s);
+
+    json::parse(&s).map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))
+}
+
+/// Whether `FilterStations` has to go to the network for the station list.
+/// Offline it never does, whatever state the cache is in.
+fn needs_fetch(
+    offline: bool,
+    have_stations: bool,
+    cache_stale: bool,
+    fetched_this_run: bool,
+) -> bool {
+    !offline && (!have_stations || (cache_stale && !fetched_this_run))
+}
+
+/// Whether `message` would start a stream that can't play because libvlc is missing.
+fn play_disabled(vlc_found: bool, message: &Message) -> bool {
+    !vlc_found && matches!(message, Message::PlayRequest | Message::PlayStation(_))
+}
+
+/// Fetches a page of stations off the UI thread and reports back with
+/// `StationsFetchedSuccess` or `FetchFailed`.
+fn spawn_fetch_thread(
+    tx_fetch_signal: Sender<Message>,
+    limit: u32,
+    offset: u32,
+    timeout: Duration,
+) -> thread::JoinHandle<()> {
+    let tx_retry = tx_fetch_signal.clone();
+    let on_retry = move |attempt, delay| {
+        tx_retry.send(Message::FetchRetry { attempt, delay });
+    };
+    spawn_page_fetch(
+        limit,
+        offset,
+        move |page_size, page_offset| {
+            fetch_with_retry(
+                FETCH_ATTEMPTS,
+                page_size,
+                page_offset,
+                timeout,
+                on_retry.clone(),
+            )
+        },
+        move |message| tx_fetch_signal.send(message),
+    )
+}
+
+/// Runs `fetch_pages` on a thread of its own, so the UI thread keeps
+/// handling events while it waits on the network.
+fn spawn_page_fetch<F, Fut>(
+    limit: u32,
+    offset: u32,
+    fetch_page: F,
+    send: impl Fn(Message) + Send + 'static,
+) -> thread::JoinHandle<()>
+where
+    F: FnMut(u32, u32) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(Vec<ApiStation>, String), Box<dyn Error>>>,
+{
+    thread::spawn(move || task::block_on(fetch_pages(limit, offset, fetch_page, send)))
+}
+
+/// Fetches `limit` stations from `offset` a page at a time with `fetch_page`,
+/// telling `send` about each page but the last as it arrives and then
+/// finishing with `StationsFetchedSuccess`, or `FetchFailed` at the first
+/// page that fails.
+async fn fetch_pages<F, Fut>(limit: u32, offset: u32, mut fetch_page: F, send: impl Fn(Message))
+where
+    F: FnMut(u32, u32) -> Fut,
+    Fut: Future<Output = Result<(Vec<ApiStation>, String), Box<dyn Error>>>,
+{
+    let mut stations: Vec<ApiStation> = vec![];
+    // Every page but the last is shown as it arrives.
+    while (stations.len() as u32) < limit {
+        let page_size = FETCH_BATCH_SIZE.min(limit - stations.len() as u32);
+        let page_offset = offset + stations.len() as u32;
+        let page = match fetch_page(page_size, page_offset).await {
+            Ok((page, mirror)) => {
+                if stations.is_empty() {
+                    send(Message::FetchedFromMirror(mirror));
+                }
+                page
             }
-            None => {
-                println!("idk fuggin chickem nuggies")
+            Err(why) => {
+                send(Message::FetchFailed(why.to_string()));
+                return;
             }
+        };
+        let last_page =
+            (page.len() as u32) < page_size || stations.len() as u32 + page_size >= limit;
+        if !last_page {
+            send(Message::StationsBatch {
+                stations: page.clone(),
+                offset,
+            });
         }
+        stations.extend(page);
+        if last_page {
+            break;
+        }
+    }
+    let complete = (stations.len() as u32) < limit;
+    send(Message::StationsFetchedSuccess {
+        stations,
+        offset,
+        complete,
     });
 }
 
-fn is_cache_present() -> bool {
-    let path = Path::new("stations.json");
-    let _ = match File::open(path) {
-        Err(_) => return false,
-        Ok(_) => return true,
-    };
+fn spawn_hls_variants_fetch(
+    tx_message: Sender<Message>,
+    station: ApiStation,
+) -> thread::JoinHandle<()> {
+    thread::spawn(
+        move || match task::block_on(fetch_hls_variants(&station.url_resolved)) {
+            Ok(variants) => tx_message.send(Message::HlsVariants {
+                uuid: station.stationuuid,
+                variants,
+            }),
+            Err(why) => debug!("no hls variants for {}: {}", station.url_resolved, why),
+        },
+    )
 }
 
-fn write_data_to_file(data: &str) {
-    let path = Path::new("stations.json");
-    let display = path.display();
-    let mut file = match File::create(&path) {
-        Err(why) => panic!("couldn't create {}:{}", display, why),
-        Ok(file) => file,
-    };
-    match file.write_all(data.as_bytes()) {
-        Err(why) => panic!("couldn't write to {}: {}", display, why),
-        Ok(_) => println!("Successfully wrote to: {}", display),
+fn spawn_favicon_fetch(tx_message: Sender<Message>, station: ApiStation) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let path = fetch_favicon(&station);
+        tx_message.send(Message::FaviconReady {
+            uuid: station.stationuuid,
+            path,
+        });
+    })
+}
+
+/// Shows the icon at `path` scaled into the frame, or a placeholder if
+/// there is none or it isn't an image fltk can read.
+fn show_favicon(frame: &mut Frame, path: Option<&Path>) {
+    match path.and_then(|path| SharedImage::load(path).ok()) {
+        Some(mut image) => {
+            image.scale(FAVICON_SIZE, FAVICON_SIZE, true, true);
+            frame.set_label("");
+            frame.set_image(Some(image));
+        }
+        None => {
+            frame.set_image(None::<SharedImage>);
+            frame.set_label("No icon");
+        }
     }
+    frame.redraw();
 }
 
-fn read_data_from_file_and_parse() -> Result<JsonValue, JsonError> {
-    let path = Path::new("stations.json");
-    let display = path.display();
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", display, why),
-        Ok(file) => file,
-    };
-    let mut s = String::new();
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("couldn't read {}: {}", display, why),
-        Ok(_) => print!("{} contains: \n{}", display, s),
+/// Runs a server search off the UI thread, answering with `RemoteSearchResults`.
+fn spawn_remote_search(
+    tx_message: Sender<Message>,
+    id: u64,
+    name: String,
+    tag: String,
+    country: String,
+    limit: u32,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        task::block_on(async {
+            let result = search_stations_remote(&name, &tag, &country, limit)
+                .await
+                .map_err(|why| why.to_string());
+            tx_message.send(Message::RemoteSearchResults { id, result });
+        })
+    })
+}
+
+/// Writes `stations` as the whole cache, starting its journal afresh.
+async fn save_station_cache(path: &Path, stations: &[ApiStation]) -> io::Result<()> {
+    // A journal left over a newer cache would undo it, so it goes first.
+    remove_if_present(&journal_path(path))?;
+    let json_vec = stations
+        .iter()
+        .cloned()
+        .map(station_to_json)
+        .collect::<Vec<_>>();
+    write_atomically(path, &serde_json::Value::Array(json_vec).to_string()).await?;
+
+    write_cache_timestamp(Path::new(CACHE_META_PATH))
+}
+
+/// Reads the station cache on the async-std runtime, so a large one doesn't
+/// hold up the ui.
+fn spawn_cache_load(tx_message: Sender<Message>, path: PathBuf) -> task::JoinHandle<()> {
+    task::spawn(async move {
+        let result = load_cached_stations(&path)
+            .await
+            .map_err(|why| why.to_string());
+        tx_message.send(Message::CacheLoaded(result));
+    })
+}
+
+fn spawn_station_refresh(tx_message: Sender<Message>, uuid: String) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let result = task::block_on(refresh_station(&uuid));
+        tx_message.send(Message::StationRefreshed { uuid, result });
+    })
+}
+
+/// Writes `stations` as the cache on the async-std runtime.
+fn spawn_cache_save(stations: Vec<ApiStation>) -> task::JoinHandle<()> {
+    task::spawn(async move {
+        let cache_path = Path::new(CACHE_PATH);
+        if let Err(why) = save_station_cache(cache_path, &stations).await {
+            warn!("couldn't write {}: {}", cache_path.display(), why);
+        }
+    })
+}
+
+/// Merges `fetched`, which `covered` those cache positions, into `loaded`,
+/// or into the cache on disk if nothing is loaded, and stores the result on
+/// the async-std runtime, recording `mirror` as its source. Only what changed
+/// is journaled, until the journal grows too long and the cache is written in
+/// full. An unchanged list only renews the cache timestamp.
+fn spawn_cache_store(
+    tx_message: Sender<Message>,
+    loaded: Option<Vec<ApiStation>>,
+    fetched: Vec<ApiStation>,
+    covered: Range<usize>,
+    mirror: Option<String>,
+) -> task::JoinHandle<()> {
+    task::spawn(async move {
+        let cache_path = Path::new(CACHE_PATH);
+        let cached = match loaded {
+            Some(loaded) => loaded,
+            None => load_cached_stations(cache_path).await.unwrap_or_default(),
+        };
+        let stations = merge_station_cache(cached.clone(), fetched, covered);
+        let changes = diff_station_cache(&cached, &stations);
+        let journaled = read_cache_journal(cache_path)
+            .await
+            .map_or(0, |changes| changes.len());
+        let saved = if changes.is_empty() {
+            Ok(())
+        } else if !is_cache_present(cache_path)
+            || journal_is_full(journaled + changes.len(), stations.len())
+        {
+            save_station_cache(cache_path, &stations).await
+        } else {
+            append_cache_journal(cache_path, &changes).await
+        }
+        .and_then(|()| write_cache_meta(Path::new(CACHE_META_PATH), mirror.as_deref()));
+        let result =
+            saved.map_err(|why| format!("couldn't write {}: {}", cache_path.display(), why));
+        tx_message.send(Message::CacheStored { stations, result });
+    })
+}
+
+/// What to list while a fetch is still streaming in `streamed` pages: the
+/// loaded stations followed by the new ones when `appending` more, or the
+/// pages alone if nothing was loaded yet. A refetch over loaded stations keeps
+/// showing those until it is done, so `None` is returned then.
+fn streamed_view(
+    loaded: Option<&[ApiStation]>,
+    streamed: &[ApiStation],
+    appending: bool,
+) -> Option<Vec<ApiStation>> {
+    match loaded {
+        Some(loaded) if appending => Some([loaded, streamed].concat()),
+        Some(_) => None,
+        None => Some(streamed.to_vec()),
     }
-    let parsed = json::parse(&s);
+}
 
-    parsed
+/// The placeholder row for an empty list, telling a filter that matched
+/// nothing apart from a list that came back empty.
+fn empty_list_message(filtered_from: usize) -> &'static str {
+    if filtered_from > 0 {
+        "No stations match the filter."
+    } else {
+        "Received 0 stations."
+    }
 }
 
-fn spawn_fetch_thread(
+/// The rows of the station list as filling it sees them.
+trait StationRows {
+    fn clear_rows(&mut self);
+    /// Adds a row without station data, which is never played.
+    fn add_placeholder(&mut self, message: &str);
+    fn add_stations(&mut self, stations: &[ApiStation]);
+}
+
+impl StationRows for Browser {
+    fn clear_rows(&mut self) {
+        self.clear();
+    }
+
+    fn add_placeholder(&mut self, message: &str) {
+        self.add(message);
+    }
+
+    fn add_stations(&mut self, stations: &[ApiStation]) {
+        append_station_browser(self, stations);
+    }
+}
+
+/// Replaces the rows with `stations`, or with the data-less `empty_message`
+/// row if there are none.
+fn fill_station_browser(browser: &Browser, stations: &[ApiStation], empty_message: &str) {
+    fill_rows(&mut browser.clone(), stations, empty_message);
+}
+
+fn fill_rows(rows: &mut impl StationRows, stations: &[ApiStation], empty_message: &str) {
+    rows.clear_rows();
+    if stations.is_empty() {
+        rows.add_placeholder(empty_message);
+    } else {
+        rows.add_stations(stations);
+    }
+}
+
+/// Fills the browser with a bold header row per country, each followed by its
+/// stations unless the country is in `collapsed`. Headers carry no station
+/// data, so they are never played. Returns the line and country of each header.
+fn fill_grouped_browser(
     browser: &Browser,
-    tx_fetch_signal: &Sender<Message>,
-    stations_container: &mut Option<Vec<ApiStation>>,
-) {
-    task::block_on(async {
-        let stations = fetch_stations().await.unwrap_or(vec![]);
-        tx_fetch_signal.send(Message::StationsFetchedSuccess);
-
-        let _ = stations_container.insert(stations.clone());
-        fill_station_browser(&browser, &mut Some(stations.clone()));
-    });
+    groups: &[(String, Vec<ApiStation>)],
+    collapsed: &HashSet<String>,
+) -> Vec<(i32, String)> {
+    let mut browser_ref = browser.clone();
+    browser_ref.clear();
+    let mut headers = vec![];
+    for (country, stations) in groups {
+        let folded = collapsed.contains(country);
+        browser_ref.add(&format!(
+            "@b@.{} {} ({})",
+            if folded { "▶" } else { "▼" },
+            country.replace('|', " "),
+            stations.len()
+        ));
+        headers.push((browser_ref.size(), country.clone()));
+        if !folded {
+            append_station_browser(browser, stations);
+        }
+    }
+
+    headers
+}
+
+/// The country whose header is at `line`, if it is one.
+fn header_country(headers: &[(i32, String)], line: i32) -> Option<String> {
+    headers
+        .iter()
+        .find(|(header, _)| *header == line)
+        .map(|(_, country)| country.clone())
+}
+
+fn append_station_browser(browser: &Browser, stations: &[ApiStation]) {
+    let mut browser_ref = browser.clone();
+    for station in stations {
+        browser_ref.add_with_data(&format_station(station), station.clone());
+    }
+}
+
+fn build_search(window: &DoubleWindow) -> (Input, Button, MenuButton) {
+    let mut input = Input::new(0, 0, window.width() - 440, 40, "");
+    input.set_label("Search");
+    input.set_trigger(CallbackTrigger::Changed | CallbackTrigger::EnterKey);
+
+    let search_button = Button::new(window.width() - 100, 0, 70, 40, "Search");
+    let mut recent = MenuButton::new(window.width() - 30, 0, 30, 40, "");
+    recent.set_tooltip("Recent searches");
+
+    (input, search_button, recent)
+}
+
+/// Lists `searches` in the recent searches dropdown, most recent on top.
+fn fill_recent_searches(menu: &mut MenuButton, searches: &[String], tx_message: &Sender<Message>) {
+    menu.clear();
+    for (index, term) in searches.iter().enumerate() {
+        menu.add_emit(
+            &escape_menu_label(term),
+            Shortcut::None,
+            MenuFlag::Normal,
+            tx_message.clone(),
+            Message::PickSearch(index),
+        );
+    }
+    if searches.is_empty() {
+        menu.deactivate();
+    } else {
+        menu.activate();
+    }
 }
 
-fn fill_station_browser(browser: &Browser, stations: &mut Option<Vec<ApiStation>>) {
-    if stations.is_some() {
-        let mut browser_ref = browser.clone();
-        if stations.clone().unwrap().len() < 1 {
-            browser_ref.add("Received 0 stations.")
+/// Reschedules a single `FilterStations` on every change so filtering only
+/// runs once typing pauses. Enter submits the search right away.
+fn debounce_search(input: &mut Input, tx_message: Sender<Message>) {
+    let mut debouncer = Debouncer::default();
+    input.set_callback(move |_| {
+        if app::event() == Event::KeyDown && app::event_key() == Key::Enter {
+            debouncer.cancel(app::remove_timeout3);
+            tx_message.send(Message::SubmitSearch);
+            return;
         }
-        browser_ref.clear();
-        for station in stations.clone().unwrap() {
-            browser_ref.add_with_data(&format_station(&station), station.clone());
+        let tx_message = tx_message.clone();
+        debouncer.schedule(app::remove_timeout3, || {
+            app::add_timeout3(SEARCH_DEBOUNCE_SECS, move |_| {
+                tx_message.send(Message::FilterStations)
+            })
+        });
+    });
+}
+
+const ALL_COUNTRIES: &str = "All countries";
+const ALL_LANGUAGES: &str = "All languages";
+
+fn build_country_choice(window: &DoubleWindow) -> Choice {
+    let mut choice = Choice::new(window.width() - 280, 0, 120, 40, "");
+    choice.set_tooltip("Only show stations from this country");
+    populate_choice(&mut choice, ALL_COUNTRIES, &[]);
+
+    choice
+}
+
+fn build_language_choice(window: &DoubleWindow) -> Choice {
+    let mut choice = Choice::new(window.width() - 440, 0, 100, 40, "");
+    choice.set_tooltip("Only show stations in this language");
+    populate_choice(&mut choice, ALL_LANGUAGES, &[]);
+
+    choice
+}
+
+/// Refills the dropdown, keeping `all` as the first entry.
+fn populate_choice(choice: &mut Choice, all: &str, entries: &[String]) {
+    choice.clear();
+    choice.add_choice(all);
+    for entry in entries {
+        choice.add_choice(&escape_menu_label(entry));
+    }
+    choice.set_value(0);
+}
+
+/// `label` as a menu item shows it. Slashes and ampersands would otherwise be
+/// read as submenus and shortcuts.
+fn escape_menu_label(label: &str) -> String {
+    label
+        .replace('/', "\\/")
+        .replace('&', "&&")
+        .replace('|', " ")
+}
+
+/// The entry picked in a dropdown filled by `populate_choice`, lowercase, or
+/// `None` for the "All …" entry.
+fn chosen_entry(choice: &Choice, entries: &[String]) -> Option<String> {
+    let selected = choice.value();
+    if selected < 1 {
+        return None;
+    }
+
+    entries
+        .get(selected as usize - 1)
+        .map(|entry| entry.to_lowercase())
+}
+
+/// Makes the url `station` plays from its `url_resolved`, so the status,
+/// probe and recording all use it. Returns false if it has no url at all.
+fn resolve_playback_url(station: &mut ApiStation) -> bool {
+    match playback_url(station).map(String::from) {
+        Some(url) => {
+            station.url_resolved = url;
+            true
         }
+        None => false,
     }
 }
 
-fn build_search(window: &DoubleWindow) -> (Input, Button) {
-    let mut input = Input::new(0, 0, window.width() - 100, 40, "");
-    input.set_label("Search");
-    input.set_trigger(CallbackTrigger::EnterKey);
+/// Sends `CrossfadeTick` every step until the timer is removed, unless
+/// `timer` runs already.
+fn start_crossfade_timer(timer: &mut Option<app::TimeoutHandle>, tx_message: &Sender<Message>) {
+    if timer.is_some() {
+        return;
+    }
+    let tx_tick = tx_message.clone();
+    *timer = Some(app::add_timeout3(
+        CROSSFADE_STEP.as_secs_f64(),
+        move |handle| {
+            tx_tick.send(Message::CrossfadeTick);
+            app::repeat_timeout3(CROSSFADE_STEP.as_secs_f64(), handle);
+        },
+    ));
+}
+
+/// Appends `event` to the scrobble log. Listening goes on if it can't be.
+fn scrobble(event: ScrobbleEvent) {
+    if let Err(why) = log_scrobble(event) {
+        warn!("couldn't log scrobble: {}", why);
+    }
+}
 
-    let search_button = Button::new(window.width() - 100, 0, 100, 40, "Search");
+/// Forgets the station that was playing and logs that it stopped.
+fn end_scrobble(playing_station: &mut Option<ApiStation>) {
+    if let Some(station) = playing_station.take() {
+        scrobble(ScrobbleEvent::stopped(&station));
+    }
+}
+
+/// Picks `entry` in a dropdown filled by `populate_choice`, ignoring case.
+/// An empty or unknown entry picks the "All …" entry.
+fn choose_entry(choice: &mut Choice, entries: &[String], entry: &str) {
+    let index = entries
+        .iter()
+        .position(|known| known.eq_ignore_ascii_case(entry))
+        .map_or(0, |index| index as i32 + 1);
+    choice.set_value(index);
+}
+
+/// The entry picked in a dropdown filled by `populate_choice` as listed, or
+/// empty for the "All …" entry.
+fn chosen_label(choice: &Choice, entries: &[String]) -> String {
+    (choice.value() as usize)
+        .checked_sub(1)
+        .and_then(|index| entries.get(index))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Combines the typed query with the country and language picked in the
+/// dropdowns, leaving out the `blocked` stations.
+fn current_query(
+    input: &Input,
+    (country_choice, countries): (&Choice, &[String]),
+    (language_choice, languages): (&Choice, &[String]),
+    bitrate_slider: &HorSlider,
+    view_menu: &MenuButton,
+    blocked: &HashSet<String>,
+    tag_chips: &[ToggleButton],
+) -> StationQuery {
+    let mut query = parse_query(&input.value());
+    query.tags.extend(chosen_chip_tags(tag_chips));
+    query.blocked = blocked.clone();
+    query.hide_duplicates = menu_toggled(view_menu, HIDE_DUPLICATES_ITEM);
+    query.hide_dead = menu_toggled(view_menu, HIDE_DEAD_ITEM);
+    query.located_only = menu_toggled(view_menu, LOCATED_ONLY_ITEM);
+    query.hide_ssl_errors = menu_toggled(view_menu, PREFER_SECURE_ITEM);
+    query.hide_unknown_bitrate = menu_toggled(view_menu, HIDE_UNKNOWN_BITRATE_ITEM);
+    query.min_bitrate = bitrate_slider.value() as u32;
+    if let Some(country) = chosen_entry(country_choice, countries) {
+        query.exact_country = Some(country);
+    }
+    if let Some(language) = chosen_entry(language_choice, languages) {
+        query.language = Some(language);
+    }
+
+    query
+}
+
+const HIDE_DUPLICATES_ITEM: &str = "Hide duplicates";
+const SERVER_SEARCH_ITEM: &str = "Server search";
+const HIDE_UNKNOWN_BITRATE_ITEM: &str = "Hide unknown bitrate";
+const GROUP_BY_COUNTRY_ITEM: &str = "Group by country";
+const SEARCH_NAME_ITEM: &str = "Search in/Name";
+const SEARCH_TAGS_ITEM: &str = "Search in/Tags";
+const SEARCH_COUNTRY_ITEM: &str = "Search in/Country";
+const SEARCH_HOMEPAGE_ITEM: &str = "Search in/Homepage";
+const HIDE_DEAD_ITEM: &str = "Hide dead stations";
+const LOCATED_ONLY_ITEM: &str = "Only stations with a location";
+const PREFER_SECURE_ITEM: &str = "Prefer secure stations";
+const LIGHT_THEME_ITEM: &str = "Light theme";
+const COMPACT_ROWS_ITEM: &str = "Compact rows";
 
-    (input, search_button)
+fn search_fields(view_menu: &MenuButton) -> SearchFields {
+    SearchFields {
+        name: menu_toggled(view_menu, SEARCH_NAME_ITEM),
+        tags: menu_toggled(view_menu, SEARCH_TAGS_ITEM),
+        country: menu_toggled(view_menu, SEARCH_COUNTRY_ITEM),
+        homepage: menu_toggled(view_menu, SEARCH_HOMEPAGE_ITEM),
+    }
+}
+
+fn menu_toggled(menu: &MenuButton, item: &str) -> bool {
+    menu.find_item(item).is_some_and(|item| item.value())
 }
 
-fn build_browser(window: &DoubleWindow) -> Browser {
-    let mut browser = Browser::new(0, 40, window.width(), window.height() - 80, "");
+fn build_view_menu(
+    window: &DoubleWindow,
+    tx_message: &Sender<Message>,
+    config: &Config,
+) -> MenuButton {
+    let mut menu = MenuButton::new(window.width() - 160, 0, 60, 40, "View");
+    menu.set_tooltip(
+        "Sort stations (pick a field again to reverse), hide duplicates or single stations, or search the server",
+    );
+    for (label, shortcut, key) in [
+        ("Sort by/Name", '1', SortKey::Name),
+        ("Sort by/Country", '2', SortKey::Country),
+        ("Sort by/Bitrate", '3', SortKey::Bitrate),
+        ("Sort by/Votes", '4', SortKey::Votes),
+    ] {
+        menu.add_emit(
+            label,
+            Shortcut::Ctrl | shortcut,
+            MenuFlag::Normal,
+            tx_message.clone(),
+            Message::SortBy(key),
+        );
+    }
+    // Only offered once a home location is configured to measure from.
+    if let Some(home) = parse_coordinates(&config.home_location) {
+        menu.add_emit(
+            "Sort by/Distance from home",
+            Shortcut::Ctrl | '5',
+            MenuFlag::Normal,
+            tx_message.clone(),
+            Message::SortBy(SortKey::Distance(home)),
+        );
+    }
+    let defaults = SearchFields::default();
+    for (item, searched) in [
+        (SEARCH_NAME_ITEM, defaults.name),
+        (SEARCH_TAGS_ITEM, defaults.tags),
+        (SEARCH_COUNTRY_ITEM, defaults.country),
+        (SEARCH_HOMEPAGE_ITEM, defaults.homepage),
+    ] {
+        menu.add_emit(
+            item,
+            Shortcut::None,
+            MenuFlag::Toggle,
+            tx_message.clone(),
+            Message::FilterStations,
+        );
+        match menu.find_item(item) {
+            Some(mut item) if searched => item.set(),
+            _ => {}
+        }
+    }
+    for item in [
+        HIDE_DUPLICATES_ITEM,
+        HIDE_DEAD_ITEM,
+        HIDE_UNKNOWN_BITRATE_ITEM,
+        LOCATED_ONLY_ITEM,
+        GROUP_BY_COUNTRY_ITEM,
+        SERVER_SEARCH_ITEM,
+    ] {
+        menu.add_emit(
+            item,
+            Shortcut::None,
+            MenuFlag::Toggle,
+            tx_message.clone(),
+            Message::FilterStations,
+        );
+    }
+    menu.add_emit(
+        PREFER_SECURE_ITEM,
+        Shortcut::None,
+        MenuFlag::Toggle,
+        tx_message.clone(),
+        Message::TogglePreferSecure,
+    );
+    if let Some(mut item) = menu.find_item(PREFER_SECURE_ITEM) {
+        if config.prefer_secure {
+            item.set();
+        }
+    }
+    menu.add_emit(
+        "Hide selected station",
+        Shortcut::Ctrl | 'h',
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::BlockStation,
+    );
+    menu.add_emit(
+        "Show hidden stations again",
+        Shortcut::None,
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::UnblockAll,
+    );
+    menu.add_emit(
+        LIGHT_THEME_ITEM,
+        Shortcut::None,
+        MenuFlag::Toggle,
+        tx_message.clone(),
+        Message::ToggleTheme,
+    );
+    if let Some(mut item) = menu.find_item(LIGHT_THEME_ITEM) {
+        if config.theme == Theme::Light {
+            item.set();
+        }
+    }
+    menu.add_emit(
+        COMPACT_ROWS_ITEM,
+        Shortcut::None,
+        MenuFlag::Toggle,
+        tx_message.clone(),
+        Message::ToggleDensity,
+    );
+    if let Some(mut item) = menu.find_item(COMPACT_ROWS_ITEM) {
+        if config.density == Density::Compact {
+            item.set();
+        }
+    }
+
+    menu
+}
+
+fn build_file_menu(window: &DoubleWindow, tx_message: &Sender<Message>) -> MenuButton {
+    let mut menu = MenuButton::new(window.width() - 340, 0, 60, 40, "File");
+    menu.set_tooltip(
+        "Export the shown stations as a playlist, open one, re-resolve or share a station, add your own or clear the station cache",
+    );
+    menu.add_emit(
+        "Export…",
+        Shortcut::Ctrl | 'e',
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::ExportPlaylist,
+    );
+    menu.add_emit(
+        "Export all stations as JSON…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::ExportDataset,
+    );
+    menu.add_emit(
+        "Import…",
+        Shortcut::Ctrl | 'o',
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::ImportPlaylist,
+    );
+    menu.add_emit(
+        "Re-resolve stream url",
+        Shortcut::None,
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::RefreshStation,
+    );
+    menu.add_emit(
+        "Copy share link",
+        Shortcut::Ctrl | 'l',
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::ShareStation,
+    );
+    menu.add_emit(
+        "Add custom station…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::AddCustomStation,
+    );
+    menu.add_emit(
+        "Settings…",
+        Shortcut::Ctrl | ',',
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::OpenSettings,
+    );
+    menu.add_emit(
+        "Clear cache…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        tx_message.clone(),
+        Message::ClearCache,
+    );
+
+    menu
+}
+
+fn build_browser(window: &DoubleWindow, density: Density) -> Browser {
+    let width = window.width() - DETAILS_WIDTH;
+    let mut browser = Browser::new(0, 40, width, window.height() - 80, "");
     browser.set_has_scrollbar(fltk::browser::BrowserScrollbar::Vertical);
-    let num_of_columns = 4;
-    let col_width = window.width() / num_of_columns;
-    let col_widths = (0..=num_of_columns).map(|_| col_width).collect::<Vec<_>>();
-    browser.set_column_widths(&col_widths);
     browser.set_column_char('|');
+    apply_density(&mut browser, density);
 
     browser
 }
 
-async fn fetch_stations() -> Result<Vec<ApiStation>, Box<dyn Error>> {
-    RadioBrowserAPI::new().await?.get_stations().send().await
+/// How many of the most common tags get a chip above the station list.
+const TAG_CHIPS: i32 = 8;
+const TAG_CHIP_HEIGHT: i32 = 24;
+
+/// A row of toggle chips across the top of the station list, blank and
+/// hidden until `fill_tag_chips` names them. Each chip that is on adds its
+/// tag to the filter.
+fn build_tag_chips(browser: &mut Browser, tx_message: &Sender<Message>) -> Vec<ToggleButton> {
+    let width = browser.w() / TAG_CHIPS;
+    let chips = (0..TAG_CHIPS)
+        .map(|index| {
+            let mut chip = ToggleButton::new(
+                browser.x() + index * width,
+                browser.y(),
+                width,
+                TAG_CHIP_HEIGHT,
+                "",
+            );
+            chip.emit(tx_message.clone(), Message::FilterStations);
+            chip.hide();
+            chip
+        })
+        .collect();
+    browser.resize(
+        browser.x(),
+        browser.y() + TAG_CHIP_HEIGHT,
+        browser.w(),
+        browser.h() - TAG_CHIP_HEIGHT,
+    );
+
+    chips
 }
 
-fn filter_stations(stations: &Vec<ApiStation>, filter: &str) -> Vec<ApiStation> {
-    stations
-        .clone()
-        .into_iter()
-        .filter(|v| {
-            if filter.len() > 0 {
-                v.name.contains(filter) || v.tags.contains(filter)
-            } else {
-                true
+const COLUMN_HEADER_HEIGHT: i32 = 22;
+
+/// A header button above each column, taken off the top of the browser.
+/// Clicking one sorts by its column, again to reverse, the same as the
+/// "Sort by" menu. Columns nothing sorts by have inert headers.
+fn build_column_headers(browser: &mut Browser, tx_message: &Sender<Message>) -> Vec<Button> {
+    let mut headers = columns()
+        .iter()
+        .map(|column| {
+            let mut header = Button::new(browser.x(), browser.y(), 0, COLUMN_HEADER_HEIGHT, None);
+            header.set_label(column.field.title());
+            header.set_align(Align::Left | Align::Inside);
+            header.clear_visible_focus();
+            match column.field.sort_key() {
+                Some(key) => {
+                    header.set_tooltip(&format!("Sort by {}", column.field.title()));
+                    header.emit(tx_message.clone(), Message::SortBy(key));
+                }
+                None => header.set_frame(FrameType::FlatBox),
             }
+            header
         })
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>();
+    browser.resize(
+        browser.x(),
+        browser.y() + COLUMN_HEADER_HEIGHT,
+        browser.w(),
+        browser.h() - COLUMN_HEADER_HEIGHT,
+    );
+    layout_column_headers(&mut headers, browser);
+
+    headers
 }
 
-fn format_station(station: &ApiStation) -> String {
-    format!(
-        "{}|{}|{}|{}",
-        (if station.name.is_empty() {
-            station.url_resolved.to_ascii_lowercase()
-        } else {
-            station.name.to_ascii_lowercase()
+/// Lines the headers up with the columns of `browser`.
+fn layout_column_headers(headers: &mut [Button], browser: &Browser) {
+    let mut x = browser.x();
+    for (header, width) in headers
+        .iter_mut()
+        .zip(column_widths(columns(), browser.w()))
+    {
+        header.resize(
+            x,
+            browser.y() - COLUMN_HEADER_HEIGHT,
+            width,
+            COLUMN_HEADER_HEIGHT,
+        );
+        x += width;
+    }
+}
+
+/// Marks the header of the column the list is sorted by with its direction.
+fn label_column_headers(headers: &mut [Button], sort_order: Option<(SortKey, bool)>) {
+    for (header, column) in headers.iter_mut().zip(columns()) {
+        header.set_label(&column.field.header_label(sort_order));
+    }
+}
+
+/// Names the chips after `tags`, hiding those left over. A chip stays on
+/// only if it keeps its tag.
+fn fill_tag_chips(chips: &mut [ToggleButton], tags: &[(String, usize)]) {
+    for (index, chip) in chips.iter_mut().enumerate() {
+        match tags.get(index) {
+            Some((tag, count)) => {
+                // `@` starts a symbol in fltk labels unless doubled.
+                let label = tag.replace('@', "@@");
+                if chip.label() != label {
+                    chip.set_value(false);
+                    chip.set_label(&label);
+                }
+                chip.set_tooltip(&format!("Only stations tagged {} ({})", tag, count));
+                chip.show();
+            }
+            None => {
+                chip.set_value(false);
+                chip.hide();
+            }
+        }
+    }
+}
+
+/// The tags of the chips that are on.
+fn chosen_chip_tags(chips: &[ToggleButton]) -> Vec<String> {
+    chips
+        .iter()
+        .filter(|chip| chip.visible() && chip.value())
+        .map(|chip| chip.label().replace("@@", "@"))
+        .collect()
+}
+
+/// Puts a banner saying VLC is missing across the top of the station list.
+fn show_vlc_missing_banner(browser: &mut Browser) {
+    let mut banner = Frame::new(browser.x(), browser.y(), browser.w(), 30, VLC_MISSING);
+    banner.set_frame(FrameType::FlatBox);
+    banner.set_color(Color::DarkRed);
+    banner.set_label_color(Color::White);
+    browser.resize(
+        browser.x(),
+        browser.y() + banner.h(),
+        browser.w(),
+        browser.h() - banner.h(),
+    );
+}
+
+fn build_control_button(browser: &Browser, x: i32, width: i32, label: &str) -> Button {
+    let mut button = Button::new(x, 0, width, 40, "").below_of(browser, 0);
+    button.set_pos(x, button.y());
+    button.set_label(label);
+
+    button
+}
+
+/// The stations in the rows currently scrolled into view.
+fn visible_stations(browser: &Browser) -> Vec<ApiStation> {
+    (1..=browser.size())
+        .filter(|&line| browser.displayed(line))
+        .filter_map(|line| unsafe { browser.data::<ApiStation>(line) })
+        .collect()
+}
+
+/// How often the browser is checked for having scrolled.
+const VIEW_CHECK_SECS: f64 = 0.3;
+
+/// Sends `PrefetchFavicons` whenever the browser scrolls or its rows change,
+/// which it raises no event for.
+fn watch_browser_view(browser: &Browser, tx_message: Sender<Message>) {
+    let browser = browser.clone();
+    let mut last_view = None;
+    app::add_timeout3(VIEW_CHECK_SECS, move |handle| {
+        let view = Some((browser.position(), browser.size()));
+        if view != last_view {
+            last_view = view;
+            tx_message.send(Message::PrefetchFavicons);
+        }
+        app::repeat_timeout3(VIEW_CHECK_SECS, handle);
+    });
+}
+
+/// The station attached to the selected row. Placeholder rows added without
+/// data, or no selection at all, give `None`.
+fn selected_station(browser: &Browser) -> Option<ApiStation> {
+    selection_data(&browser.selected_items(), |line| unsafe {
+        browser.data::<ApiStation>(line)
+    })
+}
+
+/// What `data_at` holds for the first of the `selected` lines, if any line is
+/// selected and it holds something.
+fn selection_data<T>(selected: &[i32], data_at: impl Fn(i32) -> Option<T>) -> Option<T> {
+    data_at(*selected.first()?)
+}
+
+/// Selects the row holding the station with `uuid`, if it is listed.
+fn select_station(browser: &mut Browser, uuid: &str) -> bool {
+    let line = (1..=browser.size()).find(|&line| {
+        unsafe { browser.data::<ApiStation>(line) }
+            .is_some_and(|station| station.stationuuid == uuid)
+    });
+    match line {
+        Some(line) => {
+            browser.select(line);
+            browser.middle_line(line);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Every station currently listed with its line, in browser order.
+/// Placeholder rows without a station are left out.
+fn station_rows(browser: &Browser) -> Vec<(i32, ApiStation)> {
+    (1..=browser.size())
+        .filter_map(|line| {
+            unsafe { browser.data::<ApiStation>(line) }.map(|station| (line, station))
         })
-        .trim_end()
-        .trim_start(),
-        station.state,
-        station.country,
-        station.tags
-    )
+        .collect()
+}
+
+/// The index `forward` or back from `current` among `len` rows, wrapping
+/// around at both ends. Without a current row it starts at either end.
+fn step_index(current: Option<usize>, len: usize, forward: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let index = match (current, forward) {
+        (None, true) => 0,
+        (None, false) => len - 1,
+        (Some(current), true) => (current + 1) % len,
+        (Some(current), false) => (current.min(len - 1) + len - 1) % len,
+    };
+
+    Some(index)
+}
+
+/// The next line holding a station `forward` or back from line `current`,
+/// where `has_data[i]` tells whether line `i + 1` holds one. Headers and
+/// placeholders are skipped. Past either end it wraps around if `wrap` is
+/// set and gives `None` otherwise. Without a current line (0) it starts at
+/// either end.
+fn next_selectable_line(has_data: &[bool], current: i32, forward: bool, wrap: bool) -> Option<i32> {
+    let len = has_data.len() as i32;
+    let mut line = match current {
+        1.. if current <= len => current,
+        _ if forward => 0,
+        _ => len + 1,
+    };
+    for _ in 0..len {
+        line += if forward { 1 } else { -1 };
+        if !(1..=len).contains(&line) {
+            if !wrap {
+                return None;
+            }
+            line = if forward { 1 } else { len };
+        }
+        if has_data[line as usize - 1] {
+            return Some(line);
+        }
+    }
+
+    None
+}
+
+/// Up and Down move the selection between stations and Enter plays the
+/// selected one.
+fn navigate_with_keys(browser: &mut Browser, tx_message: Sender<Message>) {
+    let mut type_ahead = TypeAheadBuffer::default();
+    browser.handle(move |browser, event| {
+        if event == Event::Unfocus {
+            type_ahead.clear();
+        }
+        if event != Event::KeyDown {
+            return false;
+        }
+        if let Some(text) = type_ahead_text() {
+            let names = (1..=browser.size())
+                .map(|line| unsafe { browser.data::<ApiStation>(line) }.map(|station| station.name))
+                .collect::<Vec<_>>();
+            let prefix = type_ahead.push(&text, Instant::now());
+            if let Some(line) = prefix_match(&names, prefix).map(|index| index as i32 + 1) {
+                browser.select(line);
+                browser.make_visible(line);
+                tx_message.send(Message::StationSelected);
+            }
+            return true;
+        }
+        match app::event_key() {
+            key @ (Key::Up | Key::Down) => {
+                let has_data = (1..=browser.size())
+                    .map(|line| unsafe { browser.data::<ApiStation>(line) }.is_some())
+                    .collect::<Vec<_>>();
+                let next = next_selectable_line(&has_data, browser.value(), key == Key::Down, true);
+                if let Some(line) = next {
+                    browser.select(line);
+                    browser.make_visible(line);
+                    tx_message.send(Message::StationSelected);
+                }
+                true
+            }
+            Key::Enter | Key::KPEnter => {
+                tx_message.send(Message::PlayRequest);
+                true
+            }
+            _ => false,
+        }
+    });
+}
+
+/// The text of the key just pressed if it types part of a station name.
+/// Space and "/" stay shortcuts, as do keys held with Ctrl, Alt or Cmd.
+fn type_ahead_text() -> Option<String> {
+    let held = app::event_state();
+    if held.contains(Shortcut::Ctrl)
+        || held.contains(Shortcut::Alt)
+        || held.contains(Shortcut::Command)
+    {
+        return None;
+    }
+    let text = app::event_text();
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphanumeric() || (c.is_ascii_punctuation() && c != '/') => {
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+fn shown_stations(browser: &Browser) -> Vec<ApiStation> {
+    station_rows(browser)
+        .into_iter()
+        .map(|(_, station)| station)
+        .collect()
+}
+
+/// Choices offered by the sleep timer dropdown, 0 meaning off.
+const SLEEP_TIMER_MINUTES: [u64; 4] = [0, 15, 30, 60];
+
+fn build_sleep_choice(window: &DoubleWindow, browser: &Browser) -> Choice {
+    let mut choice = Choice::new(window.width() - 170, 0, 70, 40, "").below_of(browser, 0);
+    choice.set_pos(window.width() - 170, choice.y());
+    choice.add_choice("Off|15 min|30 min|60 min");
+    choice.set_value(0);
+    choice.set_tooltip("Stop playback after a while");
+
+    choice
+}
+
+fn build_volume_slider(window: &DoubleWindow, browser: &Browser, volume: i32) -> HorSlider {
+    let mut slider = HorSlider::new(window.width() - 70, 0, 70, 40, "").below_of(browser, 0);
+    slider.set_pos(window.width() - 70, slider.y());
+    slider.set_bounds(0., 100.);
+    slider.set_step(1., 1);
+    slider.set_value(volume as f64);
+    slider.set_tooltip("Volume");
+
+    slider
+}
+
+/// A 0–320 kbps slider under the details, labelled with its value, that
+/// refilters the list when moved.
+fn build_bitrate_slider(above: &Choice, tx_message: &Sender<Message>) -> HorSlider {
+    let mut slider = HorSlider::new(
+        above.x(),
+        above.y() + above.h(),
+        DETAILS_WIDTH - 80,
+        30,
+        "≥ 0 kbps",
+    );
+    slider.set_align(Align::Left);
+    // Readable on either theme, like the favicon placeholder.
+    slider.set_label_color(Color::from_rgb(128, 128, 128));
+    slider.set_bounds(0., 320.);
+    slider.set_step(32., 1);
+    slider.set_tooltip("Hide stations below this bitrate");
+    let tx_message = tx_message.clone();
+    slider.set_callback(move |slider| {
+        slider.set_label(&format!("≥ {} kbps", slider.value() as u32));
+        tx_message.send(Message::FilterStations);
+    });
+
+    slider
+}
+
+const AUTO_QUALITY: &str = "Auto";
+
+/// The dropdown picking which variant of an HLS station plays. It only has
+/// more than "Auto" once a station with several is selected.
+fn build_quality_choice(above: &Frame) -> Choice {
+    let mut choice = Choice::new(
+        above.x() + 80,
+        above.y() + above.h(),
+        DETAILS_WIDTH - 80,
+        30,
+        "Quality",
+    );
+    choice.set_label_color(Color::from_rgb(128, 128, 128));
+    choice.set_tooltip("Stream quality of the selected HLS station");
+    populate_choice(&mut choice, AUTO_QUALITY, &[]);
+    choice.deactivate();
+
+    choice
+}
+
+/// The equalizer preset dropdown under the details, starting at `preset`.
+/// It stays greyed out if vlc has no equalizer.
+fn build_equalizer_choice(above: &Choice, preset: &str, tx_message: &Sender<Message>) -> Choice {
+    let mut choice = Choice::new(
+        above.x(),
+        above.y() + above.h(),
+        DETAILS_WIDTH - 80,
+        30,
+        "Equalizer",
+    );
+    choice.set_label_color(Color::from_rgb(128, 128, 128));
+    choice.add_choice(&PRESETS.join("|"));
+    let index = PRESETS
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(preset))
+        .unwrap_or(0);
+    choice.set_value(index as i32);
+    choice.set_tooltip("Equalizer preset for playback");
+    if equalizer_supported() {
+        choice.emit(tx_message.clone(), Message::EqualizerChanged);
+    } else {
+        choice.set_tooltip("This VLC has no equalizer");
+        choice.deactivate();
+    }
+
+    choice
+}
+
+/// Fetches `limit` stations ordered by votes, most voted first, skipping `offset`.
+async fn fetch_stations(
+    mirror: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<ApiStation>, Box<dyn Error>> {
+    RadioBrowserAPI::new_from_dns_a(mirror)
+        .await?
+        .get_stations()
+        .order(StationOrder::Votes)
+        .reverse(true)
+        .limit(limit.to_string())
+        .offset(offset.to_string())
+        .send()
+        .await
+}
+
+/// Like `fetch_stations`, trying each configured mirror until one answers.
+async fn fetch_stations_with_failover(
+    limit: u32,
+    offset: u32,
+) -> Result<(Vec<ApiStation>, String), Box<dyn Error>> {
+    with_failover(&mirrors(), |mirror| async move {
+        fetch_stations(&mirror, limit, offset).await
+    })
+    .await
+}
+
+/// Like `fetch_stations_with_failover`, trying up to `max_attempts` times
+/// with exponential backoff while the failures look like network trouble.
+/// `on_retry` hears the number of each attempt after the first and the wait
+/// before it. An attempt running past `timeout` is retried like any timeout.
+async fn fetch_with_retry(
+    max_attempts: u32,
+    limit: u32,
+    offset: u32,
+    timeout: Duration,
+    on_retry: impl Fn(u32, Duration),
+) -> Result<(Vec<ApiStation>, String), Box<dyn Error>> {
+    let mut delays = backoff_schedule(max_attempts, RETRY_BASE_DELAY).into_iter();
+    let mut attempt = 1;
+    loop {
+        match with_deadline(fetch_stations_with_failover(limit, offset), timeout).await {
+            Err(why) if is_transient(why.as_ref()) => {
+                let Some(delay) = delays.next() else {
+                    return Err(why);
+                };
+                let delay = with_jitter(delay, &mut rand::thread_rng());
+                attempt += 1;
+                on_retry(attempt, delay);
+                task::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Maps global key presses to messages. Space and "/" are left to the search
+/// input while it has focus so they can be typed.
+fn shortcut_message(key: Key, search_focused: bool) -> Option<Message> {
+    match key {
+        Key::Escape => Some(Message::StopRequest),
+        _ if search_focused => None,
+        key if key == Key::from_char(' ') => Some(Message::PauseRequest),
+        key if key == Key::from_char('/') => Some(Message::FocusSearch),
+        _ => None,
+    }
+}
+
+/// Click reporting is on unless `configured` off or `RRADIO_DO_NOT_TRACK` is
+/// set to something other than 0.
+fn do_not_track(configured: bool) -> bool {
+    do_not_track_with(configured, std::env::var(DO_NOT_TRACK_ENV).ok().as_deref())
+}
+
+/// `do_not_track` with `RRADIO_DO_NOT_TRACK` as `env`, `None` if unset.
+fn do_not_track_with(configured: bool, env: Option<&str>) -> bool {
+    configured || env.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn spawn_probe(tx_message: Sender<Message>, station: ApiStation) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let reachable = probe_stream(&station.url_resolved, PROBE_TIMEOUT);
+        tx_message.send(Message::ProbeFinished { station, reachable });
+    })
+}
+
+fn spawn_vote(tx_message: Sender<Message>, uuid: String) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let result = vote_for_station(&uuid);
+        tx_message.send(Message::VoteResult { uuid, result });
+    })
+}
+
+/// How long `GET /now-playing` waits for the ui to answer.
+const NOW_PLAYING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Carries out a remote control `request` by sending the ui its message.
+/// Runs on the control server's thread.
+fn control_response(tx_message: &Sender<Message>, request: ControlRequest) -> ControlResponse {
+    match request {
+        ControlRequest::Play(uuid) => tx_message.send(Message::PlayUuid(uuid)),
+        ControlRequest::Pause => tx_message.send(Message::PauseRequest),
+        ControlRequest::Stop => tx_message.send(Message::StopRequest),
+        ControlRequest::NowPlaying => {
+            let (reply, answer) = mpsc::channel();
+            tx_message.send(Message::ReportNowPlaying(reply));
+            return match answer.recv_timeout(NOW_PLAYING_TIMEOUT) {
+                Ok(report) => ControlResponse::Json(report),
+                Err(_) => ControlResponse::Unavailable("the player didn't answer".to_string()),
+            };
+        }
+    }
+
+    ControlResponse::Accepted
+}
+
+/// Tells radiobrowser the station was played, without blocking the ui.
+fn report_click(uuid: &str) {
+    let uuid = uuid.to_string();
+    thread::spawn(move || {
+        let result =
+            task::block_on(async { RadioBrowserAPI::new().await?.station_click(&uuid).await });
+        if result.is_err() {
+            Sender::<Message>::get().send(Message::ClickReportFailed);
+        }
+    });
+}
+
+/// Browser format chars coloring a column green when the station passed its
+/// last check and gray otherwise. `@.` ends formatting so names starting with
+/// `@` print as is.
+fn check_color_prefix(station: &ApiStation) -> String {
+    let color = if station.lastcheckok == 1 {
+        Color::DarkGreen
+    } else {
+        Color::Dark2
+    };
+
+    format!("@C{}@.", color.bits())
+}
+
+/// The browser columns for `station`, as picked in the config. Empty columns
+/// at the end are left off.
+fn station_columns(station: &ApiStation) -> Vec<String> {
+    let mut columns = columns()
+        .iter()
+        // A `|` in the data would start a column of its own.
+        .map(|column| column.field.value(station).replace('|', "/"))
+        .collect::<Vec<_>>();
+    while columns.last().is_some_and(|column| column.is_empty()) {
+        columns.pop();
+    }
+
+    columns
+}
+
+/// The browser row for `station`. Custom stations are never checked, so
+/// they are colored apart and tagged in their first column instead.
+fn format_station(station: &ApiStation) -> String {
+    let mut columns = station_columns(station);
+    if is_secure(station) {
+        if let Some(first) = columns.first_mut() {
+            first.insert_str(0, "🔒 ");
+        }
+    }
+    let prefix = if is_custom(station) {
+        if let Some(first) = columns.first_mut() {
+            first.push_str(" [custom]");
+        }
+        format!("@C{}@.", Color::DarkBlue.bits())
+    } else {
+        check_color_prefix(station)
+    };
+    columns
+        .iter()
+        // Format chars only apply to the column they start.
+        .map(|column| format!("{}{}", prefix, column))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stations::local_station;
+
+    /// A file of its own in the temp directory, for `name`d tests to write to.
+    pub fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("rradio-test-{}-{}", process::id(), name))
+    }
+
+    /// A station with `uuid` as its uuid and name, streaming from its own url.
+    pub fn station(uuid: &str) -> ApiStation {
+        let mut station = local_station(uuid, &format!("http://{}.example/stream", uuid));
+        station.stationuuid = uuid.to_string();
+        station
+    }
+
+    /// A path inside a directory that doesn't exist.
+    fn missing_path(name: &str) -> PathBuf {
+        temp_path("missing").join(name)
+    }
+
+    #[test]
+    fn cache_in_a_missing_directory_gives_errors() {
+        let path = missing_path(CACHE_PATH);
+
+        assert!(!is_cache_present(&path));
+        assert!(read_data_from_file_and_parse(&path).is_err());
+        assert!(write_data_to_file(&path, "[]").is_err());
+    }
+
+    #[test]
+    fn nothing_selected_gives_no_station() {
+        assert!(selection_data(&[], |_| Some(station("a"))).is_none());
+    }
+
+    #[test]
+    fn selected_placeholder_row_gives_no_station() {
+        assert!(selection_data(&[1], |_| None::<ApiStation>).is_none());
+    }
+
+    #[test]
+    fn first_selected_row_gives_its_station() {
+        let station = selection_data(&[2, 3], |line| Some(station(&line.to_string())));
+
+        assert_eq!(station.map(|station| station.name), Some("2".to_string()));
+    }
+
+    #[test]
+    fn space_and_slash_map_to_pause_and_search() {
+        let space = Key::from_char(' ');
+        let slash = Key::from_char('/');
+
+        assert!(matches!(
+            shortcut_message(space, false),
+            Some(Message::PauseRequest)
+        ));
+        assert!(matches!(
+            shortcut_message(slash, false),
+            Some(Message::FocusSearch)
+        ));
+        assert!(shortcut_message(Key::from_char('a'), false).is_none());
+    }
+
+    #[test]
+    fn typing_in_the_search_keeps_space_and_slash() {
+        assert!(shortcut_message(Key::from_char(' '), true).is_none());
+        assert!(shortcut_message(Key::from_char('/'), true).is_none());
+    }
+
+    #[test]
+    fn escape_stops_even_while_searching() {
+        assert!(matches!(
+            shortcut_message(Key::Escape, true),
+            Some(Message::StopRequest)
+        ));
+    }
+
+    /// The messages `fetch_pages` sends when `fetch_page` answers each page
+    /// with the next of `pages`.
+    fn fetched_messages(
+        limit: u32,
+        pages: Vec<Result<Vec<ApiStation>, &'static str>>,
+    ) -> Vec<Message> {
+        let sent = std::cell::RefCell::new(vec![]);
+        let mut pages = pages.into_iter();
+        task::block_on(fetch_pages(
+            limit,
+            0,
+            |_, _| {
+                let page = pages.next().expect("fetched more pages than expected");
+                async move {
+                    page.map(|stations| (stations, "mirror".to_string()))
+                        .map_err(|why| why.into())
+                }
+            },
+            |message| sent.borrow_mut().push(message),
+        ));
+
+        sent.into_inner()
+    }
+
+    #[test]
+    fn fetch_error_is_sent_as_fetch_failed() {
+        let sent = fetched_messages(10, vec![Err("mirror is down")]);
+
+        assert!(matches!(
+            sent.as_slice(),
+            [Message::FetchFailed(why)] if why == "mirror is down"
+        ));
+    }
+
+    #[test]
+    fn error_on_a_later_page_still_fails_the_fetch() {
+        let first_page = (0..FETCH_BATCH_SIZE)
+            .map(|n| station(&n.to_string()))
+            .collect();
+        let sent = fetched_messages(2 * FETCH_BATCH_SIZE, vec![Ok(first_page), Err("timed out")]);
+
+        assert!(matches!(
+            sent.as_slice(),
+            [
+                Message::FetchedFromMirror(_),
+                Message::StationsBatch { .. },
+                Message::FetchFailed(why),
+            ] if why == "timed out"
+        ));
+    }
+
+    #[test]
+    fn short_page_completes_the_fetch() {
+        let sent = fetched_messages(10, vec![Ok(vec![station("a"), station("b")])]);
+
+        assert!(matches!(
+            sent.as_slice(),
+            [
+                Message::FetchedFromMirror(mirror),
+                Message::StationsFetchedSuccess { stations, complete: true, .. },
+            ] if mirror == "mirror" && stations.len() == 2
+        ));
+    }
+
+    #[test]
+    fn fetch_runs_without_blocking_the_calling_thread() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        let caller_ran = Arc::new(AtomicBool::new(false));
+        let (release, released) = mpsc::channel::<()>();
+        let (sent, received) = mpsc::channel();
+
+        let fetch = spawn_page_fetch(
+            10,
+            0,
+            {
+                let caller_ran = caller_ran.clone();
+                move |_, _| {
+                    released.recv().unwrap();
+                    assert!(caller_ran.load(Ordering::SeqCst));
+                    async { Ok((vec![station("a")], "mirror".to_string())) }
+                }
+            },
+            move |message| sent.send(message).unwrap(),
+        );
+        // The fetch is still waiting, so getting here means it didn't block.
+        caller_ran.store(true, Ordering::SeqCst);
+        release.send(()).unwrap();
+        fetch.join().unwrap();
+
+        assert!(matches!(
+            received.try_iter().collect::<Vec<_>>().as_slice(),
+            [
+                Message::FetchedFromMirror(_),
+                Message::StationsFetchedSuccess { .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn checked_stations_are_green_and_the_rest_gray() {
+        let mut alive = station("alive");
+        alive.lastcheckok = 1;
+        let dead = station("dead");
+
+        assert_eq!(
+            check_color_prefix(&alive),
+            format!("@C{}@.", Color::DarkGreen.bits())
+        );
+        assert_eq!(
+            check_color_prefix(&dead),
+            format!("@C{}@.", Color::Dark2.bits())
+        );
+    }
+
+    #[test]
+    fn offline_never_fetches() {
+        for have_stations in [false, true] {
+            for cache_stale in [false, true] {
+                assert!(!needs_fetch(true, have_stations, cache_stale, false));
+            }
+        }
+    }
+
+    #[test]
+    fn online_fetches_without_stations_or_once_for_a_stale_cache() {
+        assert!(needs_fetch(false, false, false, true));
+        assert!(needs_fetch(false, true, true, false));
+        assert!(!needs_fetch(false, true, true, true));
+        assert!(!needs_fetch(false, true, false, false));
+    }
+
+    #[test]
+    fn clicks_are_reported_by_default() {
+        assert!(!do_not_track_with(Config::default().do_not_track, None));
+        assert!(!do_not_track_with(false, Some("")));
+        assert!(!do_not_track_with(false, Some("0")));
+    }
+
+    #[test]
+    fn config_or_environment_turn_click_reports_off() {
+        assert!(do_not_track_with(true, None));
+        assert!(do_not_track_with(false, Some("1")));
+        assert!(do_not_track_with(true, Some("0")));
+    }
+
+    #[test]
+    fn play_is_disabled_without_vlc() {
+        assert!(play_disabled(false, &Message::PlayRequest));
+        assert!(play_disabled(false, &Message::PlayStation(station("a"))));
+        assert!(!play_disabled(true, &Message::PlayRequest));
+        assert!(!play_disabled(true, &Message::PlayStation(station("a"))));
+    }
+
+    #[test]
+    fn browsing_still_works_without_vlc() {
+        assert!(!play_disabled(false, &Message::FilterStations));
+        assert!(!play_disabled(false, &Message::SubmitSearch));
+    }
+
+    #[test]
+    fn stepping_wraps_around_at_both_ends() {
+        assert_eq!(step_index(Some(2), 3, true), Some(0));
+        assert_eq!(step_index(Some(0), 3, false), Some(2));
+        assert_eq!(step_index(Some(1), 3, true), Some(2));
+        assert_eq!(step_index(Some(1), 3, false), Some(0));
+    }
+
+    #[test]
+    fn stepping_without_a_current_row_starts_at_either_end() {
+        assert_eq!(step_index(None, 3, true), Some(0));
+        assert_eq!(step_index(None, 3, false), Some(2));
+        assert_eq!(step_index(None, 0, true), None);
+        assert_eq!(step_index(Some(0), 0, false), None);
+    }
+
+    #[test]
+    fn stepping_back_from_past_the_end_counts_from_the_last_row() {
+        assert_eq!(step_index(Some(5), 3, false), Some(1));
+    }
+
+    impl StationRows for Vec<String> {
+        fn clear_rows(&mut self) {
+            self.clear();
+        }
+
+        fn add_placeholder(&mut self, message: &str) {
+            self.push(message.to_string());
+        }
+
+        fn add_stations(&mut self, stations: &[ApiStation]) {
+            self.extend(stations.iter().map(|station| station.name.clone()));
+        }
+    }
+
+    #[test]
+    fn empty_list_ends_with_the_placeholder() {
+        let mut rows = vec!["old".to_string()];
+
+        fill_rows(&mut rows, &[], empty_list_message(0));
+
+        assert_eq!(rows, ["Received 0 stations."]);
+    }
+
+    #[test]
+    fn stations_replace_the_placeholder() {
+        let mut rows = vec!["Received 0 stations.".to_string()];
+
+        fill_rows(
+            &mut rows,
+            &[station("a"), station("b")],
+            empty_list_message(0),
+        );
+
+        assert_eq!(rows, ["a", "b"]);
+    }
+
+    #[test]
+    fn filtering_to_nothing_reads_differently_from_fetching_nothing() {
+        assert_eq!(empty_list_message(0), "Received 0 stations.");
+        assert_eq!(empty_list_message(500), "No stations match the filter.");
+    }
+
+    #[test]
+    fn first_fetch_lists_the_pages_streamed_so_far() {
+        let streamed = [station("a"), station("b")];
+
+        assert_eq!(
+            streamed_view(None, &streamed, false),
+            Some(streamed.to_vec())
+        );
+    }
+
+    #[test]
+    fn appended_pages_follow_the_loaded_stations() {
+        let loaded = [station("a")];
+        let streamed = [station("b"), station("c")];
+
+        let view = streamed_view(Some(&loaded), &streamed, true).unwrap();
+
+        assert_eq!(view, [station("a"), station("b"), station("c")]);
+    }
+
+    #[test]
+    fn refetch_keeps_showing_the_loaded_stations() {
+        assert_eq!(
+            streamed_view(Some(&[station("a")]), &[station("b")], false),
+            None
+        );
+    }
+
+    #[test]
+    fn filter_is_reapplied_as_batches_arrive() {
+        let jazz = |uuid: &str| {
+            let mut station = station(uuid);
+            station.tags = "jazz".to_string();
+            station
+        };
+        let query = parse_query("tag:jazz");
+        let mut filter_cache = FilterCache::default();
+        let mut streamed = vec![jazz("a"), station("b")];
+        let shown = |filter_cache: &mut FilterCache, streamed: &[ApiStation]| {
+            let view = streamed_view(None, streamed, false).unwrap();
+            filter_cache.filter(&view, &query, SearchFields::default())
+        };
+
+        assert_eq!(shown(&mut filter_cache, &streamed), [jazz("a")]);
+
+        streamed.extend([station("c"), jazz("d")]);
+        filter_cache.invalidate();
+
+        assert_eq!(shown(&mut filter_cache, &streamed), [jazz("a"), jazz("d")]);
+    }
+
+    /// Lines 1 and 4 are headers, the rest hold stations.
+    const ROWS: [bool; 5] = [false, true, true, false, true];
+
+    #[test]
+    fn selection_skips_rows_without_data() {
+        assert_eq!(next_selectable_line(&ROWS, 3, true, false), Some(5));
+        assert_eq!(next_selectable_line(&ROWS, 5, false, false), Some(3));
+        assert_eq!(next_selectable_line(&ROWS, 2, false, false), None);
+    }
+
+    #[test]
+    fn selection_wraps_past_the_ends_only_if_asked() {
+        assert_eq!(next_selectable_line(&ROWS, 5, true, true), Some(2));
+        assert_eq!(next_selectable_line(&ROWS, 2, false, true), Some(5));
+        assert_eq!(next_selectable_line(&ROWS, 5, true, false), None);
+    }
+
+    #[test]
+    fn without_a_selection_it_starts_at_either_end() {
+        assert_eq!(next_selectable_line(&ROWS, 0, true, false), Some(2));
+        assert_eq!(next_selectable_line(&ROWS, 0, false, false), Some(5));
+    }
+
+    #[test]
+    fn list_without_stations_has_nothing_to_select() {
+        assert_eq!(next_selectable_line(&[false, false], 1, true, true), None);
+        assert_eq!(next_selectable_line(&[], 0, true, true), None);
+    }
+
+    /// A station with every field set, none left to its default.
+    fn complete_station() -> ApiStation {
+        serde_json::from_value(serde_json::json!({
+            "changeuuid": "c1",
+            "stationuuid": "960e57c5",
+            "serveruuid": "s1",
+            "name": "Jazz FM",
+            "url": "http://jazz.example/stream",
+            "url_resolved": "https://jazz.example/stream.mp3",
+            "homepage": "https://jazz.example",
+            "favicon": "https://jazz.example/icon.png",
+            "tags": "jazz,smooth",
+            "country": "Austria",
+            "countrycode": "AT",
+            "iso_3166_2": "AT-9",
+            "state": "Vienna",
+            "language": "german",
+            "languagecodes": "de",
+            "votes": 12,
+            "lastchangetime_iso8601": "2024-03-01T10:00:00Z",
+            "codec": "MP3",
+            "bitrate": 128,
+            "hls": 1,
+            "lastcheckok": 1,
+            "lastchecktime_iso8601": "2024-03-10T11:00:00Z",
+            "lastcheckoktime_iso8601": "2024-03-10T11:00:01Z",
+            "lastlocalchecktime_iso8601": "2024-03-10T11:00:02Z",
+            "clicktimestamp_iso8601": "2024-03-10T11:00:03Z",
+            "clickcount": 34,
+            "clicktrend": -2,
+            "ssl_error": 1,
+            "geo_lat": 48.21,
+            "geo_long": 16.37,
+            "has_extended_info": true,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn stations_round_trip_through_json() {
+        let station = complete_station();
+
+        let json = station_to_json(station.clone());
+
+        assert_eq!(serde_json::from_value::<ApiStation>(json).unwrap(), station);
+    }
+
+    #[test]
+    fn station_json_has_every_key_once() {
+        let json = station_to_json(complete_station());
+        let written = json.to_string();
+
+        let keys = json.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys.len(), 31);
+        for key in keys {
+            assert_eq!(
+                written.matches(&format!("\"{}\":", key)).count(),
+                1,
+                "{}",
+                key
+            );
+        }
+    }
 }
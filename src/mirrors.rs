@@ -0,0 +1,93 @@
+use std::{env, error::Error, future::Future};
+
+pub const MIRRORS_ENV: &str = "RRADIO_MIRRORS";
+pub const DEFAULT_MIRRORS: [&str; 3] = [
+    "de1.api.radio-browser.info",
+    "nl1.api.radio-browser.info",
+    "at1.api.radio-browser.info",
+];
+
+/// Mirror hostnames to try in order, from the comma separated `RRADIO_MIRRORS`
+/// or the known public servers.
+pub fn mirrors() -> Vec<String> {
+    let configured = env::var(MIRRORS_ENV)
+        .map(|mirrors| {
+            mirrors
+                .split(',')
+                .map(str::trim)
+                .filter(|mirror| !mirror.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if configured.is_empty() {
+        DEFAULT_MIRRORS
+            .iter()
+            .map(|mirror| mirror.to_string())
+            .collect()
+    } else {
+        configured
+    }
+}
+
+/// Runs `fetch` against each mirror until one succeeds, returning its result
+/// along with the mirror that answered. Fails with the last error otherwise.
+pub async fn with_failover<T, F, Fut>(
+    mirrors: &[String],
+    mut fetch: F,
+) -> Result<(T, String), Box<dyn Error>>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn Error>>>,
+{
+    let mut last_error: Box<dyn Error> = "no radiobrowser mirrors configured".into();
+    for mirror in mirrors {
+        match fetch(mirror.clone()).await {
+            Ok(value) => return Ok((value, mirror.clone())),
+            Err(why) => last_error = why,
+        }
+    }
+
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    fn hosts(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn failover_moves_on_to_the_next_mirror() {
+        let tried = RefCell::new(vec![]);
+        let result =
+            async_std::task::block_on(with_failover(&hosts(&["down", "up", "unused"]), |mirror| {
+                tried.borrow_mut().push(mirror.clone());
+                async move {
+                    if mirror == "down" {
+                        Err("connection refused".into())
+                    } else {
+                        Ok(42)
+                    }
+                }
+            }));
+
+        assert_eq!(result.unwrap(), (42, "up".to_string()));
+        assert_eq!(tried.into_inner(), ["down", "up"]);
+    }
+
+    #[test]
+    fn failover_gives_the_last_error_when_every_mirror_fails() {
+        let result =
+            async_std::task::block_on(with_failover(&hosts(&["a", "b"]), |mirror| async move {
+                Err::<(), _>(format!("{} is down", mirror).into())
+            }));
+
+        assert_eq!(result.unwrap_err().to_string(), "b is down");
+    }
+}
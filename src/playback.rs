@@ -0,0 +1,425 @@
+use std::{
+    ffi::CString,
+    fmt::{self, Display},
+    ops::RangeInclusive,
+};
+
+use fltk::app::{self, TimeoutHandle};
+use radiobrowser::ApiStation;
+use vlc::{Event, Instance, Media, MediaPlayer};
+
+/// How many milliseconds of a stream vlc buffers unless configured otherwise,
+/// the same as vlc's own default.
+pub const DEFAULT_NETWORK_CACHING_MS: u32 = 1000;
+/// Configured buffers outside this range are pulled into it.
+pub const NETWORK_CACHING_RANGE_MS: RangeInclusive<u32> = 100..=30_000;
+/// HLS plays a playlist of segments a few seconds long, so vlc buffers at
+/// least this much of them.
+const HLS_NETWORK_CACHING_MS: u32 = 3000;
+
+/// `ms` pulled into the range of buffer sizes worth asking vlc for.
+pub fn clamp_network_caching(ms: u32) -> u32 {
+    ms.clamp(
+        *NETWORK_CACHING_RANGE_MS.start(),
+        *NETWORK_CACHING_RANGE_MS.end(),
+    )
+}
+
+/// The media option buffering `ms` milliseconds, after clamping.
+pub fn network_caching_option(ms: u32) -> String {
+    format!(":network-caching={}", clamp_network_caching(ms))
+}
+
+/// The vlc media options for a stream buffering `network_caching_ms`. HLS is
+/// read through vlc's adaptive demuxer as well.
+pub fn media_options(hls: bool, network_caching_ms: u32) -> Vec<String> {
+    if hls {
+        vec![
+            network_caching_option(network_caching_ms.max(HLS_NETWORK_CACHING_MS)),
+            ":demux=adaptive".to_string(),
+        ]
+    } else {
+        vec![network_caching_option(network_caching_ms)]
+    }
+}
+
+/// Passes `option` to vlc for `media`, returning false if it can't be.
+pub fn add_media_option(media: &Media, option: &str) -> bool {
+    let Ok(option) = CString::new(option) else {
+        return false;
+    };
+    unsafe { vlc::sys::libvlc_media_add_option(media.raw(), option.as_ptr()) };
+
+    true
+}
+
+/// The url to hand vlc for `station`: the resolved one, or the one it was
+/// added with if that is missing. `None` if it has neither.
+pub fn playback_url(station: &ApiStation) -> Option<&str> {
+    [&station.url_resolved, &station.url]
+        .into_iter()
+        .map(|url| url.trim())
+        .find(|url| !url.is_empty())
+}
+
+/// The media for `station`'s stream, with the options its kind needs.
+pub fn build_media(
+    instance: &Instance,
+    station: &ApiStation,
+    network_caching_ms: u32,
+) -> Result<Media, PlayError> {
+    let url = playback_url(station).ok_or(PlayError::NoUrl)?;
+    let media = Media::new_location(instance, url).ok_or(PlayError::BadUrl)?;
+    for option in media_options(station.hls == 1, network_caching_ms) {
+        if !add_media_option(&media, &option) {
+            return Err(PlayError::BadUrl);
+        }
+    }
+
+    Ok(media)
+}
+
+/// The state vlc reaches once audio plays, when the status line goes back to
+/// showing what is playing.
+pub const PLAYING_STATUS: &str = "Playing";
+
+/// The status line text for a player state `event` from vlc, or `None` for
+/// events that don't change what the user sees. A full buffer means the
+/// stream plays again.
+pub fn player_status(event: &Event) -> Option<String> {
+    match event {
+        Event::MediaPlayerOpening => Some("Connecting…".to_string()),
+        Event::MediaPlayerBuffering(percent) if *percent < 100.0 => {
+            Some(format!("Buffering {:.0}%…", percent))
+        }
+        Event::MediaPlayerBuffering(_) | Event::MediaPlayerPlaying => {
+            Some(PLAYING_STATUS.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// `ms` as "mm:ss", or "h:mm:ss" from an hour up. Negative times count as 0.
+pub fn format_duration(ms: i64) -> String {
+    let secs = ms.max(0) / 1000;
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}", minutes, secs)
+    }
+}
+
+/// "elapsed / total" for media of a known length. Live streams report a
+/// length of 0 or less, so only the elapsed time is shown for them.
+pub fn playback_time_label(time_ms: i64, length_ms: i64) -> String {
+    if length_ms > 0 {
+        format!(
+            "{} / {}",
+            format_duration(time_ms),
+            format_duration(length_ms)
+        )
+    } else {
+        format_duration(time_ms)
+    }
+}
+
+/// The position of `player` and the length of its media in milliseconds.
+/// vlc gives -1 for a length it doesn't know.
+pub fn playback_time(player: &MediaPlayer) -> (i64, i64) {
+    let length = unsafe { vlc::sys::libvlc_media_player_get_length(player.raw()) };
+
+    (player.get_time().unwrap_or(0), length)
+}
+
+/// A player that can be paused in place, as vlc's `MediaPlayer` is.
+pub trait Pausable {
+    fn is_playing(&self) -> bool;
+    fn set_pause(&self, pause: bool);
+}
+
+impl Pausable for MediaPlayer {
+    fn is_playing(&self) -> bool {
+        MediaPlayer::is_playing(self)
+    }
+
+    fn set_pause(&self, pause: bool) {
+        MediaPlayer::set_pause(self, pause)
+    }
+}
+
+/// Pauses `player` if it is playing and resumes it otherwise, returning
+/// whether it plays now. Finite media keep their position while paused; a
+/// live stream picks up wherever it is by the time it resumes.
+pub fn toggle_pause(player: &impl Pausable) -> bool {
+    let playing = player.is_playing();
+    player.set_pause(playing);
+
+    !playing
+}
+
+const CLOCK_TICK_SECS: f64 = 1.0;
+
+/// Calls back once a second while something plays, until stopped.
+pub struct PlaybackClock {
+    handle: TimeoutHandle,
+}
+
+impl PlaybackClock {
+    pub fn start(mut tick: impl FnMut() + 'static) -> PlaybackClock {
+        let handle = app::add_timeout3(CLOCK_TICK_SECS, move |handle| {
+            tick();
+            app::repeat_timeout3(CLOCK_TICK_SECS, handle);
+        });
+
+        PlaybackClock { handle }
+    }
+
+    pub fn stop(self) {
+        app::remove_timeout3(self.handle);
+    }
+}
+
+/// What the window says for good when libvlc is missing.
+pub const VLC_MISSING: &str = "VLC not found — install libVLC to play";
+
+/// Whether libvlc can be loaded and started at all. Without it the app can
+/// still browse, search and export.
+pub fn vlc_available() -> bool {
+    Instance::new().is_some()
+}
+
+/// Why a stream couldn't be started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayError {
+    /// libvlc couldn't be loaded or initialized, usually because VLC isn't installed.
+    NoVlc,
+    /// The station has no stream url at all.
+    NoUrl,
+    /// vlc refused the stream url.
+    BadUrl,
+    /// vlc couldn't create a player.
+    NoPlayer,
+    /// The player was created but wouldn't start.
+    PlaybackFailed,
+}
+
+impl Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            PlayError::NoVlc => "Couldn't start VLC. Is VLC installed?",
+            PlayError::NoUrl => "This station has no stream url to play.",
+            PlayError::BadUrl => "VLC couldn't open this station's stream url.",
+            PlayError::NoPlayer => "VLC couldn't create a media player.",
+            PlayError::PlaybackFailed => "VLC couldn't start playing the stream.",
+        };
+
+        f.write_str(message)
+    }
+}
+
+/// A player with the stream loaded, ready to have its events attached and
+/// be started.
+pub struct ActivePlayer {
+    pub player: MediaPlayer,
+    pub media: Media,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+    use crate::stations::local_station;
+
+    /// A player that only remembers whether it plays and what it was told.
+    #[derive(Default)]
+    struct MockPlayer {
+        playing: Cell<bool>,
+        pauses: RefCell<Vec<bool>>,
+    }
+
+    impl MockPlayer {
+        fn playing() -> Self {
+            let player = MockPlayer::default();
+            player.playing.set(true);
+            player
+        }
+    }
+
+    impl Pausable for MockPlayer {
+        fn is_playing(&self) -> bool {
+            self.playing.get()
+        }
+
+        fn set_pause(&self, pause: bool) {
+            self.pauses.borrow_mut().push(pause);
+            self.playing.set(!pause);
+        }
+    }
+
+    #[test]
+    fn pausing_twice_plays_again() {
+        let player = MockPlayer::playing();
+
+        toggle_pause(&player);
+        toggle_pause(&player);
+
+        assert!(player.is_playing());
+    }
+
+    #[test]
+    fn toggling_goes_from_playing_to_paused_and_back() {
+        let player = MockPlayer::playing();
+
+        assert!(!toggle_pause(&player));
+        assert!(!player.is_playing());
+        assert!(toggle_pause(&player));
+
+        assert_eq!(*player.pauses.borrow(), [true, false]);
+    }
+
+    #[test]
+    fn toggling_a_stopped_player_starts_it() {
+        let player = MockPlayer::default();
+
+        assert!(toggle_pause(&player));
+        assert_eq!(*player.pauses.borrow(), [false]);
+    }
+
+    #[test]
+    fn each_play_error_reads_as_its_own_message() {
+        let messages = [
+            (PlayError::NoVlc, "Couldn't start VLC. Is VLC installed?"),
+            (PlayError::NoUrl, "This station has no stream url to play."),
+            (
+                PlayError::BadUrl,
+                "VLC couldn't open this station's stream url.",
+            ),
+            (PlayError::NoPlayer, "VLC couldn't create a media player."),
+            (
+                PlayError::PlaybackFailed,
+                "VLC couldn't start playing the stream.",
+            ),
+        ];
+
+        for (error, message) in messages {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn plain_streams_only_get_their_buffer_size() {
+        assert_eq!(media_options(false, 1000), [":network-caching=1000"]);
+    }
+
+    #[test]
+    fn hls_streams_buffer_more_and_use_the_adaptive_demuxer() {
+        assert_eq!(
+            media_options(true, 1000),
+            [":network-caching=3000", ":demux=adaptive"]
+        );
+        assert_eq!(
+            media_options(true, 5000),
+            [":network-caching=5000", ":demux=adaptive"]
+        );
+    }
+
+    #[test]
+    fn network_caching_is_clamped_to_a_sensible_range() {
+        assert_eq!(clamp_network_caching(0), 100);
+        assert_eq!(clamp_network_caching(1500), 1500);
+        assert_eq!(clamp_network_caching(u32::MAX), 30_000);
+    }
+
+    #[test]
+    fn network_caching_option_is_formatted_for_vlc() {
+        assert_eq!(network_caching_option(2500), ":network-caching=2500");
+        assert_eq!(network_caching_option(50), ":network-caching=100");
+    }
+
+    #[test]
+    fn player_states_map_to_status_lines() {
+        assert_eq!(
+            player_status(&Event::MediaPlayerOpening).as_deref(),
+            Some("Connecting…")
+        );
+        assert_eq!(
+            player_status(&Event::MediaPlayerBuffering(45.4)).as_deref(),
+            Some("Buffering 45%…")
+        );
+        assert_eq!(
+            player_status(&Event::MediaPlayerPlaying).as_deref(),
+            Some(PLAYING_STATUS)
+        );
+    }
+
+    #[test]
+    fn full_buffer_counts_as_playing() {
+        assert_eq!(
+            player_status(&Event::MediaPlayerBuffering(100.0)).as_deref(),
+            Some(PLAYING_STATUS)
+        );
+    }
+
+    #[test]
+    fn other_events_leave_the_status_alone() {
+        assert_eq!(player_status(&Event::MediaPlayerStopped), None);
+        assert_eq!(player_status(&Event::MediaPlayerEndReached), None);
+    }
+
+    #[test]
+    fn durations_under_an_hour_show_minutes_and_seconds() {
+        assert_eq!(format_duration(0), "00:00");
+        assert_eq!(format_duration(999), "00:00");
+        assert_eq!(format_duration(42_000), "00:42");
+        assert_eq!(format_duration(61_500), "01:01");
+        assert_eq!(format_duration(3_599_999), "59:59");
+    }
+
+    #[test]
+    fn durations_from_an_hour_show_hours_too() {
+        assert_eq!(format_duration(3_600_000), "1:00:00");
+        assert_eq!(format_duration(37_230_000), "10:20:30");
+    }
+
+    #[test]
+    fn negative_durations_count_as_zero() {
+        assert_eq!(format_duration(-1), "00:00");
+    }
+
+    #[test]
+    fn live_streams_only_show_the_elapsed_time() {
+        assert_eq!(playback_time_label(65_000, 185_000), "01:05 / 03:05");
+        assert_eq!(playback_time_label(65_000, 0), "01:05");
+        assert_eq!(playback_time_label(65_000, -1), "01:05");
+    }
+
+    fn streaming(url: &str, url_resolved: &str) -> ApiStation {
+        let mut station = local_station("radio", url);
+        station.url_resolved = url_resolved.to_string();
+        station
+    }
+
+    #[test]
+    fn the_resolved_url_plays_before_the_added_one() {
+        assert_eq!(
+            playback_url(&streaming("http://added", " http://resolved ")),
+            Some("http://resolved")
+        );
+        assert_eq!(
+            playback_url(&streaming(" http://added ", "")),
+            Some("http://added")
+        );
+        assert_eq!(
+            playback_url(&streaming("http://added", "  ")),
+            Some("http://added")
+        );
+    }
+
+    #[test]
+    fn a_station_without_any_url_has_nothing_to_play() {
+        assert_eq!(playback_url(&streaming("", "")), None);
+        assert_eq!(playback_url(&streaming(" ", "\t")), None);
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use radiobrowser::ApiStation;
+
+use crate::{stations::local_station, write_data_to_file};
+
+/// The M3U playlist for `stations`, leaving out those without a resolved url.
+pub fn render_m3u(stations: &[ApiStation]) -> String {
+    let mut playlist = String::from("#EXTM3U\n");
+    for station in stations
+        .iter()
+        .filter(|station| !station.url_resolved.trim().is_empty())
+    {
+        // A line break in the name would end the comment early.
+        let name = station.name.replace(['\r', '\n'], " ");
+        playlist.push_str(&format!("#EXTINF:-1,{}\n", name.trim()));
+        playlist.push_str(&format!("{}\n", station.url_resolved.trim()));
+    }
+
+    playlist
+}
+
+/// Writes `stations` as an M3U playlist and returns how many were written.
+pub fn export_m3u(stations: &[ApiStation], path: &Path) -> io::Result<usize> {
+    write_data_to_file(path, &render_m3u(stations))?;
+
+    Ok(stations
+        .iter()
+        .filter(|station| !station.url_resolved.trim().is_empty())
+        .count())
+}
+
+fn is_stream_url(line: &str) -> bool {
+    line.split_once("://")
+        .is_some_and(|(scheme, rest)| !scheme.is_empty() && !rest.is_empty())
+}
+
+/// Reads an M3U playlist, with or without `#EXTINF` titles. Stations without
+/// a title are named after their url, and lines that aren't urls are skipped.
+pub fn parse_m3u(contents: &str) -> Vec<ApiStation> {
+    let mut stations = vec![];
+    let mut title: Option<String> = None;
+
+    for line in contents.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            title = info
+                .split_once(',')
+                .map(|(_, title)| title.trim().to_string())
+                .filter(|title| !title.is_empty());
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else if is_stream_url(line) {
+            let name = title.take().unwrap_or_else(|| line.to_string());
+            stations.push(local_station(&name, line));
+        }
+    }
+
+    stations
+}
+
+/// Reads a PLS playlist, pairing each `FileN` with its `TitleN`.
+/// Entries without a valid url are skipped.
+pub fn parse_pls(contents: &str) -> Vec<ApiStation> {
+    let mut entries: BTreeMap<u32, (Option<String>, Option<String>)> = BTreeMap::new();
+
+    for line in contents.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+        if let Some(Ok(index)) = key.strip_prefix("file").map(str::parse::<u32>) {
+            entries.entry(index).or_default().0 = Some(value);
+        } else if let Some(Ok(index)) = key.strip_prefix("title").map(str::parse::<u32>) {
+            entries.entry(index).or_default().1 = Some(value);
+        }
+    }
+
+    entries
+        .into_values()
+        .filter_map(|(url, title)| {
+            let url = url.filter(|url| is_stream_url(url))?;
+            let name = title
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| url.clone());
+
+            Some(local_station(&name, &url))
+        })
+        .collect()
+}
+
+/// Reads the playlist at `path` as PLS or M3U, going by its extension.
+pub fn import_playlist(path: &Path) -> io::Result<Vec<ApiStation>> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let parse = match extension.as_deref() {
+        Some("pls") => parse_pls,
+        Some("m3u" | "m3u8") => parse_m3u,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "expected a .m3u, .m3u8 or .pls file",
+            ))
+        }
+    };
+
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn m3u_lists_each_station_with_its_name() {
+        let stations = [
+            local_station("Jazz FM", "http://jazz.example/stream"),
+            local_station("Broken", " "),
+            local_station("Rock\nAntenne", "https://rock.example/live.mp3"),
+        ];
+
+        assert_eq!(
+            render_m3u(&stations),
+            "#EXTM3U\n\
+             #EXTINF:-1,Jazz FM\n\
+             http://jazz.example/stream\n\
+             #EXTINF:-1,Rock Antenne\n\
+             https://rock.example/live.mp3\n"
+        );
+    }
+
+    fn names_and_urls(stations: &[ApiStation]) -> Vec<(&str, &str)> {
+        stations
+            .iter()
+            .map(|station| (station.name.as_str(), station.url_resolved.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn m3u_reads_back_what_was_exported() {
+        let stations = [
+            local_station("Jazz FM", "http://jazz.example/stream"),
+            local_station("Rock Antenne", "https://rock.example/live.mp3"),
+        ];
+
+        assert_eq!(
+            names_and_urls(&parse_m3u(&render_m3u(&stations))),
+            names_and_urls(&stations)
+        );
+    }
+
+    #[test]
+    fn m3u_without_extinf_names_stations_after_their_url() {
+        let playlist = "http://jazz.example/stream\n\nhttp://rock.example/live\n";
+
+        assert_eq!(
+            names_and_urls(&parse_m3u(playlist)),
+            [
+                ("http://jazz.example/stream", "http://jazz.example/stream"),
+                ("http://rock.example/live", "http://rock.example/live"),
+            ]
+        );
+    }
+
+    #[test]
+    fn m3u_skips_malformed_lines() {
+        let playlist = "#EXTM3U\n#EXTINF:-1,Jazz FM\nnot a url\n#EXTINF:garbage\nhttp://rock\n";
+
+        assert_eq!(
+            names_and_urls(&parse_m3u(playlist)),
+            [("http://rock", "http://rock")]
+        );
+    }
+
+    #[test]
+    fn pls_pairs_files_with_titles_by_number() {
+        let playlist = "[playlist]\n\
+                        Title2=Rock Antenne\n\
+                        File1=http://jazz.example/stream\n\
+                        Title1=Jazz FM\n\
+                        File2=http://rock.example/live\n\
+                        NumberOfEntries=2\n";
+
+        assert_eq!(
+            names_and_urls(&parse_pls(playlist)),
+            [
+                ("Jazz FM", "http://jazz.example/stream"),
+                ("Rock Antenne", "http://rock.example/live"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pls_skips_entries_without_a_valid_url() {
+        let playlist = "[playlist]\nFile1=nowhere\nTitle1=Broken\nTitle2=No file\n\
+                        File3=http://jazz\nFileX=http://ignored\nmalformed\n";
+
+        assert_eq!(
+            names_and_urls(&parse_pls(playlist)),
+            [("http://jazz", "http://jazz")]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::{future::Future, time::Duration};
+
+use async_std::{future, task};
+
+/// How long a stream gets to answer before it counts as unreachable.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Whether `probe` reports success within `timeout`. A probe still running
+/// by then counts as a failure.
+pub async fn within_timeout<F: Future<Output = bool>>(probe: F, timeout: Duration) -> bool {
+    future::timeout(timeout, probe).await.unwrap_or(false)
+}
+
+/// Asks `url` for the stream and checks that it answers with a success status.
+/// Only the headers are waited for; the body is dropped unread.
+async fn request_stream(url: &str) -> bool {
+    reqwest::get(url)
+        .await
+        .is_ok_and(|response| response.status().is_success())
+}
+
+/// Whether the stream at `url` responds within `timeout`. Blocks, so call it
+/// from a background thread.
+pub fn probe_stream(url: &str, timeout: Duration) -> bool {
+    task::block_on(within_timeout(request_stream(url), timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn probe_answering_in_time_decides() {
+        assert!(task::block_on(within_timeout(async { true }, TIMEOUT)));
+        assert!(!task::block_on(within_timeout(async { false }, TIMEOUT)));
+    }
+
+    #[test]
+    fn probe_running_past_the_timeout_fails() {
+        let hanging = future::pending::<bool>();
+
+        assert!(!task::block_on(within_timeout(hanging, TIMEOUT)));
+    }
+
+    #[test]
+    fn slow_success_still_fails() {
+        let slow = async {
+            task::sleep(TIMEOUT * 4).await;
+            true
+        };
+
+        assert!(!task::block_on(within_timeout(slow, TIMEOUT)));
+    }
+}
@@ -0,0 +1,118 @@
+use std::{env, time::Duration};
+
+pub const RECONNECT_ATTEMPTS_ENV: &str = "RRADIO_RECONNECT_ATTEMPTS";
+pub const RECONNECT_BACKOFF_ENV: &str = "RRADIO_RECONNECT_BACKOFF_SECS";
+const DEFAULT_RECONNECT_ATTEMPTS: u32 = 3;
+const DEFAULT_RECONNECT_BACKOFF_SECS: u64 = 2;
+
+/// How often and how patiently a dropped stream is reconnected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub attempts: u32,
+    /// The wait before the first attempt, doubled for every one after.
+    pub backoff: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Reads `RRADIO_RECONNECT_ATTEMPTS` and `RRADIO_RECONNECT_BACKOFF_SECS`,
+    /// falling back to 3 attempts starting at 2 seconds.
+    pub fn from_env() -> Self {
+        let attempts = env::var(RECONNECT_ATTEMPTS_ENV)
+            .ok()
+            .and_then(|attempts| attempts.trim().parse::<u32>().ok())
+            .unwrap_or(DEFAULT_RECONNECT_ATTEMPTS);
+        let backoff = env::var(RECONNECT_BACKOFF_ENV)
+            .ok()
+            .and_then(|secs| secs.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RECONNECT_BACKOFF_SECS);
+
+        ReconnectPolicy {
+            attempts,
+            backoff: Duration::from_secs(backoff),
+        }
+    }
+}
+
+/// Counts reconnect attempts for the current stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryState {
+    policy: ReconnectPolicy,
+    attempt: u32,
+}
+
+impl RetryState {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        RetryState { policy, attempt: 0 }
+    }
+
+    /// The wait before the next attempt along with its number, or `None`
+    /// once all attempts are used up.
+    pub fn next_delay(&mut self) -> Option<(u32, Duration)> {
+        if self.attempt >= self.policy.attempts {
+            return None;
+        }
+        let delay = self
+            .policy
+            .backoff
+            .saturating_mul(2u32.saturating_pow(self.attempt));
+        self.attempt += 1;
+
+        Some((self.attempt, delay))
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.policy.attempts
+    }
+
+    /// Called once a stream plays, so the next drop gets every attempt again.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: ReconnectPolicy = ReconnectPolicy {
+        attempts: 3,
+        backoff: Duration::from_secs(2),
+    };
+
+    #[test]
+    fn failing_reconnects_back_off_until_attempts_run_out() {
+        let mut retry = RetryState::new(POLICY);
+        let delays = std::iter::from_fn(|| retry.next_delay()).collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            [
+                (1, Duration::from_secs(2)),
+                (2, Duration::from_secs(4)),
+                (3, Duration::from_secs(8)),
+            ]
+        );
+        assert_eq!(retry.next_delay(), None);
+    }
+
+    #[test]
+    fn playing_again_gives_the_next_drop_every_attempt() {
+        let mut retry = RetryState::new(POLICY);
+        retry.next_delay();
+        retry.next_delay();
+
+        retry.reset();
+
+        assert_eq!(retry.next_delay(), Some((1, Duration::from_secs(2))));
+    }
+
+    #[test]
+    fn without_attempts_nothing_is_retried() {
+        let mut retry = RetryState::new(ReconnectPolicy {
+            attempts: 0,
+            ..POLICY
+        });
+
+        assert_eq!(retry.next_delay(), None);
+    }
+}
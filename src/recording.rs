@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use radiobrowser::ApiStation;
+
+use crate::filename::station_filename;
+
+/// The extension matching what a raw dump of a `codec` stream contains.
+fn codec_extension(codec: &str) -> &'static str {
+    match codec.to_lowercase().as_str() {
+        "mp3" => "mp3",
+        "aac" | "aac+" => "aac",
+        "ogg" | "vorbis" => "ogg",
+        "opus" => "opus",
+        "flac" => "flac",
+        _ => "ts",
+    }
+}
+
+/// `<name>-<yyyymmdd-hhmmss>.<ext>` for a recording of `station` started at `now`.
+pub fn recording_filename(station: &ApiStation, now: DateTime<Local>) -> String {
+    format!(
+        "{}-{}.{}",
+        station_filename(station),
+        now.format("%Y%m%d-%H%M%S"),
+        codec_extension(&station.codec)
+    )
+}
+
+/// `path`, or the first of `name-2.ext`, `name-3.ext`, … that doesn't exist yet.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (2..)
+        .map(|counter| path.with_file_name(format!("{}-{}{}", stem, counter, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("ran out of recording file names")
+}
+
+/// The vlc media option dumping the stream unchanged into `out`.
+pub fn sout_option(out: &Path) -> String {
+    let dst = out.display().to_string().replace('"', "\\\"");
+
+    format!(":sout=#std{{access=file,mux=raw,dst=\"{}\"}}", dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{stations::local_station, tests::temp_path};
+
+    #[test]
+    fn recording_is_named_after_the_station_and_start_time() {
+        let mut station = local_station("Jazz: FM / Live", "http://jazz");
+        station.codec = "MP3".to_string();
+        let started = Local.with_ymd_and_hms(2024, 3, 9, 21, 5, 30).unwrap();
+
+        assert_eq!(
+            recording_filename(&station, started),
+            "Jazz_ FM _ Live-20240309-210530.mp3"
+        );
+    }
+
+    #[test]
+    fn unknown_codecs_record_to_a_transport_stream() {
+        let station = local_station("Jazz", "http://jazz");
+        let started = Local.with_ymd_and_hms(2024, 3, 9, 21, 5, 30).unwrap();
+
+        assert!(recording_filename(&station, started).ends_with(".ts"));
+    }
+
+    #[test]
+    fn existing_recordings_are_not_overwritten() {
+        let path = temp_path("recording.mp3");
+        let taken = temp_path("recording-2.mp3");
+        fs::write(&path, "").unwrap();
+        fs::write(&taken, "").unwrap();
+
+        let unique = unique_path(&path);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&taken);
+
+        assert_eq!(unique, temp_path("recording-3.mp3"));
+        assert_eq!(unique_path(&path), path);
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+use radiobrowser::{ApiStation, RadioBrowserAPI, StationOrder};
+
+use crate::mirrors::{mirrors, with_failover};
+
+/// Filters for a server side search. Empty inputs are left out so they
+/// don't narrow the results.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RemoteQuery {
+    pub name: Option<String>,
+    pub tag: Option<String>,
+    pub country: Option<String>,
+}
+
+impl RemoteQuery {
+    pub fn new(name: &str, tag: &str, country: &str) -> Self {
+        let param = |value: &str| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+
+        RemoteQuery {
+            name: param(name),
+            tag: param(tag),
+            country: param(country),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.tag.is_none() && self.country.is_none()
+    }
+}
+
+/// Has radiobrowser filter by name, tag and country instead of searching
+/// the downloaded list. Returns up to `limit` of the most voted matches.
+pub async fn search_stations_remote(
+    name: &str,
+    tag: &str,
+    country: &str,
+    limit: u32,
+) -> Result<Vec<ApiStation>, Box<dyn Error>> {
+    let query = RemoteQuery::new(name, tag, country);
+    let (stations, _) = with_failover(&mirrors(), |mirror| {
+        let query = query.clone();
+        async move {
+            let mut search = RadioBrowserAPI::new_from_dns_a(&mirror)
+                .await?
+                .get_stations()
+                .order(StationOrder::Votes)
+                .reverse(true)
+                .limit(limit.to_string());
+            if let Some(name) = &query.name {
+                search = search.name(name);
+            }
+            if let Some(tag) = &query.tag {
+                search = search.tag(tag);
+            }
+            if let Some(country) = &query.country {
+                search = search.country(country);
+            }
+
+            search.send().await
+        }
+    })
+    .await?;
+
+    Ok(stations)
+}
+
+/// Why a station couldn't be looked up again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshError {
+    /// radiobrowser doesn't list the station any more.
+    Deleted,
+    Failed(String),
+}
+
+impl Display for RefreshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefreshError::Deleted => f.write_str("the station is no longer on radiobrowser"),
+            RefreshError::Failed(why) => write!(f, "couldn't look up the station: {}", why),
+        }
+    }
+}
+
+impl Error for RefreshError {}
+
+/// The station with `uuid` as radiobrowser lists it now, with its stream url
+/// resolved afresh.
+pub async fn refresh_station(uuid: &str) -> Result<ApiStation, RefreshError> {
+    let (stations, _) = with_failover(&mirrors(), |mirror| {
+        let endpoint = format!("/json/stations/byuuid/{}", uuid);
+        async move {
+            RadioBrowserAPI::new_from_dns_a(&mirror)
+                .await?
+                .send::<_, Vec<ApiStation>>(endpoint, HashMap::new())
+                .await
+        }
+    })
+    .await
+    .map_err(|why| RefreshError::Failed(why.to_string()))?;
+
+    stations
+        .into_iter()
+        .find(|station| station.stationuuid == uuid)
+        .ok_or(RefreshError::Deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_keeps_the_given_inputs_trimmed() {
+        assert_eq!(
+            RemoteQuery::new(" Jazz FM ", "smooth", "Germany"),
+            RemoteQuery {
+                name: Some("Jazz FM".to_string()),
+                tag: Some("smooth".to_string()),
+                country: Some("Germany".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn empty_inputs_are_left_out_of_the_query() {
+        let query = RemoteQuery::new("", "  ", "Germany");
+
+        assert_eq!(query.name, None);
+        assert_eq!(query.tag, None);
+        assert_eq!(query.country.as_deref(), Some("Germany"));
+        assert!(!query.is_empty());
+        assert!(RemoteQuery::new(" ", "", "\t").is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use std::{error::Error, future::Future, io, time::Duration};
+
+use async_std::future;
+use rand::Rng;
+
+/// The message of the `TimedOut` error a request past its deadline fails with.
+pub const TIMED_OUT: &str = "request timed out";
+
+/// The wait before the first retry, doubled for every one after.
+pub const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// The waits between `max_attempts` tries: 1s, 2s, 4s and so on, one fewer
+/// than there are attempts.
+pub fn backoff_schedule(max_attempts: u32, base: Duration) -> Vec<Duration> {
+    (0..max_attempts.saturating_sub(1))
+        .map(|retry| base.saturating_mul(2u32.saturating_pow(retry)))
+        .collect()
+}
+
+/// Stretches `delay` by up to a quarter so clients that failed together
+/// don't all retry at the same moment.
+pub fn with_jitter<R: Rng>(delay: Duration, rng: &mut R) -> Duration {
+    delay.mul_f64(1.0 + rng.gen_range(0.0..0.25))
+}
+
+/// Whether `why` is a network hiccup worth retrying: a timeout, a refused
+/// or dropped connection, or a server error. Answers that arrived but
+/// couldn't be read would fail the same way again.
+pub fn is_transient(why: &(dyn Error + 'static)) -> bool {
+    if let Some(why) = why.downcast_ref::<reqwest::Error>() {
+        return why.is_timeout()
+            || why.is_connect()
+            || (why.is_request() && !why.is_builder())
+            || why.status().is_some_and(|status| status.is_server_error());
+    }
+
+    why.is::<io::Error>()
+}
+
+/// Runs `request`, failing it with a `TimedOut` io error if it doesn't
+/// finish within `deadline`, which `is_transient` retries like any timeout.
+pub async fn with_deadline<T, F>(request: F, deadline: Duration) -> Result<T, Box<dyn Error>>
+where
+    F: Future<Output = Result<T, Box<dyn Error>>>,
+{
+    future::timeout(deadline, request)
+        .await
+        .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, TIMED_OUT).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_between_each_attempt() {
+        assert_eq!(
+            backoff_schedule(4, RETRY_BASE_DELAY),
+            [1, 2, 4].map(Duration::from_secs)
+        );
+    }
+
+    #[test]
+    fn single_attempt_has_nothing_to_wait_for() {
+        assert!(backoff_schedule(1, RETRY_BASE_DELAY).is_empty());
+        assert!(backoff_schedule(0, RETRY_BASE_DELAY).is_empty());
+    }
+
+    #[test]
+    fn huge_attempt_counts_saturate_instead_of_overflowing() {
+        let schedule = backoff_schedule(40, RETRY_BASE_DELAY);
+
+        assert_eq!(schedule.len(), 39);
+        assert!(schedule.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn jitter_stretches_by_up_to_a_quarter() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let delay = Duration::from_secs(4);
+
+        for _ in 0..100 {
+            let jittered = with_jitter(delay, &mut rng);
+            assert!(jittered >= delay && jittered < Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn io_errors_are_transient_and_others_are_not() {
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        let unreadable: Box<dyn Error> = "unexpected answer".into();
+
+        assert!(is_transient(&refused));
+        assert!(!is_transient(unreadable.as_ref()));
+    }
+
+    #[test]
+    fn request_that_never_resolves_times_out() {
+        let never = std::future::pending::<Result<(), Box<dyn Error>>>();
+
+        let result = async_std::task::block_on(with_deadline(never, Duration::from_millis(20)));
+
+        let why = result.unwrap_err();
+        assert_eq!(why.to_string(), TIMED_OUT);
+        assert_eq!(
+            why.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(io::ErrorKind::TimedOut)
+        );
+        assert!(is_transient(why.as_ref()));
+    }
+
+    #[test]
+    fn request_finishing_in_time_keeps_its_result() {
+        let answered = async { Ok::<_, Box<dyn Error>>(42) };
+
+        let result = async_std::task::block_on(with_deadline(answered, Duration::from_secs(1)));
+
+        assert_eq!(result.unwrap(), 42);
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::Local;
+use radiobrowser::ApiStation;
+
+pub const SCROBBLES_PATH: &str = "scrobbles.csv";
+const SCROBBLES_HEADER: [&str; 5] = ["timestamp", "event", "name", "uuid", "url"];
+
+/// Playback of a station starting or stopping, as one row of `scrobbles.csv`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrobbleEvent {
+    /// When it happened, in RFC 3339 local time.
+    pub timestamp: String,
+    /// "start" or "stop".
+    pub event: &'static str,
+    pub name: String,
+    pub uuid: String,
+    pub url: String,
+}
+
+impl ScrobbleEvent {
+    pub fn started(station: &ApiStation) -> Self {
+        ScrobbleEvent::now("start", station)
+    }
+
+    pub fn stopped(station: &ApiStation) -> Self {
+        ScrobbleEvent::now("stop", station)
+    }
+
+    fn now(event: &'static str, station: &ApiStation) -> Self {
+        ScrobbleEvent {
+            timestamp: Local::now().to_rfc3339(),
+            event,
+            name: station.name.trim().to_string(),
+            uuid: station.stationuuid.clone(),
+            url: station.url_resolved.clone(),
+        }
+    }
+
+    /// The row for this event, line break included.
+    pub fn csv_row(&self) -> String {
+        csv_row(&[
+            &self.timestamp,
+            self.event,
+            &self.name,
+            &self.uuid,
+            &self.url,
+        ])
+    }
+}
+
+/// `field` as a CSV field. Fields with commas, quotes or line breaks are
+/// quoted, with their quotes doubled.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `fields` escaped and joined into one CSV line.
+pub fn csv_row(fields: &[&str]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>();
+
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Appends `event` to `scrobbles.csv`, starting the file with a header.
+pub fn log_scrobble(event: ScrobbleEvent) -> io::Result<()> {
+    log_scrobble_to(Path::new(SCROBBLES_PATH), &event)
+}
+
+pub fn log_scrobble_to(path: &Path, event: &ScrobbleEvent) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        file.write_all(csv_row(&SCROBBLES_HEADER).as_bytes())?;
+    }
+
+    file.write_all(event.csv_row().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_path;
+    use std::fs;
+
+    fn played(name: &str) -> ScrobbleEvent {
+        ScrobbleEvent {
+            timestamp: "2024-03-10T12:00:00+01:00".to_string(),
+            event: "start",
+            name: name.to_string(),
+            uuid: "960e57c5".to_string(),
+            url: "http://radio.example/stream".to_string(),
+        }
+    }
+
+    #[test]
+    fn plain_fields_are_left_as_they_are() {
+        assert_eq!(csv_row(&["Jazz FM", "", "a b"]), "Jazz FM,,a b\r\n");
+    }
+
+    #[test]
+    fn commas_quotes_and_line_breaks_get_quoted() {
+        assert_eq!(csv_field("Rock, Pop"), "\"Rock, Pop\"");
+        assert_eq!(csv_field("Radio \"Eins\""), "\"Radio \"\"Eins\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(
+            played("Jazz, \"Smooth\"").csv_row(),
+            "2024-03-10T12:00:00+01:00,start,\"Jazz, \"\"Smooth\"\"\",960e57c5,http://radio.example/stream\r\n"
+        );
+    }
+
+    #[test]
+    fn the_header_is_only_written_to_a_new_file() {
+        let path = temp_path("scrobbles.csv");
+        let _ = fs::remove_file(&path);
+
+        log_scrobble_to(&path, &played("Jazz FM")).unwrap();
+        log_scrobble_to(&path, &played("Jazz FM")).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let row = played("Jazz FM").csv_row();
+        assert_eq!(
+            written,
+            format!("timestamp,event,name,uuid,url\r\n{}{}", row, row)
+        );
+    }
+}
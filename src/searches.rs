@@ -0,0 +1,77 @@
+use std::{io, path::Path};
+
+use crate::{read_data_from_file_and_parse, write_data_to_file};
+
+pub const SEARCHES_PATH: &str = "searches.json";
+/// How many searches are remembered.
+pub const MAX_SEARCHES: usize = 20;
+
+/// Returns the remembered searches, most recent first, or none if there are
+/// none yet.
+pub fn load_searches() -> Vec<String> {
+    match read_data_from_file_and_parse(Path::new(SEARCHES_PATH)) {
+        Ok(parsed) => parsed
+            .members()
+            .filter_map(|term| term.as_str().map(String::from))
+            .take(MAX_SEARCHES)
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+pub fn save_searches(searches: &[String]) -> io::Result<()> {
+    let data = serde_json::to_string(searches)?;
+
+    write_data_to_file(Path::new(SEARCHES_PATH), &data)
+}
+
+/// Puts `term` in front of `history`, moving it there if it was searched
+/// before, and forgets the oldest past `MAX_SEARCHES`. Blank terms are
+/// ignored.
+pub fn push_search(history: &mut Vec<String>, term: &str) {
+    let term = term.trim();
+    if term.is_empty() {
+        return;
+    }
+    history.retain(|past| past != term);
+    history.insert(0, term.to_string());
+    history.truncate(MAX_SEARCHES);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_search_comes_first_once() {
+        let mut history = vec![];
+
+        push_search(&mut history, "jazz");
+        push_search(&mut history, "rock");
+        push_search(&mut history, " jazz ");
+
+        assert_eq!(history, ["jazz", "rock"]);
+    }
+
+    #[test]
+    fn blank_searches_are_not_recorded() {
+        let mut history = vec!["jazz".to_string()];
+
+        push_search(&mut history, "");
+        push_search(&mut history, " \t");
+
+        assert_eq!(history, ["jazz"]);
+    }
+
+    #[test]
+    fn oldest_searches_are_forgotten_past_the_cap() {
+        let mut history = vec![];
+        for n in 0..MAX_SEARCHES + 5 {
+            push_search(&mut history, &n.to_string());
+        }
+
+        assert_eq!(history.len(), MAX_SEARCHES);
+        assert_eq!(history[0], (MAX_SEARCHES + 4).to_string());
+        assert_eq!(history[MAX_SEARCHES - 1], "5");
+    }
+}
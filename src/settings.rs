@@ -0,0 +1,338 @@
+use std::ops::RangeInclusive;
+
+use fltk::{
+    button::{Button, CheckButton},
+    enums::{Align, Color},
+    frame::Frame,
+    input::IntInput,
+    menu::Choice,
+    prelude::{GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    window::Window,
+};
+
+use crate::{config::Config, playback::NETWORK_CACHING_RANGE_MS, theme::Theme};
+
+const VOLUME_RANGE: RangeInclusive<u32> = 0..=100;
+/// Up to a year. 0 fetches again on every start.
+const CACHE_TTL_RANGE_HOURS: RangeInclusive<u32> = 0..=24 * 365;
+const FETCH_LIMIT_RANGE: RangeInclusive<u32> = 1..=100_000;
+
+/// The settings the form edits, checked and ready to go into the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub volume: i32,
+    pub cache_ttl_hours: u64,
+    pub fetch_limit: u32,
+    pub network_caching_ms: u32,
+    pub theme: Theme,
+    pub do_not_track: bool,
+}
+
+impl Settings {
+    pub fn from_config(config: &Config) -> Self {
+        Settings {
+            volume: config.volume,
+            cache_ttl_hours: config.cache_ttl_hours,
+            fetch_limit: config.fetch_limit,
+            network_caching_ms: config.network_caching_ms,
+            theme: config.theme,
+            do_not_track: config.do_not_track,
+        }
+    }
+
+    /// Writes these settings into `config`, leaving everything else alone.
+    pub fn apply(&self, config: &mut Config) {
+        config.volume = self.volume;
+        config.cache_ttl_hours = self.cache_ttl_hours;
+        config.fetch_limit = self.fetch_limit;
+        config.network_caching_ms = self.network_caching_ms;
+        config.theme = self.theme;
+        config.do_not_track = self.do_not_track;
+    }
+}
+
+/// A field of the form that takes a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsField {
+    Volume,
+    CacheTtl,
+    FetchLimit,
+    NetworkCaching,
+}
+
+/// The form as typed, before it is checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsForm {
+    pub volume: String,
+    pub cache_ttl_hours: String,
+    pub fetch_limit: String,
+    pub network_caching_ms: String,
+    pub theme: Theme,
+    pub do_not_track: bool,
+}
+
+impl SettingsForm {
+    pub fn from_settings(settings: &Settings) -> Self {
+        SettingsForm {
+            volume: settings.volume.to_string(),
+            cache_ttl_hours: settings.cache_ttl_hours.to_string(),
+            fetch_limit: settings.fetch_limit.to_string(),
+            network_caching_ms: settings.network_caching_ms.to_string(),
+            theme: settings.theme,
+            do_not_track: settings.do_not_track,
+        }
+    }
+
+    /// The settings typed in, or what is wrong with each field that isn't a
+    /// whole number in its range.
+    pub fn validate(&self) -> Result<Settings, Vec<(SettingsField, String)>> {
+        let mut errors = vec![];
+        let mut check = |field, text: &str, range| {
+            parse_in_range(text, range)
+                .map_err(|why| errors.push((field, why)))
+                .unwrap_or_default()
+        };
+        let volume = check(SettingsField::Volume, &self.volume, VOLUME_RANGE);
+        let cache_ttl_hours = check(
+            SettingsField::CacheTtl,
+            &self.cache_ttl_hours,
+            CACHE_TTL_RANGE_HOURS,
+        );
+        let fetch_limit = check(
+            SettingsField::FetchLimit,
+            &self.fetch_limit,
+            FETCH_LIMIT_RANGE,
+        );
+        let network_caching_ms = check(
+            SettingsField::NetworkCaching,
+            &self.network_caching_ms,
+            NETWORK_CACHING_RANGE_MS,
+        );
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Settings {
+            volume: volume as i32,
+            cache_ttl_hours: cache_ttl_hours as u64,
+            fetch_limit,
+            network_caching_ms,
+            theme: self.theme,
+            do_not_track: self.do_not_track,
+        })
+    }
+}
+
+/// `text` as a whole number within `range`, or why it isn't one.
+fn parse_in_range(text: &str, range: RangeInclusive<u32>) -> Result<u32, String> {
+    let out_of_range = || format!("use {} to {}", range.start(), range.end());
+    let value = text.trim().parse::<u32>().map_err(|_| out_of_range())?;
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(out_of_range())
+    }
+}
+
+const THEMES: [(Theme, &str); 2] = [(Theme::Dark, "Dark"), (Theme::Light, "Light")];
+const ROW_HEIGHT: i32 = 30;
+const LABEL_WIDTH: i32 = 150;
+const INPUT_WIDTH: i32 = 80;
+
+/// Opens the settings as a window of its own, filled from `config`. Saving
+/// with every field valid closes it and hands the settings to `on_save`;
+/// otherwise each invalid field says what it takes next to it.
+pub fn open_settings_window(config: &Config, mut on_save: impl FnMut(Settings) + 'static) {
+    let form = SettingsForm::from_settings(&Settings::from_config(config));
+    let mut window = Window::default()
+        .with_size(380, 8 * ROW_HEIGHT)
+        .with_label("Settings");
+    let mut row = 0;
+    let mut number_field = |label: &str, value: &str| {
+        let y = 10 + row * ROW_HEIGHT;
+        row += 1;
+        let mut input = IntInput::new(10 + LABEL_WIDTH, y, INPUT_WIDTH, ROW_HEIGHT - 4, None);
+        input.set_label(label);
+        input.set_value(value);
+        let mut error = Frame::new(
+            input.x() + INPUT_WIDTH + 5,
+            y,
+            380 - input.x() - INPUT_WIDTH - 10,
+            ROW_HEIGHT - 4,
+            None,
+        );
+        error.set_align(Align::Left | Align::Inside);
+        error.set_label_color(Color::Red);
+        (input, error)
+    };
+    let fields = [
+        (SettingsField::Volume, number_field("Volume", &form.volume)),
+        (
+            SettingsField::CacheTtl,
+            number_field("Cache lifetime (h)", &form.cache_ttl_hours),
+        ),
+        (
+            SettingsField::FetchLimit,
+            number_field("Stations per fetch", &form.fetch_limit),
+        ),
+        (
+            SettingsField::NetworkCaching,
+            number_field("Buffering (ms)", &form.network_caching_ms),
+        ),
+    ];
+    let mut theme = Choice::new(
+        10 + LABEL_WIDTH,
+        10 + 4 * ROW_HEIGHT,
+        INPUT_WIDTH,
+        ROW_HEIGHT - 4,
+        "Theme",
+    );
+    for (_, name) in THEMES {
+        theme.add_choice(name);
+    }
+    theme.set_value(
+        THEMES
+            .iter()
+            .position(|(option, _)| *option == form.theme)
+            .unwrap_or(0) as i32,
+    );
+    let do_not_track = CheckButton::new(
+        10 + LABEL_WIDTH,
+        10 + 5 * ROW_HEIGHT,
+        200,
+        ROW_HEIGHT - 4,
+        "Don't report plays",
+    );
+    do_not_track.set_checked(form.do_not_track);
+    let mut save = Button::new(380 - 170, 10 + 6 * ROW_HEIGHT + 10, 75, ROW_HEIGHT, "Save");
+    let mut cancel = Button::new(380 - 85, save.y(), 75, ROW_HEIGHT, "Cancel");
+    window.end();
+    window.make_modal(true);
+    window.show();
+
+    cancel.set_callback({
+        let mut window = window.clone();
+        move |_| window.hide()
+    });
+    save.set_callback(move |_| {
+        let value = |field| {
+            fields
+                .iter()
+                .find(|(known, _)| *known == field)
+                .map(|(_, (input, _))| input.value())
+                .unwrap_or_default()
+        };
+        let form = SettingsForm {
+            volume: value(SettingsField::Volume),
+            cache_ttl_hours: value(SettingsField::CacheTtl),
+            fetch_limit: value(SettingsField::FetchLimit),
+            network_caching_ms: value(SettingsField::NetworkCaching),
+            theme: THEMES
+                .get(theme.value().max(0) as usize)
+                .map_or(Theme::default(), |(theme, _)| *theme),
+            do_not_track: do_not_track.is_checked(),
+        };
+        match form.validate() {
+            Ok(settings) => {
+                window.hide();
+                on_save(settings);
+            }
+            Err(errors) => {
+                for (field, (_, error)) in fields.iter() {
+                    let mut error = error.clone();
+                    match errors.iter().find(|(invalid, _)| invalid == field) {
+                        Some((_, why)) => error.set_label(why),
+                        None => error.set_label(""),
+                    }
+                    error.redraw();
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> SettingsForm {
+        SettingsForm {
+            volume: " 55 ".to_string(),
+            cache_ttl_hours: "48".to_string(),
+            fetch_limit: "2000".to_string(),
+            network_caching_ms: "3000".to_string(),
+            theme: Theme::Light,
+            do_not_track: true,
+        }
+    }
+
+    #[test]
+    fn a_valid_form_goes_into_the_config() {
+        let mut config = Config::default();
+
+        form().validate().unwrap().apply(&mut config);
+
+        assert_eq!(
+            config,
+            Config {
+                volume: 55,
+                cache_ttl_hours: 48,
+                fetch_limit: 2000,
+                network_caching_ms: 3000,
+                theme: Theme::Light,
+                do_not_track: true,
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn the_form_shows_the_config_as_it_is() {
+        let config = Config::default();
+        let settings = Settings::from_config(&config);
+
+        assert_eq!(
+            SettingsForm::from_settings(&settings).validate(),
+            Ok(settings)
+        );
+    }
+
+    #[test]
+    fn out_of_range_fields_are_each_reported() {
+        let form = SettingsForm {
+            volume: "101".to_string(),
+            fetch_limit: "0".to_string(),
+            network_caching_ms: "99".to_string(),
+            ..form()
+        };
+
+        assert_eq!(
+            form.validate(),
+            Err(vec![
+                (SettingsField::Volume, "use 0 to 100".to_string()),
+                (SettingsField::FetchLimit, "use 1 to 100000".to_string()),
+                (
+                    SettingsField::NetworkCaching,
+                    "use 100 to 30000".to_string()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn fields_that_arent_whole_numbers_are_reported() {
+        for typed in ["", "-1", "1.5", "twelve"] {
+            let form = SettingsForm {
+                cache_ttl_hours: typed.to_string(),
+                ..form()
+            };
+
+            assert_eq!(
+                form.validate(),
+                Err(vec![(SettingsField::CacheTtl, "use 0 to 8760".to_string())]),
+                "{:?}",
+                typed
+            );
+        }
+    }
+}
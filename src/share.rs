@@ -0,0 +1,62 @@
+use radiobrowser::ApiStation;
+
+use crate::{custom::is_custom, homepage::is_web_url};
+
+/// Prefixes a station uuid to get a playlist of it from any radiobrowser
+/// mirror. Opening it counts as a click, like playing it here does.
+const SHARE_URL_BASE: &str = "https://all.api.radio-browser.info/m3u/url/";
+
+/// A link to pass `station` on with: its radiobrowser link if it came from
+/// radiobrowser, otherwise its homepage, otherwise its stream.
+pub fn station_share_url(station: &ApiStation) -> String {
+    let uuid = station.stationuuid.trim();
+    if !uuid.is_empty() && !is_custom(station) {
+        return format!("{}{}", SHARE_URL_BASE, uuid);
+    }
+    if is_web_url(station.homepage.trim()) {
+        return station.homepage.trim().to_string();
+    }
+
+    station.url_resolved.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::station;
+
+    fn shared(uuid: &str, homepage: &str) -> String {
+        let mut station = station(uuid);
+        station.homepage = homepage.to_string();
+        station_share_url(&station)
+    }
+
+    #[test]
+    fn radiobrowser_stations_share_their_uuid_link() {
+        assert_eq!(
+            shared(" 960e57c5 ", "https://radio.example.org"),
+            "https://all.api.radio-browser.info/m3u/url/960e57c5"
+        );
+    }
+
+    #[test]
+    fn stations_without_a_uuid_share_their_homepage() {
+        assert_eq!(
+            shared("", " https://radio.example.org "),
+            "https://radio.example.org"
+        );
+        assert_eq!(
+            shared("custom-1", "https://radio.example.org"),
+            "https://radio.example.org"
+        );
+    }
+
+    #[test]
+    fn the_stream_is_shared_without_a_web_homepage() {
+        assert_eq!(shared("", ""), "http://.example/stream");
+        assert_eq!(
+            shared("custom-1", "radio.example.org"),
+            "http://custom-1.example/stream"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::io;
+
+/// One thing to do on the way out, such as saving a file, named for the log.
+pub type ShutdownStep<'a> = (&'a str, Box<dyn FnOnce() -> io::Result<()> + 'a>);
+
+/// Makes sure the shutdown runs once, however often the window is closed.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    done: bool,
+}
+
+impl Shutdown {
+    /// Runs every step in order, carrying on past failures, and returns them
+    /// as `name: error`. Returns `None` without running anything if the
+    /// shutdown already ran.
+    pub fn run(&mut self, steps: Vec<ShutdownStep>) -> Option<Vec<String>> {
+        if self.done {
+            return None;
+        }
+        self.done = true;
+
+        Some(
+            steps
+                .into_iter()
+                .filter_map(|(name, step)| step().err().map(|why| format!("{}: {}", name, why)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// A save that notes down its name, failing if `fails`.
+    fn save<'a>(name: &'a str, saved: &'a RefCell<Vec<&'a str>>, fails: bool) -> ShutdownStep<'a> {
+        (
+            name,
+            Box::new(move || {
+                saved.borrow_mut().push(name);
+                if fails {
+                    Err(io::Error::other("disk full"))
+                } else {
+                    Ok(())
+                }
+            }),
+        )
+    }
+
+    #[test]
+    fn every_save_runs_in_order_past_failures() {
+        let saved = RefCell::new(vec![]);
+        let mut shutdown = Shutdown::default();
+
+        let failures = shutdown.run(vec![
+            save("favorites", &saved, false),
+            save("history", &saved, true),
+            save("volume", &saved, false),
+        ]);
+
+        assert_eq!(failures, Some(vec!["history: disk full".to_string()]));
+        assert_eq!(*saved.borrow(), ["favorites", "history", "volume"]);
+    }
+
+    #[test]
+    fn closing_twice_saves_once() {
+        let saved = RefCell::new(vec![]);
+        let mut shutdown = Shutdown::default();
+
+        assert_eq!(
+            shutdown.run(vec![save("favorites", &saved, false)]),
+            Some(vec![])
+        );
+        assert_eq!(shutdown.run(vec![save("favorites", &saved, false)]), None);
+        assert_eq!(*saved.borrow(), ["favorites"]);
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use fltk::app::{self, Sender, TimeoutHandle};
+
+use crate::Message;
+
+const TICK_SECS: f64 = 60.;
+
+/// A pending stop, cancelled by dropping it through `cancel`.
+pub struct SleepTimer {
+    deadline: Instant,
+    handle: TimeoutHandle,
+}
+
+impl SleepTimer {
+    pub fn minutes_left(&self) -> u64 {
+        remaining_minutes(self.deadline, Instant::now())
+    }
+
+    pub fn cancel(self) {
+        app::remove_timeout3(self.handle);
+    }
+}
+
+/// Whole minutes left until `deadline`, rounded up so the countdown only
+/// reaches 0 when the timer fires.
+pub fn remaining_minutes(deadline: Instant, now: Instant) -> u64 {
+    deadline
+        .saturating_duration_since(now)
+        .as_secs()
+        .div_ceil(60)
+}
+
+/// Sends a `SleepTimerTick` every minute and a `StopRequest` once `minutes` have passed.
+pub fn schedule_sleep_timer(minutes: u64) -> SleepTimer {
+    let tx_message = Sender::<Message>::get();
+    let deadline = Instant::now() + Duration::from_secs(minutes * 60);
+    let handle = app::add_timeout3(TICK_SECS, move |handle| {
+        let remaining = remaining_minutes(deadline, Instant::now());
+        if remaining == 0 {
+            tx_message.send(Message::StopRequest);
+        } else {
+            tx_message.send(Message::SleepTimerTick(remaining));
+            app::repeat_timeout3(TICK_SECS, handle);
+        }
+    });
+
+    SleepTimer { deadline, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_minutes_round_up() {
+        let now = Instant::now();
+
+        assert_eq!(
+            remaining_minutes(now + Duration::from_secs(30 * 60), now),
+            30
+        );
+        assert_eq!(
+            remaining_minutes(now + Duration::from_secs(29 * 60 + 1), now),
+            30
+        );
+        assert_eq!(remaining_minutes(now + Duration::from_secs(1), now), 1);
+    }
+
+    #[test]
+    fn nothing_remains_once_the_deadline_has_passed() {
+        let now = Instant::now();
+
+        assert_eq!(remaining_minutes(now, now), 0);
+        assert_eq!(remaining_minutes(now, now + Duration::from_secs(60)), 0);
+    }
+}
@@ -0,0 +1,32 @@
+use fltk::{
+    app::{self, TimeoutHandle},
+    text::SimpleTerminal,
+};
+
+const FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+const FRAME_SECS: f64 = 0.1;
+
+/// An animated indicator in the status bar, running until stopped.
+pub struct Spinner {
+    handle: TimeoutHandle,
+}
+
+impl Spinner {
+    pub fn start(status: &SimpleTerminal, label: &str) -> Spinner {
+        let mut status = status.clone();
+        let label = label.to_string();
+        let mut frame = 0;
+        status.set_text(&format!("{} {}", label, FRAMES[frame]));
+        let handle = app::add_timeout3(FRAME_SECS, move |handle| {
+            frame = (frame + 1) % FRAMES.len();
+            status.set_text(&format!("{} {}", label, FRAMES[frame]));
+            app::repeat_timeout3(FRAME_SECS, handle);
+        });
+
+        Spinner { handle }
+    }
+
+    pub fn stop(self) {
+        app::remove_timeout3(self.handle);
+    }
+}
@@ -0,0 +1,714 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use radiobrowser::ApiStation;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::geo::{compare_distance, station_coordinates};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Country,
+    Bitrate,
+    Votes,
+    /// Nearest first from this latitude and longitude.
+    Distance((f64, f64)),
+}
+
+/// Stable sort by `key`. Stations without a name always end up last,
+/// whichever direction is chosen, as do stations without coordinates when
+/// sorting by distance.
+pub fn sort_stations(stations: &mut [ApiStation], key: SortKey, ascending: bool) {
+    stations.sort_by(|a, b| {
+        let ordering = compare_by(a, b, key);
+        if ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    });
+    if let SortKey::Distance(_) = key {
+        stations.sort_by_key(|station| station_coordinates(station).is_none());
+    }
+    stations.sort_by_key(|station| station.name.trim().is_empty());
+}
+
+/// The order to sort by when `key` is picked while sorted by `current`:
+/// the same key again reverses it, any other starts ascending.
+pub fn next_sort_order(current: Option<(SortKey, bool)>, key: SortKey) -> (SortKey, bool) {
+    match current {
+        Some((current, ascending)) if current == key => (key, !ascending),
+        _ => (key, true),
+    }
+}
+
+fn compare_by(a: &ApiStation, b: &ApiStation, key: SortKey) -> Ordering {
+    match key {
+        SortKey::Name => a
+            .name
+            .trim()
+            .to_lowercase()
+            .cmp(&b.name.trim().to_lowercase()),
+        SortKey::Country => a.country.to_lowercase().cmp(&b.country.to_lowercase()),
+        SortKey::Bitrate => a.bitrate.cmp(&b.bitrate),
+        SortKey::Votes => a.votes.cmp(&b.votes),
+        SortKey::Distance(home) => compare_distance(a, b, home),
+    }
+}
+
+/// Whether `a` and `b` are the same station, however their votes, clicks or
+/// check results differ. Stations without a uuid compare by stream url.
+pub fn same_station(a: &ApiStation, b: &ApiStation) -> bool {
+    if a.stationuuid.is_empty() && b.stationuuid.is_empty() {
+        !a.url_resolved.is_empty() && a.url_resolved == b.url_resolved
+    } else {
+        a.stationuuid == b.stationuuid
+    }
+}
+
+/// Whether `station` streams over https and passed its last TLS check.
+pub fn is_secure(station: &ApiStation) -> bool {
+    is_https(&station.url_resolved) && station.ssl_error.unwrap_or(0) == 0
+}
+
+fn is_https(url: &str) -> bool {
+    url.trim()
+        .get(..8)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"))
+}
+
+/// The https url of `station`, its resolved url first, if either is one.
+pub fn secure_stream_url(station: &ApiStation) -> Option<&str> {
+    [&station.url_resolved, &station.url]
+        .into_iter()
+        .find(|url| is_https(url))
+        .map(|url| url.trim())
+}
+
+/// The station with `uuid`, if it is among `stations`.
+pub fn find_station_by_uuid<'a>(stations: &'a [ApiStation], uuid: &str) -> Option<&'a ApiStation> {
+    stations.iter().find(|station| station.stationuuid == uuid)
+}
+
+/// Puts `updated` in place of every station with its uuid, returning
+/// whether there was one.
+pub fn replace_station(stations: &mut [ApiStation], updated: &ApiStation) -> bool {
+    let mut replaced = false;
+    for station in stations
+        .iter_mut()
+        .filter(|station| station.stationuuid == updated.stationuuid)
+    {
+        *station = updated.clone();
+        replaced = true;
+    }
+
+    replaced
+}
+
+/// Drops the stations with `uuid`, returning whether there were any.
+pub fn remove_station(stations: &mut Vec<ApiStation>, uuid: &str) -> bool {
+    let before = stations.len();
+    stations.retain(|station| station.stationuuid != uuid);
+
+    stations.len() != before
+}
+
+/// Sorted, deduplicated country names, skipping stations without one.
+pub fn distinct_countries(stations: &[ApiStation]) -> Vec<String> {
+    let mut countries = stations
+        .iter()
+        .map(|station| station.country.trim())
+        .filter(|country| !country.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    countries.sort();
+    countries.dedup();
+
+    countries
+}
+
+/// The `n` tags the most stations carry, lowercase, with how many carry each.
+/// A tag listed twice by one station counts once. Ties go alphabetically.
+pub fn top_tags(stations: &[ApiStation], n: usize) -> Vec<(String, usize)> {
+    let mut counts = HashMap::<String, usize>::new();
+    for station in stations {
+        let mut tags = station
+            .tags
+            .split(',')
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let mut tags = counts.into_iter().collect::<Vec<_>>();
+    tags.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    tags.truncate(n);
+
+    tags
+}
+
+/// Sorted, deduplicated languages, lowercase. Stations list several as
+/// `english,spanish`, so each one counts on its own.
+pub fn distinct_languages(stations: &[ApiStation]) -> Vec<String> {
+    let mut languages = stations
+        .iter()
+        .flat_map(|station| station_languages(&station.language))
+        .collect::<Vec<_>>();
+    languages.sort();
+    languages.dedup();
+
+    languages
+}
+
+/// The comma separated `languages`, trimmed and lowercase, skipping empty ones.
+pub fn station_languages(languages: &str) -> Vec<String> {
+    languages
+        .split(',')
+        .map(|language| language.trim().to_lowercase())
+        .filter(|language| !language.is_empty())
+        .collect()
+}
+
+/// The `n` stations ranking highest by `key`, best first. Ties go by
+/// `stationuuid` so the order doesn't depend on the input's.
+pub fn top_by<F: Fn(&ApiStation) -> i64>(
+    stations: &[ApiStation],
+    key: F,
+    n: usize,
+) -> Vec<ApiStation> {
+    let mut ranked = stations.iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| {
+        key(b)
+            .cmp(&key(a))
+            .then_with(|| a.stationuuid.cmp(&b.stationuuid))
+    });
+
+    ranked.into_iter().take(n).cloned().collect()
+}
+
+/// The group of stations without a country.
+pub const UNKNOWN_COUNTRY: &str = "Unknown";
+
+/// Stations grouped by country, groups sorted by country and stations within
+/// them by name, both ignoring case. Stations without a country go under
+/// "Unknown", sorted among the others.
+pub fn group_by_country(stations: &[ApiStation]) -> Vec<(String, Vec<ApiStation>)> {
+    let mut groups: Vec<(String, Vec<ApiStation>)> = vec![];
+    for station in stations {
+        let country = match station.country.trim() {
+            "" => UNKNOWN_COUNTRY,
+            country => country,
+        };
+        match groups
+            .iter_mut()
+            .find(|(group, _)| group.eq_ignore_ascii_case(country))
+        {
+            Some((_, members)) => members.push(station.clone()),
+            None => groups.push((country.to_string(), vec![station.clone()])),
+        }
+    }
+    groups.sort_by_key(|(country, _)| country.to_lowercase());
+    for (_, members) in groups.iter_mut() {
+        sort_stations(members, SortKey::Name, true);
+    }
+
+    groups
+}
+
+/// Collapses stations sharing a `url_resolved` into the one with the most votes,
+/// keeping the position of the first occurrence. Stations without a url are
+/// never merged.
+pub fn dedupe_stations(stations: Vec<ApiStation>) -> Vec<ApiStation> {
+    let mut deduped: Vec<ApiStation> = Vec::with_capacity(stations.len());
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for station in stations {
+        let url = station.url_resolved.trim().to_string();
+        if url.is_empty() {
+            deduped.push(station);
+            continue;
+        }
+        match seen.get(&url) {
+            Some(&index) => {
+                if station.votes > deduped[index].votes {
+                    deduped[index] = station;
+                }
+            }
+            None => {
+                seen.insert(url, deduped.len());
+                deduped.push(station);
+            }
+        }
+    }
+
+    deduped
+}
+
+/// A station that isn't from radiobrowser, with only a name and url.
+pub fn local_station(name: &str, url: &str) -> ApiStation {
+    serde_json::from_value(serde_json::json!({
+        "changeuuid": "",
+        "stationuuid": "",
+        "name": name,
+        "url": url,
+        "url_resolved": url,
+        "homepage": "",
+        "favicon": "",
+        "tags": "",
+        "country": "",
+        "countrycode": "",
+        "state": "",
+        "language": "",
+        "votes": 0,
+        "codec": "",
+        "bitrate": 0,
+        "hls": 0,
+        "lastcheckok": 0,
+        "clickcount": 0,
+        "clicktrend": 0,
+    }))
+    .expect("local station is missing an ApiStation field")
+}
+
+/// A random station, preferring ones that passed their last check.
+pub fn pick_random(stations: &[ApiStation]) -> Option<&ApiStation> {
+    pick_random_with(stations, &mut rand::thread_rng())
+}
+
+/// `pick_random` drawing from `rng`, so a seeded rng always picks the same.
+pub fn pick_random_with<'a, R: Rng>(
+    stations: &'a [ApiStation],
+    rng: &mut R,
+) -> Option<&'a ApiStation> {
+    let alive = stations
+        .iter()
+        .filter(|station| station.lastcheckok == 1)
+        .collect::<Vec<_>>();
+    if alive.is_empty() {
+        stations.choose(rng)
+    } else {
+        alive.choose(rng).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::tests::station;
+
+    fn voted(name: &str, url: &str, votes: i32) -> ApiStation {
+        let mut station = local_station(name, url);
+        station.votes = votes;
+        station
+    }
+
+    fn names(stations: &[ApiStation]) -> Vec<&str> {
+        stations
+            .iter()
+            .map(|station| station.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn duplicates_collapse_to_the_most_voted() {
+        let stations = vec![
+            voted("Jazz", "http://jazz", 3),
+            voted("Rock", "http://rock", 1),
+            voted("Jazz HQ", "http://jazz", 9),
+        ];
+
+        let deduped = dedupe_stations(stations);
+
+        assert_eq!(names(&deduped), ["Jazz HQ", "Rock"]);
+    }
+
+    #[test]
+    fn stations_without_a_url_are_not_duplicates() {
+        let stations = vec![voted("A", "", 1), voted("B", "  ", 2), voted("C", "", 3)];
+
+        assert_eq!(names(&dedupe_stations(stations)), ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn same_seed_picks_the_same_station() {
+        let stations = (0..20)
+            .map(|n| local_station(&n.to_string(), "http://radio"))
+            .collect::<Vec<_>>();
+
+        let pick = |seed| {
+            pick_random_with(&stations, &mut StdRng::seed_from_u64(seed))
+                .map(|station| station.name.clone())
+        };
+
+        assert_eq!(pick(7), pick(7));
+        assert!(pick(7).is_some());
+    }
+
+    #[test]
+    fn random_picks_prefer_stations_that_passed_their_check() {
+        let mut stations = vec![
+            local_station("dead", "http://dead"),
+            local_station("alive", "http://alive"),
+            local_station("also dead", "http://also-dead"),
+        ];
+        stations[1].lastcheckok = 1;
+
+        for seed in 0..10 {
+            let pick = pick_random_with(&stations, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(pick.map(|station| station.name.as_str()), Some("alive"));
+        }
+    }
+
+    #[test]
+    fn random_pick_falls_back_to_unchecked_stations_and_empty_lists() {
+        let stations = vec![local_station("dead", "http://dead")];
+        let mut rng = StdRng::seed_from_u64(1);
+
+        assert_eq!(
+            pick_random_with(&stations, &mut rng).map(|station| station.name.as_str()),
+            Some("dead")
+        );
+        assert!(pick_random_with(&[], &mut rng).is_none());
+    }
+
+    fn speaking(languages: &[&str]) -> Vec<ApiStation> {
+        languages
+            .iter()
+            .map(|language| {
+                let mut station = local_station(language, "http://radio");
+                station.language = language.to_string();
+                station
+            })
+            .collect()
+    }
+
+    #[test]
+    fn comma_joined_languages_count_on_their_own() {
+        let stations = speaking(&["English,Spanish", " spanish , german", "english"]);
+
+        assert_eq!(
+            distinct_languages(&stations),
+            ["english", "german", "spanish"]
+        );
+    }
+
+    #[test]
+    fn empty_languages_are_left_out() {
+        let stations = speaking(&["", " , ", "french,"]);
+
+        assert_eq!(distinct_languages(&stations), ["french"]);
+        assert!(station_languages("").is_empty());
+    }
+
+    #[test]
+    fn copies_of_a_station_are_the_same_however_volatile_fields_differ() {
+        let fetched = station("a");
+        let mut refetched = station("a");
+        refetched.clickcount = 900;
+        refetched.votes = 12;
+        refetched.lastcheckok = 1;
+        refetched.url_resolved = "http://moved.example/stream".to_string();
+
+        assert!(same_station(&fetched, &refetched));
+        assert!(!same_station(&fetched, &station("b")));
+    }
+
+    #[test]
+    fn stations_without_a_uuid_compare_by_url() {
+        let a = local_station("Jazz", "http://jazz");
+        let renamed = local_station("Jazz FM", "http://jazz");
+
+        assert!(same_station(&a, &renamed));
+        assert!(!same_station(&a, &local_station("Jazz", "http://rock")));
+        assert!(!same_station(
+            &local_station("a", ""),
+            &local_station("b", "")
+        ));
+        assert!(!same_station(&a, &station("a")));
+    }
+
+    fn in_country(name: &str, country: &str) -> ApiStation {
+        let mut station = local_station(name, "http://radio");
+        station.country = country.to_string();
+        station
+    }
+
+    #[test]
+    fn stations_group_by_country_sorted_by_name() {
+        let stations = [
+            in_country("rock", "Germany"),
+            in_country("Jazz", "Austria"),
+            in_country("blues", "germany"),
+            in_country("Nameless", " "),
+            in_country("Alpha", "Germany"),
+        ];
+
+        let groups = group_by_country(&stations)
+            .into_iter()
+            .map(|(country, members)| (country, names(&members).join(",")))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            groups,
+            [
+                ("Austria".to_string(), "Jazz".to_string()),
+                ("Germany".to_string(), "Alpha,blues,rock".to_string()),
+                ("Unknown".to_string(), "Nameless".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_stations_give_no_groups() {
+        assert!(group_by_country(&[]).is_empty());
+    }
+
+    fn ranked(uuid: &str, votes: i32, clicktrend: i32) -> ApiStation {
+        let mut station = station(uuid);
+        station.votes = votes;
+        station.clicktrend = clicktrend;
+        station
+    }
+
+    fn uuids(stations: &[ApiStation]) -> Vec<&str> {
+        stations
+            .iter()
+            .map(|station| station.stationuuid.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn top_by_votes_and_clicktrend_keeps_the_best_in_order() {
+        let stations = [
+            ranked("a", 10, -3),
+            ranked("b", 50, 2),
+            ranked("c", 30, 9),
+            ranked("d", 5, 4),
+        ];
+
+        assert_eq!(
+            uuids(&top_by(&stations, |station| station.votes as i64, 3)),
+            ["b", "c", "a"]
+        );
+        assert_eq!(
+            uuids(&top_by(&stations, |station| station.clicktrend as i64, 2)),
+            ["c", "d"]
+        );
+    }
+
+    #[test]
+    fn top_by_breaks_ties_by_uuid_whatever_the_input_order() {
+        let stations = [ranked("c", 7, 0), ranked("a", 7, 0), ranked("b", 9, 0)];
+
+        assert_eq!(
+            uuids(&top_by(&stations, |station| station.votes as i64, 10)),
+            ["b", "a", "c"]
+        );
+    }
+
+    #[test]
+    fn station_is_found_by_its_uuid() {
+        let stations = [station("a"), station("b")];
+
+        assert_eq!(find_station_by_uuid(&stations, "b"), Some(&stations[1]));
+    }
+
+    #[test]
+    fn unknown_uuid_finds_nothing() {
+        let stations = [station("a")];
+
+        assert_eq!(find_station_by_uuid(&stations, "gone"), None);
+        assert_eq!(find_station_by_uuid(&[], "a"), None);
+    }
+
+    fn streaming(url: &str, resolved: &str, ssl_error: Option<u8>) -> ApiStation {
+        let mut station = local_station("radio", url);
+        station.url_resolved = resolved.to_string();
+        station.ssl_error = ssl_error;
+        station
+    }
+
+    #[test]
+    fn secure_needs_https_and_no_tls_error() {
+        let https = "https://radio.example/stream";
+        let http = "http://radio.example/stream";
+
+        assert!(is_secure(&streaming(http, https, None)));
+        assert!(is_secure(&streaming(http, https, Some(0))));
+        assert!(!is_secure(&streaming(https, https, Some(1))));
+        assert!(!is_secure(&streaming(https, http, None)));
+        assert!(!is_secure(&streaming(https, http, Some(1))));
+    }
+
+    #[test]
+    fn the_scheme_is_matched_in_any_case() {
+        assert!(is_secure(&streaming("", " HTTPS://radio.example", None)));
+        assert!(!is_secure(&streaming("", "https:/radio.example", None)));
+        assert!(!is_secure(&streaming("", "", None)));
+    }
+
+    #[test]
+    fn the_secure_url_prefers_the_resolved_one() {
+        assert_eq!(
+            secure_stream_url(&streaming("https://a", " https://b ", None)),
+            Some("https://b")
+        );
+        assert_eq!(
+            secure_stream_url(&streaming("https://a", "http://b", None)),
+            Some("https://a")
+        );
+        assert_eq!(
+            secure_stream_url(&streaming("http://a", "http://b", None)),
+            None
+        );
+    }
+
+    fn tagged(tags: &[&str]) -> Vec<ApiStation> {
+        tags.iter()
+            .map(|tags| {
+                let mut station = local_station(tags, "http://radio");
+                station.tags = tags.to_string();
+                station
+            })
+            .collect()
+    }
+
+    fn counted(tags: &[(&str, usize)]) -> Vec<(String, usize)> {
+        tags.iter()
+            .map(|(tag, count)| (tag.to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn the_most_carried_tags_come_first() {
+        let stations = tagged(&["jazz,smooth jazz", "Jazz, Rock", "rock,pop,jazz", "pop", ""]);
+
+        assert_eq!(
+            top_tags(&stations, 3),
+            counted(&[("jazz", 3), ("pop", 2), ("rock", 2)])
+        );
+        assert_eq!(top_tags(&stations, 1), counted(&[("jazz", 3)]));
+    }
+
+    #[test]
+    fn a_tag_repeated_by_one_station_counts_once() {
+        let stations = tagged(&["rock,Rock, rock ,,", "pop"]);
+
+        assert_eq!(top_tags(&stations, 5), counted(&[("pop", 1), ("rock", 1)]));
+    }
+
+    fn resolved(uuid: &str, url_resolved: &str) -> ApiStation {
+        let mut station = station(uuid);
+        station.url_resolved = url_resolved.to_string();
+        station
+    }
+
+    #[test]
+    fn a_refreshed_station_replaces_only_its_own_entry() {
+        let mut stations = vec![resolved("a", "http://old-a"), resolved("b", "http://old-b")];
+
+        assert!(replace_station(
+            &mut stations,
+            &resolved("b", "http://new-b")
+        ));
+
+        assert_eq!(
+            stations,
+            [resolved("a", "http://old-a"), resolved("b", "http://new-b")]
+        );
+    }
+
+    #[test]
+    fn refreshing_a_station_not_listed_changes_nothing() {
+        let mut stations = vec![resolved("a", "http://old-a")];
+
+        assert!(!replace_station(&mut stations, &resolved("z", "http://z")));
+        assert_eq!(stations, [resolved("a", "http://old-a")]);
+    }
+
+    #[test]
+    fn a_station_deleted_upstream_is_removed() {
+        let mut stations = vec![station("a"), station("b"), station("a")];
+
+        assert!(remove_station(&mut stations, "a"));
+        assert_eq!(uuids(&stations), ["b"]);
+        assert!(!remove_station(&mut stations, "a"));
+    }
+
+    #[test]
+    fn countries_are_listed_once_each_in_order() {
+        let stations = [
+            in_country("a", "Germany"),
+            in_country("b", " Austria "),
+            in_country("c", ""),
+            in_country("d", "Germany"),
+            in_country("e", "  "),
+        ];
+
+        assert_eq!(distinct_countries(&stations), ["Austria", "Germany"]);
+    }
+
+    fn sorted(mut stations: Vec<ApiStation>, key: SortKey, ascending: bool) -> Vec<ApiStation> {
+        sort_stations(&mut stations, key, ascending);
+        stations
+    }
+
+    #[test]
+    fn stations_without_a_name_sort_last_either_way() {
+        let stations = vec![
+            local_station("", "http://b"),
+            local_station("beta", "http://beta"),
+            local_station("Alpha", "http://alpha"),
+        ];
+
+        assert_eq!(
+            names(&sorted(stations.clone(), SortKey::Name, true)),
+            ["Alpha", "beta", ""]
+        );
+        assert_eq!(
+            names(&sorted(stations, SortKey::Name, false)),
+            ["beta", "Alpha", ""]
+        );
+    }
+
+    #[test]
+    fn sorting_keeps_the_order_of_equal_stations() {
+        let stations = vec![
+            voted("a", "http://a", 2),
+            voted("b", "http://b", 5),
+            voted("c", "http://c", 2),
+        ];
+
+        assert_eq!(
+            names(&sorted(stations.clone(), SortKey::Votes, true)),
+            ["a", "c", "b"]
+        );
+        assert_eq!(
+            names(&sorted(stations, SortKey::Votes, false)),
+            ["b", "a", "c"]
+        );
+    }
+
+    #[test]
+    fn picking_the_same_key_again_reverses_the_order() {
+        assert_eq!(next_sort_order(None, SortKey::Name), (SortKey::Name, true));
+        assert_eq!(
+            next_sort_order(Some((SortKey::Name, true)), SortKey::Name),
+            (SortKey::Name, false)
+        );
+        assert_eq!(
+            next_sort_order(Some((SortKey::Name, false)), SortKey::Name),
+            (SortKey::Name, true)
+        );
+        assert_eq!(
+            next_sort_order(Some((SortKey::Name, false)), SortKey::Votes),
+            (SortKey::Votes, true)
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use fltk::{
+    browser::Browser,
+    enums::Color,
+    frame::Frame,
+    prelude::{DisplayExt, WidgetExt},
+    text::{SimpleTerminal, TextDisplay},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// The colors a theme gives each part of the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub background: Color,
+    /// Background of the station list and details pane.
+    pub list: Color,
+    pub list_text: Color,
+    pub selection: Color,
+    pub status: Color,
+    pub status_text: Color,
+}
+
+impl Theme {
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette {
+                background: Color::Black,
+                list: Color::from_rgb(32, 32, 32),
+                list_text: Color::from_rgb(230, 230, 230),
+                selection: Color::Magenta,
+                status: Color::Black,
+                status_text: Color::White,
+            },
+            Theme::Light => Palette {
+                background: Color::from_rgb(235, 235, 235),
+                list: Color::White,
+                list_text: Color::Black,
+                selection: Color::Magenta,
+                status: Color::from_rgb(245, 245, 245),
+                status_text: Color::Black,
+            },
+        }
+    }
+
+    pub fn toggled(self) -> Theme {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Dark,
+        }
+    }
+}
+
+/// Recolors the window's main widgets for `theme` and redraws them.
+pub fn apply_theme(
+    theme: &Theme,
+    frame: &mut Frame,
+    browser: &mut Browser,
+    details: &mut TextDisplay,
+    count: &mut Frame,
+    status: &mut SimpleTerminal,
+) {
+    let palette = theme.palette();
+
+    frame.set_color(palette.background);
+    browser.set_color(palette.list);
+    browser.set_selection_color(palette.selection);
+    details.set_color(palette.list);
+    details.set_text_color(palette.list_text);
+    count.set_label_color(palette.status_text);
+    status.set_color(palette.status);
+    status.set_text_color(palette.status_text);
+
+    frame.redraw();
+    browser.redraw();
+    details.redraw();
+    count.redraw();
+    status.redraw();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dark_theme_shows_light_text_on_dark_colors() {
+        let palette = Theme::Dark.palette();
+
+        assert_eq!(palette.background, Color::Black);
+        assert_eq!(palette.list, Color::from_rgb(32, 32, 32));
+        assert_eq!(palette.list_text, Color::from_rgb(230, 230, 230));
+        assert_eq!(palette.status_text, Color::White);
+    }
+
+    #[test]
+    fn light_theme_shows_dark_text_on_light_colors() {
+        let palette = Theme::Light.palette();
+
+        assert_eq!(palette.list, Color::White);
+        assert_eq!(palette.list_text, Color::Black);
+        assert_eq!(palette.status, Color::from_rgb(245, 245, 245));
+        assert_eq!(palette.status_text, Color::Black);
+    }
+
+    #[test]
+    fn status_text_contrasts_with_the_status_bar() {
+        for theme in [Theme::Dark, Theme::Light] {
+            let palette = theme.palette();
+
+            assert_ne!(palette.status_text, palette.status);
+            assert_ne!(palette.list_text, palette.list);
+        }
+    }
+
+    #[test]
+    fn toggling_switches_between_the_two_themes() {
+        assert_eq!(Theme::Dark.toggled(), Theme::Light);
+        assert_eq!(Theme::Light.toggled(), Theme::Dark);
+    }
+}
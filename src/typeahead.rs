@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+/// How long after the last key press typing carries on the same prefix.
+pub const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Letters typed in quick succession, which together name the row to jump to.
+#[derive(Debug)]
+pub struct TypeAheadBuffer {
+    typed: String,
+    last_key: Option<Instant>,
+    timeout: Duration,
+}
+
+impl Default for TypeAheadBuffer {
+    fn default() -> Self {
+        TypeAheadBuffer::new(TYPE_AHEAD_TIMEOUT)
+    }
+}
+
+impl TypeAheadBuffer {
+    pub fn new(timeout: Duration) -> Self {
+        TypeAheadBuffer {
+            typed: String::new(),
+            last_key: None,
+            timeout,
+        }
+    }
+
+    /// Adds `text` typed at `now` and returns the prefix typed so far. After
+    /// more than the timeout without typing, the prefix starts over.
+    pub fn push(&mut self, text: &str, now: Instant) -> &str {
+        let expired = self
+            .last_key
+            .is_none_or(|last| now.saturating_duration_since(last) > self.timeout);
+        if expired {
+            self.typed.clear();
+        }
+        self.typed.push_str(&text.to_lowercase());
+        self.last_key = Some(now);
+
+        &self.typed
+    }
+
+    pub fn clear(&mut self) {
+        self.typed.clear();
+        self.last_key = None;
+    }
+}
+
+/// The index of the first of `names` starting with `prefix`, ignoring case
+/// and leading spaces. Rows without a name, like headers, never match.
+pub fn prefix_match(names: &[Option<String>], prefix: &str) -> Option<usize> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let prefix = prefix.to_lowercase();
+
+    names.iter().position(|name| {
+        name.as_ref()
+            .is_some_and(|name| name.trim_start().to_lowercase().starts_with(&prefix))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(names: &[Option<&str>]) -> Vec<Option<String>> {
+        names.iter().map(|name| name.map(String::from)).collect()
+    }
+
+    #[test]
+    fn the_first_row_starting_with_the_prefix_matches() {
+        let names = rows(&[
+            None,
+            Some("Rock Antenne"),
+            Some(" radio Eins"),
+            Some("Radio X"),
+        ]);
+
+        assert_eq!(prefix_match(&names, "r"), Some(1));
+        assert_eq!(prefix_match(&names, "RA"), Some(2));
+        assert_eq!(prefix_match(&names, "radio x"), Some(3));
+    }
+
+    #[test]
+    fn no_row_matches_an_unknown_or_empty_prefix() {
+        let names = rows(&[None, Some("Rock Antenne")]);
+
+        assert_eq!(prefix_match(&names, "jazz"), None);
+        assert_eq!(prefix_match(&names, ""), None);
+    }
+
+    #[test]
+    fn quick_keys_add_up_to_one_prefix() {
+        let mut buffer = TypeAheadBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        buffer.push("R", start);
+        buffer.push("a", start + Duration::from_millis(300));
+
+        assert_eq!(buffer.push("d", start + Duration::from_millis(800)), "rad");
+    }
+
+    #[test]
+    fn a_pause_longer_than_the_timeout_starts_over() {
+        let mut buffer = TypeAheadBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        buffer.push("r", start);
+
+        assert_eq!(buffer.push("j", start + Duration::from_millis(501)), "j");
+    }
+
+    #[test]
+    fn clearing_starts_over_at_once() {
+        let mut buffer = TypeAheadBuffer::default();
+        let start = Instant::now();
+
+        buffer.push("r", start);
+        buffer.clear();
+
+        assert_eq!(buffer.push("j", start), "j");
+    }
+}
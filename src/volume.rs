@@ -0,0 +1,96 @@
+use std::{fs, io};
+
+use crate::config::Config;
+
+/// Where older versions kept the volume, before it moved into the config.
+const LEGACY_VOLUME_PATH: &str = "volume.conf";
+
+/// Reads a stored volume, clamped to 0..=100 in case it was edited by hand.
+pub fn parse_volume(data: &str) -> Option<i32> {
+    data.trim()
+        .parse::<i32>()
+        .ok()
+        .map(|volume| volume.clamp(0, 100))
+}
+
+/// Moves a volume left in `volume.conf` into `config` and saves it there,
+/// then removes the file, so the volume is only ever kept in the config.
+pub fn migrate_legacy_volume(config: &mut Config) -> io::Result<()> {
+    let Ok(data) = fs::read_to_string(LEGACY_VOLUME_PATH) else {
+        return Ok(());
+    };
+    if let Some(volume) = parse_volume(&data) {
+        config.volume = volume;
+        config.save()?;
+    }
+
+    fs::remove_file(LEGACY_VOLUME_PATH)
+}
+
+/// Whether output is muted, holding the level to go back to on unmute.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MuteState {
+    #[default]
+    Unmuted,
+    Muted {
+        previous: i32,
+    },
+}
+
+impl MuteState {
+    /// Mutes from `volume`, or unmutes back to the level muted from.
+    /// Returns the new state along with the volume to output.
+    pub fn toggle(self, volume: i32) -> (MuteState, i32) {
+        match self {
+            MuteState::Unmuted => (MuteState::Muted { previous: volume }, 0),
+            MuteState::Muted { previous } => (MuteState::Unmuted, previous),
+        }
+    }
+
+    /// Picking a new level always unmutes at that level.
+    pub fn volume_changed(self, volume: i32) -> (MuteState, i32) {
+        (MuteState::Unmuted, volume)
+    }
+
+    pub fn is_muted(self) -> bool {
+        matches!(self, MuteState::Muted { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_volume_is_clamped_on_load() {
+        assert_eq!(parse_volume("150"), Some(100));
+        assert_eq!(parse_volume("-20"), Some(0));
+        assert_eq!(parse_volume(" 65\n"), Some(65));
+    }
+
+    #[test]
+    fn unreadable_volume_is_ignored() {
+        assert_eq!(parse_volume(""), None);
+        assert_eq!(parse_volume("loud"), None);
+        assert_eq!(parse_volume("99999999999"), None);
+    }
+
+    #[test]
+    fn unmuting_restores_the_level_muted_from() {
+        let (state, volume) = MuteState::Unmuted.toggle(70);
+        assert_eq!((state, volume), (MuteState::Muted { previous: 70 }, 0));
+        assert!(state.is_muted());
+
+        assert_eq!(state.toggle(volume), (MuteState::Unmuted, 70));
+    }
+
+    #[test]
+    fn changing_the_volume_while_muted_unmutes_at_the_new_level() {
+        let (state, _) = MuteState::Unmuted.toggle(70);
+
+        let (state, volume) = state.volume_changed(40);
+        assert_eq!((state, volume), (MuteState::Unmuted, 40));
+
+        assert_eq!(state.toggle(volume), (MuteState::Muted { previous: 40 }, 0));
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use async_std::task;
+use radiobrowser::RadioBrowserAPI;
+
+/// Stations voted for this session. radiobrowser only counts one vote per
+/// station every few minutes anyway, so asking again is just noise.
+#[derive(Debug, Default)]
+pub struct VoteTracker {
+    voted: HashSet<String>,
+}
+
+impl VoteTracker {
+    /// Claims the vote for `uuid`, returning false if it was already used.
+    pub fn try_vote(&mut self, uuid: &str) -> bool {
+        self.voted.insert(uuid.to_string())
+    }
+
+    /// Gives the vote for `uuid` back, for when sending it failed.
+    pub fn release(&mut self, uuid: &str) {
+        self.voted.remove(uuid);
+    }
+}
+
+/// Votes for the station with `uuid`. Blocks until radiobrowser answers, and
+/// fails with its message if it refused the vote.
+pub fn vote_for_station(uuid: &str) -> Result<(), String> {
+    let result = task::block_on(async { RadioBrowserAPI::new().await?.station_vote(uuid).await })
+        .map_err(|why| why.to_string())?;
+
+    if result.ok {
+        Ok(())
+    } else {
+        Err(result.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_station_gets_one_vote_per_session() {
+        let mut votes = VoteTracker::default();
+
+        assert!(votes.try_vote("a"));
+        assert!(!votes.try_vote("a"));
+        assert!(votes.try_vote("b"));
+    }
+
+    #[test]
+    fn released_vote_can_be_sent_again() {
+        let mut votes = VoteTracker::default();
+        votes.try_vote("a");
+
+        votes.release("a");
+
+        assert!(votes.try_vote("a"));
+    }
+}
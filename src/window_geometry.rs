@@ -0,0 +1,120 @@
+use std::{fs, io, path::Path};
+
+use fltk::{app, prelude::WidgetExt, window::Window};
+
+use crate::write_data_to_file;
+
+pub const WINDOW_CONF_PATH: &str = "window.conf";
+
+/// Position and size of a window or screen, as `x y w h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Geometry {
+    fn parse(data: &str) -> Option<Geometry> {
+        let values = data
+            .split_whitespace()
+            .map(|value| value.parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match values[..] {
+            [x, y, w, h] if w > 0 && h > 0 => Some(Geometry { x, y, w, h }),
+            _ => None,
+        }
+    }
+}
+
+/// Shrinks `window` to fit on `screen` and moves it fully onto it.
+pub fn clamp_to_screen(window: Geometry, screen: Geometry) -> Geometry {
+    let w = window.w.min(screen.w);
+    let h = window.h.min(screen.h);
+
+    Geometry {
+        x: window.x.clamp(screen.x, screen.x + screen.w - w),
+        y: window.y.clamp(screen.y, screen.y + screen.h - h),
+        w,
+        h,
+    }
+}
+
+pub fn save_window_geometry(window: &Window) -> io::Result<()> {
+    write_data_to_file(
+        Path::new(WINDOW_CONF_PATH),
+        &format!(
+            "{} {} {} {}",
+            window.x(),
+            window.y(),
+            window.width(),
+            window.height()
+        ),
+    )
+}
+
+/// Applies the saved geometry, if any, clamped to the screen it was on or
+/// the nearest one if that screen is gone.
+pub fn restore_window_geometry(window: &mut Window) {
+    let Some(saved) = fs::read_to_string(WINDOW_CONF_PATH)
+        .ok()
+        .and_then(|data| Geometry::parse(&data))
+    else {
+        return;
+    };
+    let (x, y, w, h) = app::screen_xywh(app::screen_num(saved.x, saved.y));
+    let geometry = clamp_to_screen(saved, Geometry { x, y, w, h });
+
+    window.resize(geometry.x, geometry.y, geometry.w, geometry.h);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Geometry = Geometry {
+        x: 0,
+        y: 0,
+        w: 1920,
+        h: 1080,
+    };
+
+    fn geometry(x: i32, y: i32, w: i32, h: i32) -> Geometry {
+        Geometry { x, y, w, h }
+    }
+
+    #[test]
+    fn window_on_screen_stays_put() {
+        let window = geometry(100, 50, 640, 480);
+
+        assert_eq!(clamp_to_screen(window, SCREEN), window);
+    }
+
+    #[test]
+    fn window_off_a_disconnected_monitor_moves_onto_the_screen() {
+        assert_eq!(
+            clamp_to_screen(geometry(2500, -300, 640, 480), SCREEN),
+            geometry(1280, 0, 640, 480)
+        );
+    }
+
+    #[test]
+    fn window_larger_than_the_screen_shrinks_to_fit() {
+        assert_eq!(
+            clamp_to_screen(geometry(-10, 20, 2560, 1440), SCREEN),
+            SCREEN
+        );
+    }
+
+    #[test]
+    fn saved_geometry_parses_only_with_four_values_and_a_size() {
+        assert_eq!(
+            Geometry::parse("10 20 640 480\n"),
+            Some(geometry(10, 20, 640, 480))
+        );
+        assert_eq!(Geometry::parse("10 20 640"), None);
+        assert_eq!(Geometry::parse("10 20 0 480"), None);
+        assert_eq!(Geometry::parse("garbage"), None);
+    }
+}